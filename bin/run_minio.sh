@@ -1,5 +1,6 @@
 #/bin/bash
 
+# See also: tlessctl s3 server start|stop|status
 docker run \
     --rm -it -d \
     --net host \
@@ -1,10 +1,15 @@
+use crate::env::Env;
 use crate::tasks::dag::Dag;
 use crate::tasks::docker::{Docker, DockerContainer};
-use crate::tasks::eval::{Eval, EvalExperiment, EvalRunArgs};
-use crate::tasks::s3::S3;
+use crate::tasks::eval::{Eval, EvalExperiment, EvalRunArgs, ExperimentSpec};
+use crate::tasks::s3::{DatasetManifest, S3OutputFormat, S3PresignMethod, S3ServerMode, S3};
+use crate::tasks::storage;
 use crate::tasks::ubench::{MicroBenchmarks, Ubench, UbenchRunArgs};
+use crate::tasks::workflows::{AvailableWorkflow, WorkflowTrigger, Workflows};
 use clap::{Parser, Subcommand};
 use env_logger;
+use std::path::Path;
+use std::time;
 
 pub mod env;
 pub mod tasks;
@@ -46,6 +51,47 @@ enum Command {
         #[command(subcommand)]
         s3_command: S3Command,
     },
+    /// Interact with the workflows deployed on Knative
+    Workflows {
+        #[command(subcommand)]
+        workflows_command: WorkflowsCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum WorkflowsCommand {
+    /// Send the events that start one execution of a workflow
+    Trigger {
+        workflow: AvailableWorkflow,
+        /// URL of the Knative in-memory channel dispatcher (e.g. after
+        /// port-forwarding to svc/imc-dispatcher in knative-eventing)
+        #[arg(long, default_value = "http://localhost:8080")]
+        trigger_url: String,
+        /// Fan-out for workflows that support scaling-up (0 is the default)
+        #[arg(long, default_value = "0")]
+        scale_up_factor: u32,
+        /// Number of entries (e.g. images) in the input, as uploaded by the
+        /// dataset-size experiment (0 is the default input)
+        #[arg(long, default_value = "0")]
+        input_size: u32,
+        /// Run id to keep the outputs of the execution under (default: a
+        /// new random one)
+        #[arg(long)]
+        run_id: Option<String>,
+    },
+    /// Upload the DAGs and datasets of all workflows
+    UploadState {
+        /// Storage backend to upload to (s3://<bucket> or file://<path>)
+        #[arg(long, default_value = "s3://tless")]
+        storage: String,
+        /// Remove everything in the storage backend first
+        #[arg(long)]
+        clean: bool,
+        /// Envelope-encrypt the datasets (s3:// only). Workflow functions
+        /// can not decrypt them yet, so workflows will fail to run on them
+        #[arg(long)]
+        encrypt: bool,
+    },
 }
 
 #[derive(Debug, Subcommand)]
@@ -55,6 +101,9 @@ enum DagCommand {
         name: String,
         /// Path to the YAML file describing the workflow
         yaml_path: String,
+        /// Storage backend to upload to (s3://<bucket> or file://<path>)
+        #[arg(long, default_value = "s3://tless")]
+        storage: String,
     },
 }
 
@@ -96,12 +145,44 @@ enum EvalCommand {
         #[command(subcommand)]
         eval_sub_command: EvalSubCommand,
     },
+    /// Run the experiment described in a YAML specification file
+    Run {
+        #[arg(long)]
+        spec: String,
+        /// Resume an interrupted run of this spec from its checkpoint
+        #[arg(long)]
+        resume: bool,
+    },
     /// Evaluate the latency when scaling-up the number of functions in the
     /// workflow
     ScaleUpLatency {
         #[command(subcommand)]
         eval_sub_command: EvalSubCommand,
     },
+    /// Evaluate the throughput, and latency under load, when invoking
+    /// workflows at increasing concurrency levels
+    Throughput {
+        #[command(subcommand)]
+        eval_sub_command: EvalSubCommand,
+    },
+    /// Evaluate the latency of each run, and the interference between runs,
+    /// when starting several runs of the same workflow at the same time
+    ConcurrentRuns {
+        #[command(subcommand)]
+        eval_sub_command: EvalSubCommand,
+    },
+    /// Break down the cold start of each workflow function into phases,
+    /// from scheduling its pod to its handler starting
+    ColdStartBreakdown {
+        #[command(subcommand)]
+        eval_sub_command: EvalSubCommand,
+    },
+    /// Evaluate the end-to-end latency of each workflow as we increase the
+    /// size of its input
+    DatasetSize {
+        #[command(subcommand)]
+        eval_sub_command: EvalSubCommand,
+    },
 }
 
 #[derive(Debug, Subcommand)]
@@ -135,6 +216,20 @@ enum S3Command {
         #[arg(long)]
         prefix: String,
     },
+    /// Show object counts and total size per prefix in an S3 bucket
+    Du {
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
+        /// Only account for keys under this prefix
+        #[arg(long)]
+        prefix: Option<String>,
+        /// Number of path components to group keys by
+        #[arg(long, default_value = "2")]
+        depth: usize,
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Download a directory from S3 to the host
     GetDir {
         #[arg(long, default_value = "tless")]
@@ -143,16 +238,62 @@ enum S3Command {
         s3_path: String,
         #[arg(long)]
         host_path: String,
+        /// Decrypt envelope-encrypted objects after downloading them
+        #[arg(long)]
+        decrypt: bool,
     },
-    /// Clear a sub-tree in an S3 bucket indicated by a prefix
+    /// Fetch an object from S3, and print it or write it to a file
     GetKey {
         #[arg(long, default_value = "tless")]
         bucket_name: String,
         #[arg(long)]
         key: String,
+        /// Stream the object to this host path instead of printing it
+        #[arg(long)]
+        out: Option<String>,
+        /// How to print the object to stdout (ignored with --out)
+        #[arg(long, value_enum, default_value = "raw")]
+        format: S3OutputFormat,
+        /// Decrypt an envelope-encrypted object
+        #[arg(long)]
+        decrypt: bool,
+    },
+    /// Manage lifecycle (expiry) rules and versioning for a bucket
+    Lifecycle {
+        #[command(subcommand)]
+        lifecycle_command: S3LifecycleCommand,
     },
     /// List all buckets in an S3 server
     ListBuckets {},
+    /// Generate a URL to get or put an object without MinIO credentials
+    Presign {
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
+        #[arg(long)]
+        key: String,
+        /// How long the URL is valid for (e.g. 3600, 30m, 12h, or 7d)
+        #[arg(long, default_value = "1h")]
+        expires: String,
+        #[arg(long, value_enum, default_value = "get")]
+        method: S3PresignMethod,
+    },
+    /// Manage a local MinIO server for development
+    Server {
+        #[command(subcommand)]
+        server_command: S3ServerCommand,
+    },
+    /// Save and restore the state of a prefix using server-side copies
+    Snapshot {
+        #[command(subcommand)]
+        snapshot_command: S3SnapshotCommand,
+    },
+    /// Show the size, ETag, last-modified date and metadata of an object
+    Stat {
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
+        #[arg(long)]
+        key: String,
+    },
     /// List all keys in an S3 bucket
     ListKeys {
         /// Name of the bucket
@@ -162,7 +303,7 @@ enum S3Command {
         #[arg(long)]
         prefix: Option<String>,
     },
-    /// Upload a directory to S3
+    /// Upload a directory to S3, and write a manifest of its content
     UploadDir {
         /// Name of the bucket to store files in
         #[arg(long, default_value = "tless")]
@@ -173,8 +314,12 @@ enum S3Command {
         /// Path in the S3 server to store files to
         #[arg(long)]
         s3_path: String,
+        /// Envelope-encrypt each file with a fresh data key wrapped by the
+        /// user key (TLESS_USER_KEY)
+        #[arg(long)]
+        encrypt: bool,
     },
-    /// Upload an object to S3
+    /// Upload an object to S3, and write a manifest of its content
     UploadKey {
         /// Name of the bucket to store files in
         #[arg(long, default_value = "tless")]
@@ -185,6 +330,132 @@ enum S3Command {
         /// Path in the S3 server for the uploaded file
         #[arg(long)]
         s3_path: String,
+        /// Envelope-encrypt the file with a fresh data key wrapped by the
+        /// user key (TLESS_USER_KEY)
+        #[arg(long)]
+        encrypt: bool,
+    },
+    /// Check the objects under a dataset prefix against its manifest, and
+    /// report missing, extra, or corrupted files
+    Verify {
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
+        #[arg(long)]
+        prefix: String,
+    },
+    /// Print keys created or deleted under a prefix as it happens
+    Watch {
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
+        #[arg(long, default_value = "")]
+        prefix: String,
+        /// Polling interval, if bucket notifications are not available
+        #[arg(long, default_value = "1000")]
+        poll_interval_ms: u64,
+        /// Poll for changes instead of listening to bucket notifications
+        #[arg(long)]
+        no_notifications: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum S3LifecycleCommand {
+    /// Install the default retention rules for run artefacts (exec-tokens
+    /// and outputs), and enable versioning to protect DAGs and crypto state
+    ApplyDefaults {
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
+        /// Days after which exec tokens expire
+        #[arg(long, default_value = "1")]
+        exec_tokens_days: usize,
+        /// Days after which workflow outputs expire
+        #[arg(long, default_value = "1")]
+        outputs_days: usize,
+        /// Days to keep old versions of overwritten or removed objects
+        #[arg(long, default_value = "30")]
+        noncurrent_days: usize,
+    },
+    /// Remove all the rules installed by tlessctl
+    Clear {
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
+    },
+    /// Remove the rule for a prefix
+    RemoveRule {
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
+        #[arg(long)]
+        prefix: String,
+    },
+    /// Expire objects under a prefix after a number of days (S3 lifecycle
+    /// rules have day granularity)
+    SetExpiry {
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
+        #[arg(long)]
+        prefix: String,
+        #[arg(long)]
+        days: usize,
+        /// Days to keep non-current versions, if versioning is enabled
+        #[arg(long)]
+        noncurrent_days: Option<usize>,
+    },
+    /// Show the versioning status and lifecycle rules of a bucket
+    Show {
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum S3ServerCommand {
+    /// Start MinIO, wait for it to be ready, and create the tless bucket.
+    /// Prints the environment to use it: eval "$(tlessctl s3 server start)"
+    Start {
+        #[arg(long, value_enum, default_value = "container")]
+        mode: S3ServerMode,
+        /// Directory to store objects in (default: ./dev/minio/data)
+        #[arg(long)]
+        data_dir: Option<String>,
+        #[arg(long, default_value = "9000")]
+        port: u16,
+        #[arg(long, default_value = "9001")]
+        console_port: u16,
+    },
+    /// Show whether the local MinIO server is up, and where
+    Status {},
+    /// Stop the local MinIO server (objects in the data directory are kept)
+    Stop {},
+}
+
+#[derive(Debug, Subcommand)]
+enum S3SnapshotCommand {
+    /// Remove a snapshot
+    Delete {
+        name: String,
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
+    },
+    /// List all snapshots in a bucket
+    List {
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
+    },
+    /// Copy the objects in a snapshot back to the prefix they came from
+    Restore {
+        name: String,
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
+        /// Remove all objects under the prefix before restoring
+        #[arg(long)]
+        clean: bool,
+    },
+    /// Copy all objects under a prefix (e.g. a workflow) to a new snapshot
+    Save {
+        prefix: String,
+        name: String,
+        #[arg(long, default_value = "tless")]
+        bucket_name: String,
     },
 }
 
@@ -205,8 +476,12 @@ async fn main() {
 
     match &cli.task {
         Command::Dag { dag_command } => match dag_command {
-            DagCommand::Upload { name, yaml_path } => {
-                Dag::upload(name, yaml_path).await;
+            DagCommand::Upload {
+                name,
+                yaml_path,
+                storage,
+            } => {
+                Dag::upload(storage::from_url(storage).as_ref(), name, yaml_path).await;
             }
         },
         Command::Docker { docker_command } => match docker_command {
@@ -243,6 +518,10 @@ async fn main() {
                     Eval::plot(&EvalExperiment::E2eLatencyCold);
                 }
             },
+            EvalCommand::Run { spec, resume } => {
+                let (exp, run_args) = ExperimentSpec::load(spec, *resume);
+                Eval::run(&exp, &run_args).await;
+            }
             EvalCommand::ScaleUpLatency { eval_sub_command } => match eval_sub_command {
                 EvalSubCommand::Run(run_args) => {
                     Eval::run(&EvalExperiment::ScaleUpLatency, run_args).await;
@@ -251,6 +530,38 @@ async fn main() {
                     Eval::plot(&EvalExperiment::ScaleUpLatency);
                 }
             },
+            EvalCommand::Throughput { eval_sub_command } => match eval_sub_command {
+                EvalSubCommand::Run(run_args) => {
+                    Eval::run(&EvalExperiment::Throughput, run_args).await;
+                }
+                EvalSubCommand::Plot {} => {
+                    Eval::plot(&EvalExperiment::Throughput);
+                }
+            },
+            EvalCommand::ConcurrentRuns { eval_sub_command } => match eval_sub_command {
+                EvalSubCommand::Run(run_args) => {
+                    Eval::run(&EvalExperiment::ConcurrentRuns, run_args).await;
+                }
+                EvalSubCommand::Plot {} => {
+                    Eval::plot(&EvalExperiment::ConcurrentRuns);
+                }
+            },
+            EvalCommand::ColdStartBreakdown { eval_sub_command } => match eval_sub_command {
+                EvalSubCommand::Run(run_args) => {
+                    Eval::run(&EvalExperiment::ColdStartBreakdown, run_args).await;
+                }
+                EvalSubCommand::Plot {} => {
+                    Eval::plot(&EvalExperiment::ColdStartBreakdown);
+                }
+            },
+            EvalCommand::DatasetSize { eval_sub_command } => match eval_sub_command {
+                EvalSubCommand::Run(run_args) => {
+                    Eval::run(&EvalExperiment::DatasetSize, run_args).await;
+                }
+                EvalSubCommand::Plot {} => {
+                    Eval::plot(&EvalExperiment::DatasetSize);
+                }
+            },
         },
         Command::Ubench { ubench_command } => match ubench_command {
             UbenchCommand::VerifyEdag { ubench_sub_command } => match ubench_sub_command {
@@ -273,20 +584,125 @@ async fn main() {
             } => {
                 S3::clear_dir(bucket_name.to_string(), prefix.to_string()).await;
             }
+            S3Command::Du {
+                bucket_name,
+                prefix,
+                depth,
+                json,
+            } => {
+                S3::du(bucket_name, prefix, *depth, *json).await;
+            }
             S3Command::GetDir {
                 bucket_name,
                 s3_path,
                 host_path,
+                decrypt,
             } => {
-                S3::get_dir(bucket_name, s3_path, host_path).await;
-            }
-            S3Command::GetKey { bucket_name, key } => {
-                let key_contents = S3::get_key(bucket_name, key).await;
-                println!("{key_contents}");
+                S3::get_dir(bucket_name, s3_path, host_path, *decrypt).await;
             }
+            S3Command::GetKey {
+                bucket_name,
+                key,
+                out,
+                format,
+                decrypt,
+            } => match out {
+                Some(host_path) => {
+                    S3::get_key_to_file(bucket_name, key, host_path, *decrypt).await;
+                }
+                None => {
+                    S3::get_key_to_stdout(bucket_name, key, format, *decrypt).await;
+                }
+            },
+            S3Command::Lifecycle { lifecycle_command } => match lifecycle_command {
+                S3LifecycleCommand::ApplyDefaults {
+                    bucket_name,
+                    exec_tokens_days,
+                    outputs_days,
+                    noncurrent_days,
+                } => {
+                    S3::lifecycle_apply_defaults(
+                        bucket_name,
+                        *exec_tokens_days,
+                        *outputs_days,
+                        *noncurrent_days,
+                    )
+                    .await;
+                }
+                S3LifecycleCommand::Clear { bucket_name } => {
+                    S3::lifecycle_clear(bucket_name).await;
+                }
+                S3LifecycleCommand::RemoveRule {
+                    bucket_name,
+                    prefix,
+                } => {
+                    S3::lifecycle_remove_rule(bucket_name, prefix).await;
+                }
+                S3LifecycleCommand::SetExpiry {
+                    bucket_name,
+                    prefix,
+                    days,
+                    noncurrent_days,
+                } => {
+                    S3::lifecycle_set_expiry(bucket_name, prefix, *days, *noncurrent_days).await;
+                }
+                S3LifecycleCommand::Show { bucket_name } => {
+                    S3::lifecycle_show(bucket_name).await;
+                }
+            },
             S3Command::ListBuckets {} => {
                 S3::list_buckets().await;
             }
+            S3Command::Presign {
+                bucket_name,
+                key,
+                expires,
+                method,
+            } => {
+                let url = S3::presign(bucket_name, key, S3::parse_expiry(expires), method).await;
+                println!("{url}");
+            }
+            S3Command::Server { server_command } => match server_command {
+                S3ServerCommand::Start {
+                    mode,
+                    data_dir,
+                    port,
+                    console_port,
+                } => {
+                    S3::server_start(mode, data_dir, *port, *console_port).await;
+                }
+                S3ServerCommand::Status {} => {
+                    S3::server_status().await;
+                }
+                S3ServerCommand::Stop {} => {
+                    S3::server_stop();
+                }
+            },
+            S3Command::Snapshot { snapshot_command } => match snapshot_command {
+                S3SnapshotCommand::Delete { name, bucket_name } => {
+                    S3::snapshot_delete(bucket_name, name).await;
+                }
+                S3SnapshotCommand::List { bucket_name } => {
+                    S3::snapshot_list(bucket_name).await;
+                }
+                S3SnapshotCommand::Restore {
+                    name,
+                    bucket_name,
+                    clean,
+                } => {
+                    S3::snapshot_restore(bucket_name, name, *clean).await;
+                }
+                S3SnapshotCommand::Save {
+                    prefix,
+                    name,
+                    bucket_name,
+                } => {
+                    S3::snapshot_save(bucket_name, prefix, name).await;
+                }
+            },
+            S3Command::Stat { bucket_name, key } => {
+                S3::stat_key(bucket_name, key).await;
+            }
             S3Command::ListKeys {
                 bucket_name,
                 prefix,
@@ -297,20 +713,90 @@ async fn main() {
                 bucket_name,
                 host_path,
                 s3_path,
+                encrypt,
             } => {
                 S3::upload_dir(
                     bucket_name.to_string(),
                     host_path.to_string(),
                     s3_path.to_string(),
+                    *encrypt,
                 )
                 .await;
+
+                let storage = storage::from_url(&format!("s3://{bucket_name}"));
+                DatasetManifest::from_host_path(Path::new(host_path), s3_path, *encrypt)
+                    .upload(storage.as_ref())
+                    .await;
             }
             S3Command::UploadKey {
                 bucket_name,
                 host_path,
                 s3_path,
+                encrypt,
+            } => {
+                S3::upload_file(bucket_name, host_path, s3_path, *encrypt).await;
+
+                let storage = storage::from_url(&format!("s3://{bucket_name}"));
+                DatasetManifest::from_host_path(Path::new(host_path), s3_path, *encrypt)
+                    .upload(storage.as_ref())
+                    .await;
+            }
+            S3Command::Verify {
+                bucket_name,
+                prefix,
+            } => {
+                if !S3::verify(bucket_name, prefix).await.is_ok() {
+                    std::process::exit(1);
+                }
+            }
+            S3Command::Watch {
+                bucket_name,
+                prefix,
+                poll_interval_ms,
+                no_notifications,
+            } => {
+                S3::watch(
+                    bucket_name,
+                    prefix,
+                    time::Duration::from_millis(*poll_interval_ms),
+                    !no_notifications,
+                )
+                .await;
+            }
+        },
+        Command::Workflows { workflows_command } => match workflows_command {
+            WorkflowsCommand::Trigger {
+                workflow,
+                trigger_url,
+                scale_up_factor,
+                input_size,
+                run_id,
             } => {
-                S3::upload_file(bucket_name, host_path, s3_path).await;
+                let run_id = run_id.clone().unwrap_or_else(Workflows::new_run_id);
+                let trigger = WorkflowTrigger::new(Some(trigger_url.to_string()));
+                let result = trigger
+                    .trigger(workflow, *scale_up_factor, *input_size, &run_id)
+                    .await;
+                match result {
+                    Ok(run_magic) => println!(
+                        "{workflow}: triggered (run id: {run_id}, run magic: {run_magic}, output: {})",
+                        Workflows::get_output_key(workflow, &run_id)
+                    ),
+                    Err(e) => panic!("tlessctl(workflows): failed to trigger {workflow}: {e}"),
+                }
+            }
+            WorkflowsCommand::UploadState {
+                storage,
+                clean,
+                encrypt,
+            } => {
+                Workflows::upload_state(
+                    storage::from_url(storage).as_ref(),
+                    &Env::proj_root(),
+                    *clean,
+                    *encrypt,
+                )
+                .await;
             }
         },
     }
@@ -1,7 +1,10 @@
 // List the different tasks here
+pub mod cluster;
+pub mod crypto;
 pub mod dag;
 pub mod docker;
 pub mod eval;
 pub mod s3;
+pub mod storage;
 pub mod ubench;
 pub mod workflows;
@@ -0,0 +1,1226 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use k8s_openapi::api::core::v1::{Node, Pod, Service};
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use kube::api::{Api, DeleteParams, DynamicObject, ListParams, LogParams, Patch, PatchParams};
+use kube::core::{ApiResource, GroupVersionKind};
+use kube::discovery::{Discovery, Scope};
+use kube::{Client, ResourceExt};
+use log::{debug, warn};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::{fmt, time};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::{JoinHandle, JoinSet};
+
+// Field manager we use for server-side apply
+const FIELD_MANAGER: &str = "tlessctl";
+// Interval between checks when we wait for something in the cluster
+const POLL_INTERVAL: time::Duration = time::Duration::from_secs(2);
+// How long pods of fake Knative Services stay up after their last event
+const FAKE_SCALE_TO_ZERO_AFTER: time::Duration = time::Duration::from_secs(5);
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClusterBackend {
+    #[default]
+    Kube,
+    Fake,
+}
+
+impl fmt::Display for ClusterBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClusterBackend::Kube => write!(f, "kube"),
+            ClusterBackend::Fake => write!(f, "fake"),
+        }
+    }
+}
+
+/// A pod, and whether all its containers are ready to serve
+#[derive(Clone, Debug)]
+pub struct PodStatus {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub ready: bool,
+}
+
+/// Resource usage of a pod (or a process). CPU time and network counters
+/// are cumulative since it started, and memory is its current working set
+#[derive(Clone, Debug, Default)]
+pub struct ResourceUsage {
+    pub name: String,
+    pub cpu_secs: f64,
+    pub memory_bytes: u64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// When a pod was created, and when each of its conditions (e.g.
+/// PodScheduled or Ready) last became true. Conditions that are not true
+/// are left out
+#[derive(Clone, Debug, Default)]
+pub struct PodTimeline {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub created: Option<DateTime<Utc>>,
+    pub conditions: BTreeMap<String, DateTime<Utc>>,
+}
+
+/// A Knative object that runs a workflow function (i.e. a Service or a
+/// JobSink), and that must be ready before we trigger the workflow
+#[derive(Clone, Debug)]
+pub struct Workload {
+    pub gvk: GroupVersionKind,
+    pub namespace: String,
+    pub name: String,
+    /// Labels of the pods it creates
+    pub pod_labels: BTreeMap<String, String>,
+}
+
+impl fmt::Display for Workload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels: Vec<String> = self
+            .pod_labels
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        write!(
+            f,
+            "{}/{} ({})",
+            self.gvk.kind.to_lowercase(),
+            self.name,
+            labels.join(",")
+        )
+    }
+}
+
+/// A local port that forwards to a service in the cluster. Forwarding stops
+/// when we drop it
+#[derive(Debug)]
+pub struct PortForward {
+    /// URL that reaches the service from outside the cluster
+    pub url: String,
+    task: JoinHandle<()>,
+}
+
+impl PortForward {
+    /// Listen on a free local port, and handle each connection to it in its
+    /// own task. Stopping the port-forward stops all of them
+    async fn start<F, Fut>(handle_connection: F) -> Self
+    where
+        F: Fn(TcpStream) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .unwrap_or_else(|e| panic!("tlessctl(cluster): failed to bind local port: {e}"));
+        let port = listener.local_addr().unwrap().port();
+
+        let task = tokio::spawn(async move {
+            let mut connections = JoinSet::new();
+            loop {
+                match listener.accept().await {
+                    Ok((connection, _)) => {
+                        connections.spawn(handle_connection(connection));
+                    }
+                    Err(e) => warn!("tlessctl(cluster): failed to accept connection: {e}"),
+                }
+
+                while connections.try_join_next().is_some() {}
+            }
+        });
+
+        Self {
+            url: format!("http://127.0.0.1:{port}"),
+            task,
+        }
+    }
+}
+
+impl Drop for PortForward {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Operations we need from a Kubernetes cluster to deploy and tear-down
+/// workflows. Manifests are multi-document YAML strings
+#[async_trait(?Send)]
+pub trait Cluster {
+    /// Create, or update, all the objects in a manifest
+    async fn apply(&self, manifest: &str);
+
+    /// Delete all the objects in a manifest, and wait for them (and their
+    /// dependents) to be gone
+    async fn delete(&self, manifest: &str);
+
+    /// List the pods in a namespace that match a label selector (e.g.
+    /// 'app=foo,tier!=db'). An empty selector matches all pods
+    async fn list_pods(&self, namespace: &str, label_selector: &str) -> Vec<PodStatus>;
+
+    async fn get_service_cluster_ip(&self, namespace: &str, name: &str) -> Option<String>;
+
+    /// Resource usage of the pods in a namespace that match a label selector
+    async fn get_pod_usage(&self, namespace: &str, label_selector: &str) -> Vec<ResourceUsage>;
+
+    /// Creation and condition transition times of the pods in a namespace
+    /// that match a label selector
+    async fn get_pod_timelines(&self, namespace: &str, label_selector: &str) -> Vec<PodTimeline>;
+
+    /// Log lines of the main container of a pod (i.e. not a sidecar), with
+    /// the time each one was written. Returns None if the pod is gone, or
+    /// its container has not started yet
+    async fn get_pod_logs(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> Option<Vec<(DateTime<Utc>, String)>>;
+
+    /// Whether a workload reports itself as ready
+    async fn is_ready(&self, workload: &Workload) -> bool;
+
+    /// Forward a local port to a port of a service, so that we can reach the
+    /// service from outside the cluster
+    async fn port_forward(&self, namespace: &str, service: &str, port: u16) -> PortForward;
+
+    /// Wait for a set of workloads to be ready, and return the ones that are
+    /// still not ready after the timeout
+    async fn wait_for_workloads(
+        &self,
+        workloads: &[Workload],
+        timeout: time::Duration,
+    ) -> Vec<Workload> {
+        let deadline = time::Instant::now() + timeout;
+        let mut pending = workloads.to_vec();
+        loop {
+            let mut not_ready = Vec::new();
+            for workload in pending {
+                if !self.is_ready(&workload).await {
+                    not_ready.push(workload);
+                }
+            }
+            pending = not_ready;
+
+            if pending.is_empty() || time::Instant::now() >= deadline {
+                return pending;
+            }
+
+            debug!(
+                "tlessctl(cluster): waiting for {} workloads to be ready: {}",
+                pending.len(),
+                pending
+                    .iter()
+                    .map(|workload| workload.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Wait until exactly num_expected pods match a selector, and are ready
+    async fn wait_for_pods(&self, namespace: &str, label_selector: &str, num_expected: usize) {
+        loop {
+            let pods = self.list_pods(namespace, label_selector).await;
+            if pods.len() == num_expected && pods.iter().all(|pod| pod.ready) {
+                return;
+            }
+
+            let not_ready: Vec<&str> = pods
+                .iter()
+                .filter(|pod| !pod.ready)
+                .map(|pod| pod.name.as_str())
+                .collect();
+            debug!(
+                "tlessctl(cluster): waiting for {num_expected} pods (label: {label_selector}) to be ready (found: {}, not ready: {not_ready:?})...",
+                pods.len()
+            );
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Pick a cluster backend
+pub async fn from_backend(backend: &ClusterBackend) -> Box<dyn Cluster> {
+    match backend {
+        ClusterBackend::Kube => Box::new(KubeCluster::new().await),
+        ClusterBackend::Fake => Box::new(FakeCluster::new(FAKE_SCALE_TO_ZERO_AFTER)),
+    }
+}
+
+/// Split a multi-document YAML manifest into its objects
+fn parse_manifest(manifest: &str) -> Vec<DynamicObject> {
+    serde_yaml::Deserializer::from_str(manifest)
+        .filter_map(|document| {
+            let value = serde_yaml::Value::deserialize(document)
+                .unwrap_or_else(|e| panic!("tlessctl(cluster): malformed manifest: {e}"));
+            if value.is_null() {
+                return None;
+            }
+
+            Some(
+                serde_yaml::from_value(value)
+                    .unwrap_or_else(|e| panic!("tlessctl(cluster): malformed object: {e}")),
+            )
+        })
+        .collect()
+}
+
+/// Labels in the pod template of an object, for the kinds that have one
+fn get_pod_template_labels(object: &DynamicObject) -> Option<BTreeMap<String, String>> {
+    let gvk = get_gvk(object);
+    let labels = match (gvk.group.as_str(), gvk.kind.as_str()) {
+        ("serving.knative.dev", "Service") => {
+            &object.data["spec"]["template"]["metadata"]["labels"]
+        }
+        ("sinks.knative.dev", "JobSink") => {
+            &object.data["spec"]["job"]["spec"]["template"]["metadata"]["labels"]
+        }
+        _ => return None,
+    };
+
+    Some(serde_json::from_value(labels.clone()).unwrap_or_default())
+}
+
+/// Find all the workloads in a manifest
+pub fn get_workloads(manifest: &str) -> Vec<Workload> {
+    parse_manifest(manifest)
+        .iter()
+        .filter_map(|object| {
+            Some(Workload {
+                pod_labels: get_pod_template_labels(object)?,
+                gvk: get_gvk(object),
+                namespace: object.namespace().unwrap_or_else(|| "default".to_string()),
+                name: object.name_any(),
+            })
+        })
+        .collect()
+}
+
+fn get_list_params(label_selector: &str) -> ListParams {
+    match label_selector {
+        "" => ListParams::default(),
+        selector => ListParams::default().labels(selector),
+    }
+}
+
+fn is_pod_ready(pod: &Pod) -> bool {
+    pod.status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref())
+        .map(|conditions| {
+            conditions
+                .iter()
+                .any(|c| c.type_ == "Ready" && c.status == "True")
+        })
+        .unwrap_or(false)
+}
+
+fn get_gvk(object: &DynamicObject) -> GroupVersionKind {
+    let types = object.types.as_ref().unwrap_or_else(|| {
+        panic!(
+            "tlessctl(cluster): object {} has no apiVersion or kind",
+            object.name_any()
+        )
+    });
+
+    GroupVersionKind::try_from(types)
+        .unwrap_or_else(|e| panic!("tlessctl(cluster): malformed apiVersion: {e}"))
+}
+
+/// Subset of the kubelet's stats summary that we need to report the
+/// resource usage of pods
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatsSummary {
+    #[serde(default)]
+    pods: Vec<PodStats>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PodStats {
+    pod_ref: PodReference,
+    cpu: Option<CpuStats>,
+    memory: Option<MemoryStats>,
+    network: Option<NetworkStats>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PodReference {
+    name: String,
+    namespace: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CpuStats {
+    usage_core_nano_seconds: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MemoryStats {
+    working_set_bytes: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NetworkStats {
+    rx_bytes: Option<u64>,
+    tx_bytes: Option<u64>,
+}
+
+impl From<PodStats> for ResourceUsage {
+    fn from(stats: PodStats) -> Self {
+        Self {
+            name: stats.pod_ref.name,
+            cpu_secs: stats
+                .cpu
+                .and_then(|cpu| cpu.usage_core_nano_seconds)
+                .unwrap_or_default() as f64
+                / 1e9,
+            memory_bytes: stats
+                .memory
+                .and_then(|memory| memory.working_set_bytes)
+                .unwrap_or_default(),
+            rx_bytes: stats
+                .network
+                .as_ref()
+                .and_then(|network| network.rx_bytes)
+                .unwrap_or_default(),
+            tx_bytes: stats
+                .network
+                .as_ref()
+                .and_then(|network| network.tx_bytes)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Cluster we talk to through the Kubernetes API, using the local kube
+/// config (or the service account, if we run in a pod)
+pub struct KubeCluster {
+    client: Client,
+}
+
+impl KubeCluster {
+    pub async fn new() -> Self {
+        let client = Client::try_default()
+            .await
+            .unwrap_or_else(|e| panic!("tlessctl(cluster): failed to create client: {e}"));
+
+        Self { client }
+    }
+
+    async fn discover(&self) -> Discovery {
+        Discovery::new(self.client.clone())
+            .run()
+            .await
+            .unwrap_or_else(|e| panic!("tlessctl(cluster): failed to discover API resources: {e}"))
+    }
+
+    /// The API server can only forward ports of pods, so, like kubectl, we
+    /// pick a ready pod behind the service, and the container port that the
+    /// service port maps to
+    async fn get_service_backend(
+        &self,
+        namespace: &str,
+        service: &str,
+        port: u16,
+    ) -> (String, u16) {
+        let services: Api<Service> = Api::namespaced(self.client.clone(), namespace);
+        let spec = services
+            .get(service)
+            .await
+            .unwrap_or_else(|e| panic!("tlessctl(cluster): failed to get service {service}: {e}"))
+            .spec
+            .unwrap_or_default();
+
+        let service_port = spec
+            .ports
+            .unwrap_or_default()
+            .into_iter()
+            .find(|service_port| service_port.port == port as i32)
+            .unwrap_or_else(|| panic!("tlessctl(cluster): service {service} has no port {port}"));
+        let selector: Vec<String> = spec
+            .selector
+            .unwrap_or_default()
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        if selector.is_empty() {
+            panic!("tlessctl(cluster): service {service} has no pod selector");
+        }
+
+        let pods: Api<Pod> = Api::namespaced(self.client.clone(), namespace);
+        let pod = pods
+            .list(&get_list_params(&selector.join(",")))
+            .await
+            .unwrap_or_else(|e| panic!("tlessctl(cluster): failed to list pods: {e}"))
+            .into_iter()
+            .find(is_pod_ready)
+            .unwrap_or_else(|| panic!("tlessctl(cluster): no ready pods behind service {service}"));
+
+        let target_port = match service_port.target_port {
+            None => port,
+            Some(IntOrString::Int(target_port)) => target_port as u16,
+            Some(IntOrString::String(port_name)) => pod
+                .spec
+                .iter()
+                .flat_map(|spec| &spec.containers)
+                .flat_map(|container| container.ports.iter().flatten())
+                .find(|container_port| container_port.name.as_ref() == Some(&port_name))
+                .map(|container_port| container_port.container_port as u16)
+                .unwrap_or_else(|| {
+                    panic!("tlessctl(cluster): no container port named {port_name} for {service}")
+                }),
+        };
+
+        (pod.name_any(), target_port)
+    }
+
+    /// Forward one local connection to a port of a pod
+    async fn forward_connection(
+        pods: Api<Pod>,
+        pod_name: String,
+        port: u16,
+        mut connection: TcpStream,
+    ) {
+        let mut forwarder = match pods.portforward(&pod_name, &[port]).await {
+            Ok(forwarder) => forwarder,
+            Err(e) => {
+                warn!("tlessctl(cluster): failed to port-forward to {pod_name}: {e}");
+                return;
+            }
+        };
+
+        let mut upstream = forwarder.take_stream(port).unwrap();
+        if let Err(e) = tokio::io::copy_bidirectional(&mut connection, &mut upstream).await {
+            debug!("tlessctl(cluster): port-forward connection to {pod_name} closed: {e}");
+        }
+
+        drop(upstream);
+        if let Err(e) = forwarder.join().await {
+            debug!("tlessctl(cluster): port-forward to {pod_name} failed: {e}");
+        }
+    }
+
+    fn get_api(&self, discovery: &Discovery, object: &DynamicObject) -> Api<DynamicObject> {
+        let gvk = get_gvk(object);
+        let (resource, capabilities) = discovery
+            .resolve_gvk(&gvk)
+            .unwrap_or_else(|| panic!("tlessctl(cluster): unknown resource kind: {gvk:?}"));
+
+        match capabilities.scope {
+            Scope::Namespaced => Api::namespaced_with(
+                self.client.clone(),
+                object.namespace().as_deref().unwrap_or("default"),
+                &resource,
+            ),
+            Scope::Cluster => Api::all_with(self.client.clone(), &resource),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl Cluster for KubeCluster {
+    async fn apply(&self, manifest: &str) {
+        let discovery = self.discover().await;
+        let params = PatchParams::apply(FIELD_MANAGER).force();
+
+        for object in parse_manifest(manifest) {
+            let name = object.name_any();
+            debug!(
+                "tlessctl(cluster): applying {} {name}",
+                get_gvk(&object).kind
+            );
+
+            self.get_api(&discovery, &object)
+                .patch(&name, &params, &Patch::Apply(&object))
+                .await
+                .unwrap_or_else(|e| panic!("tlessctl(cluster): failed to apply {name}: {e}"));
+        }
+    }
+
+    async fn delete(&self, manifest: &str) {
+        let discovery = self.discover().await;
+
+        // Delete objects in reverse order, so that we remove the objects that
+        // depend on others first
+        let objects = parse_manifest(manifest);
+        for object in objects.iter().rev() {
+            let name = object.name_any();
+            debug!(
+                "tlessctl(cluster): deleting {} {name}",
+                get_gvk(object).kind
+            );
+
+            match self
+                .get_api(&discovery, object)
+                .delete(&name, &DeleteParams::foreground())
+                .await
+            {
+                Ok(_) => {}
+                Err(kube::Error::Api(e)) if e.code == 404 => {}
+                Err(e) => panic!("tlessctl(cluster): failed to delete {name}: {e}"),
+            }
+        }
+
+        // With a foreground cascade, objects are only gone once all their
+        // dependents are gone too
+        for object in &objects {
+            let api = self.get_api(&discovery, object);
+            let name = object.name_any();
+            while api
+                .get_opt(&name)
+                .await
+                .unwrap_or_else(|e| panic!("tlessctl(cluster): failed to get {name}: {e}"))
+                .is_some()
+            {
+                debug!("tlessctl(cluster): waiting for {name} to be deleted...");
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+
+    async fn list_pods(&self, namespace: &str, label_selector: &str) -> Vec<PodStatus> {
+        let api: Api<Pod> = Api::namespaced(self.client.clone(), namespace);
+        api.list(&get_list_params(label_selector))
+            .await
+            .unwrap_or_else(|e| panic!("tlessctl(cluster): failed to list pods: {e}"))
+            .into_iter()
+            .map(|pod| PodStatus {
+                ready: is_pod_ready(&pod),
+                name: pod.name_any(),
+                labels: pod.labels().clone(),
+            })
+            .collect()
+    }
+
+    async fn get_service_cluster_ip(&self, namespace: &str, name: &str) -> Option<String> {
+        let api: Api<Service> = Api::namespaced(self.client.clone(), namespace);
+        api.get_opt(name)
+            .await
+            .unwrap_or_else(|e| panic!("tlessctl(cluster): failed to get service {name}: {e}"))?
+            .spec?
+            .cluster_ip
+    }
+
+    async fn get_pod_usage(&self, namespace: &str, label_selector: &str) -> Vec<ResourceUsage> {
+        let pod_names: BTreeSet<String> = self
+            .list_pods(namespace, label_selector)
+            .await
+            .into_iter()
+            .map(|pod| pod.name)
+            .collect();
+        if pod_names.is_empty() {
+            return Vec::new();
+        }
+
+        // The kubelet in each node reports the usage of the pods it runs
+        let api: Api<Node> = Api::all(self.client.clone());
+        let nodes = api
+            .list(&ListParams::default())
+            .await
+            .unwrap_or_else(|e| panic!("tlessctl(cluster): failed to list nodes: {e}"));
+
+        let mut usage = Vec::new();
+        for node in nodes {
+            let node_name = node.name_any();
+            let request =
+                http::Request::get(format!("/api/v1/nodes/{node_name}/proxy/stats/summary"))
+                    .body(Vec::new())
+                    .unwrap();
+
+            // Usage is best-effort, so we do not fail if a kubelet is down
+            let summary: StatsSummary = match self.client.request(request).await {
+                Ok(summary) => summary,
+                Err(e) => {
+                    warn!("tlessctl(cluster): failed to get stats for node {node_name}: {e}");
+                    continue;
+                }
+            };
+
+            usage.extend(
+                summary
+                    .pods
+                    .into_iter()
+                    .filter(|pod| {
+                        pod.pod_ref.namespace == namespace && pod_names.contains(&pod.pod_ref.name)
+                    })
+                    .map(ResourceUsage::from),
+            );
+        }
+
+        usage
+    }
+
+    async fn get_pod_timelines(&self, namespace: &str, label_selector: &str) -> Vec<PodTimeline> {
+        let api: Api<Pod> = Api::namespaced(self.client.clone(), namespace);
+        api.list(&get_list_params(label_selector))
+            .await
+            .unwrap_or_else(|e| panic!("tlessctl(cluster): failed to list pods: {e}"))
+            .into_iter()
+            .map(|pod| {
+                let conditions = pod
+                    .status
+                    .as_ref()
+                    .and_then(|status| status.conditions.as_ref())
+                    .map(|conditions| {
+                        conditions
+                            .iter()
+                            .filter(|c| c.status == "True")
+                            .filter_map(|c| {
+                                Some((c.type_.clone(), c.last_transition_time.as_ref()?.0))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                PodTimeline {
+                    name: pod.name_any(),
+                    labels: pod.labels().clone(),
+                    created: pod.metadata.creation_timestamp.as_ref().map(|time| time.0),
+                    conditions,
+                }
+            })
+            .collect()
+    }
+
+    async fn get_pod_logs(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> Option<Vec<(DateTime<Utc>, String)>> {
+        let api: Api<Pod> = Api::namespaced(self.client.clone(), namespace);
+        let pod = api
+            .get_opt(name)
+            .await
+            .unwrap_or_else(|e| panic!("tlessctl(cluster): failed to get pod {name}: {e}"))?;
+
+        // Knative runs a queue-proxy sidecar next to the function container
+        let container = pod
+            .spec?
+            .containers
+            .into_iter()
+            .map(|container| container.name)
+            .find(|container| container != "queue-proxy")?;
+        let params = LogParams {
+            container: Some(container),
+            timestamps: true,
+            ..Default::default()
+        };
+
+        let logs = match api.logs(name, &params).await {
+            Ok(logs) => logs,
+            Err(e) => {
+                debug!("tlessctl(cluster): no logs for pod {name} yet: {e}");
+                return None;
+            }
+        };
+
+        // Each line starts with its RFC 3339 timestamp
+        Some(
+            logs.lines()
+                .filter_map(|line| {
+                    let (time, line) = line.split_once(' ')?;
+                    let time = DateTime::parse_from_rfc3339(time).ok()?;
+                    Some((time.with_timezone(&Utc), line.to_string()))
+                })
+                .collect(),
+        )
+    }
+
+    async fn is_ready(&self, workload: &Workload) -> bool {
+        // Knative kinds are namespaced, and follow the default plural naming
+        let resource = ApiResource::from_gvk(&workload.gvk);
+        let api: Api<DynamicObject> =
+            Api::namespaced_with(self.client.clone(), &workload.namespace, &resource);
+
+        let object = match api.get_opt(&workload.name).await {
+            Ok(Some(object)) => object,
+            Ok(None) => return false,
+            Err(e) => panic!("tlessctl(cluster): failed to get {workload}: {e}"),
+        };
+
+        match object.data["status"]["conditions"].as_array() {
+            Some(conditions) => conditions
+                .iter()
+                .any(|c| c["type"] == "Ready" && c["status"] == "True"),
+            None => false,
+        }
+    }
+
+    async fn port_forward(&self, namespace: &str, service: &str, port: u16) -> PortForward {
+        let (pod_name, target_port) = self.get_service_backend(namespace, service, port).await;
+        let pods: Api<Pod> = Api::namespaced(self.client.clone(), namespace);
+
+        let port_forward = PortForward::start(move |connection| {
+            Self::forward_connection(pods.clone(), pod_name.clone(), target_port, connection)
+        })
+        .await;
+        debug!(
+            "tlessctl(cluster): port-forwarding {} to {namespace}/{service}:{port}",
+            port_forward.url
+        );
+
+        port_forward
+    }
+}
+
+/// Whether a set of labels matches a selector. We support the equality-based
+/// subset of the selector syntax: 'key=value', 'key!=value', and 'key'
+fn matches_selector(labels: &BTreeMap<String, String>, label_selector: &str) -> bool {
+    label_selector
+        .split(',')
+        .map(str::trim)
+        .filter(|requirement| !requirement.is_empty())
+        .all(|requirement| {
+            if let Some((key, value)) = requirement.split_once("!=") {
+                labels.get(key.trim()).map(String::as_str) != Some(value.trim())
+            } else if let Some((key, value)) = requirement.split_once('=') {
+                let key = key.trim().trim_end_matches('=');
+                labels.get(key).map(String::as_str) == Some(value.trim())
+            } else {
+                labels.contains_key(requirement)
+            }
+        })
+}
+
+/// An event we received through a port-forward of the fake cluster
+#[derive(Clone, Debug)]
+pub struct FakeEvent {
+    /// Host header of the request, which names the channel of the event
+    pub host: String,
+    pub body: String,
+}
+
+// Objects in the fake cluster, by (namespace, kind, name)
+type ObjectKey = (String, String, String);
+
+/// The pod that a fake object creates. Pods of Knative Services scale to
+/// zero once they have not received events for a while, and a new pod (i.e.
+/// the next generation) comes up with the next event
+#[derive(Clone, Debug)]
+struct FakePod {
+    generation: u32,
+    labels: BTreeMap<String, String>,
+    created: DateTime<Utc>,
+    last_active: time::Instant,
+    scales_to_zero: bool,
+}
+
+#[derive(Debug)]
+struct FakeState {
+    objects: BTreeMap<ObjectKey, DynamicObject>,
+    pods: BTreeMap<ObjectKey, FakePod>,
+    events: Vec<FakeEvent>,
+    scale_to_zero_after: time::Duration,
+}
+
+impl FakeState {
+    fn is_up(&self, pod: &FakePod) -> bool {
+        !pod.scales_to_zero || pod.last_active.elapsed() < self.scale_to_zero_after
+    }
+
+    /// Pods that are up, with their names
+    fn get_pods<'a>(&'a self, namespace: &'a str) -> impl Iterator<Item = (String, &'a FakePod)> {
+        self.pods
+            .iter()
+            .filter(move |((pod_namespace, _, _), pod)| {
+                pod_namespace == namespace && self.is_up(pod)
+            })
+            .map(|((_, _, name), pod)| (format!("{name}-{}", pod.generation), pod))
+    }
+
+    /// Events wake up all the Knative Services, as if the workflow ran
+    /// through all its functions
+    fn receive_event(&mut self, event: FakeEvent) {
+        debug!(
+            "tlessctl(cluster): fake cluster received event for {}: {}",
+            event.host, event.body
+        );
+
+        let scale_to_zero_after = self.scale_to_zero_after;
+        for pod in self.pods.values_mut().filter(|pod| pod.scales_to_zero) {
+            if pod.last_active.elapsed() >= scale_to_zero_after {
+                pod.generation += 1;
+                pod.created = Utc::now();
+            }
+            pod.last_active = time::Instant::now();
+        }
+        self.events.push(event);
+    }
+}
+
+/// In-memory cluster, to check the orchestration logic without deploying
+/// anything. Every Pod and Knative Service we apply gets one pod, which is
+/// ready straight away. Pods of Knative Services scale to zero after a
+/// while, and back up when we send an event through a port-forward. Any
+/// port-forward accepts all HTTP requests as events
+#[derive(Debug)]
+pub struct FakeCluster {
+    state: Arc<Mutex<FakeState>>,
+}
+
+impl FakeCluster {
+    pub fn new(scale_to_zero_after: time::Duration) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(FakeState {
+                objects: BTreeMap::new(),
+                pods: BTreeMap::new(),
+                events: Vec::new(),
+                scale_to_zero_after,
+            })),
+        }
+    }
+
+    fn get_key(object: &DynamicObject) -> ObjectKey {
+        (
+            object.namespace().unwrap_or_else(|| "default".to_string()),
+            get_gvk(object).kind,
+            object.name_any(),
+        )
+    }
+
+    /// Labels of the pod an object would create, if any
+    fn get_pod_labels(object: &DynamicObject) -> Option<BTreeMap<String, String>> {
+        let gvk = get_gvk(object);
+        match (gvk.group.as_str(), gvk.kind.as_str()) {
+            ("", "Pod") => Some(object.labels().clone()),
+            // JobSinks only create pods when they receive events
+            ("sinks.knative.dev", "JobSink") => None,
+            _ => get_pod_template_labels(object),
+        }
+    }
+
+    /// Read one HTTP/1.1 request after another from a connection, record
+    /// each one as an event, and accept it
+    async fn serve_events(state: Arc<Mutex<FakeState>>, connection: TcpStream) {
+        let mut connection = BufReader::new(connection);
+        loop {
+            let mut headers = BTreeMap::<String, String>::new();
+            let mut line = String::new();
+            loop {
+                line.clear();
+                match connection.read_line(&mut line).await {
+                    Ok(0) | Err(_) => return,
+                    Ok(_) => {}
+                }
+
+                // Headers end with an empty line, and we skip the request line
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((key, value)) = line.split_once(':') {
+                    headers.insert(key.trim().to_lowercase(), value.trim().to_string());
+                }
+            }
+
+            let content_length = headers
+                .get("content-length")
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(0);
+            let mut body = vec![0; content_length];
+            if connection.read_exact(&mut body).await.is_err() {
+                return;
+            }
+
+            state.lock().unwrap().receive_event(FakeEvent {
+                host: headers.remove("host").unwrap_or_default(),
+                body: String::from_utf8_lossy(&body).to_string(),
+            });
+
+            let response = b"HTTP/1.1 202 Accepted\r\ncontent-length: 0\r\n\r\n";
+            if connection.get_mut().write_all(response).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Events we have received so far, in order
+    #[cfg(test)]
+    pub fn get_events(&self) -> Vec<FakeEvent> {
+        self.state.lock().unwrap().events.clone()
+    }
+}
+
+#[async_trait(?Send)]
+impl Cluster for FakeCluster {
+    async fn apply(&self, manifest: &str) {
+        let mut state = self.state.lock().unwrap();
+        for object in parse_manifest(manifest) {
+            let key = Self::get_key(&object);
+            if let Some(labels) = Self::get_pod_labels(&object) {
+                let scales_to_zero = get_gvk(&object).group == "serving.knative.dev";
+                state.pods.entry(key.clone()).or_insert_with(|| FakePod {
+                    generation: 0,
+                    labels,
+                    created: Utc::now(),
+                    last_active: time::Instant::now(),
+                    scales_to_zero,
+                });
+            }
+            state.objects.insert(key, object);
+        }
+    }
+
+    async fn delete(&self, manifest: &str) {
+        let mut state = self.state.lock().unwrap();
+        for object in parse_manifest(manifest) {
+            let key = Self::get_key(&object);
+            state.pods.remove(&key);
+            state.objects.remove(&key);
+        }
+    }
+
+    async fn list_pods(&self, namespace: &str, label_selector: &str) -> Vec<PodStatus> {
+        let state = self.state.lock().unwrap();
+        state
+            .get_pods(namespace)
+            .filter(|(_, pod)| matches_selector(&pod.labels, label_selector))
+            .map(|(name, pod)| PodStatus {
+                name,
+                labels: pod.labels.clone(),
+                ready: true,
+            })
+            .collect()
+    }
+
+    async fn get_service_cluster_ip(&self, namespace: &str, name: &str) -> Option<String> {
+        let key = (
+            namespace.to_string(),
+            "Service".to_string(),
+            name.to_string(),
+        );
+        self.state
+            .lock()
+            .unwrap()
+            .objects
+            .contains_key(&key)
+            .then(|| "127.0.0.1".to_string())
+    }
+
+    async fn get_pod_usage(&self, namespace: &str, label_selector: &str) -> Vec<ResourceUsage> {
+        // Fake pods do not use any resources
+        self.list_pods(namespace, label_selector)
+            .await
+            .into_iter()
+            .map(|pod| ResourceUsage {
+                name: pod.name,
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    async fn get_pod_timelines(&self, namespace: &str, label_selector: &str) -> Vec<PodTimeline> {
+        // Fake pods go through all their conditions as soon as they start
+        let state = self.state.lock().unwrap();
+        state
+            .get_pods(namespace)
+            .filter(|(_, pod)| matches_selector(&pod.labels, label_selector))
+            .map(|(name, pod)| PodTimeline {
+                name,
+                labels: pod.labels.clone(),
+                created: Some(pod.created),
+                conditions: ["PodScheduled", "Initialized", "ContainersReady", "Ready"]
+                    .into_iter()
+                    .map(|condition| (condition.to_string(), pod.created))
+                    .collect(),
+            })
+            .collect()
+    }
+
+    async fn get_pod_logs(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> Option<Vec<(DateTime<Utc>, String)>> {
+        // Fake pods do not log anything
+        self.list_pods(namespace, "")
+            .await
+            .iter()
+            .any(|pod| pod.name == name)
+            .then(Vec::new)
+    }
+
+    async fn is_ready(&self, workload: &Workload) -> bool {
+        let key = (
+            workload.namespace.clone(),
+            workload.gvk.kind.clone(),
+            workload.name.clone(),
+        );
+        self.state.lock().unwrap().objects.contains_key(&key)
+    }
+
+    async fn port_forward(&self, namespace: &str, service: &str, port: u16) -> PortForward {
+        let state = self.state.clone();
+        let port_forward =
+            PortForward::start(move |connection| Self::serve_events(state.clone(), connection))
+                .await;
+        debug!(
+            "tlessctl(cluster): fake port-forward {} to {namespace}/{service}:{port}",
+            port_forward.url
+        );
+
+        port_forward
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // MinIO, which never scales down, and a Knative Service, which does
+    const COMMON_MANIFEST: &str = "
+apiVersion: v1
+kind: Pod
+metadata:
+  name: minio
+  namespace: tless
+  labels:
+    tless.workflows/name: minio
+spec:
+  containers:
+    - name: minio
+      image: minio/minio
+";
+    const WORKFLOW_MANIFEST: &str = "
+apiVersion: serving.knative.dev/v1
+kind: Service
+metadata:
+  name: splitter
+  namespace: tless
+spec:
+  template:
+    metadata:
+      labels:
+        tless.workflows/name: word-count
+    spec:
+      containers:
+        - image: splitter
+";
+
+    fn get_pod_names(pods: &[PodStatus]) -> Vec<&str> {
+        pods.iter().map(|pod| pod.name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn fake_pods_follow_applied_objects() {
+        let cluster = FakeCluster::new(time::Duration::from_secs(60));
+        cluster.apply(COMMON_MANIFEST).await;
+        cluster.apply(WORKFLOW_MANIFEST).await;
+
+        let workloads = get_workloads(WORKFLOW_MANIFEST);
+        assert!(cluster
+            .wait_for_workloads(&workloads, time::Duration::ZERO)
+            .await
+            .is_empty());
+        assert_eq!(
+            get_pod_names(&cluster.list_pods("tless", "").await),
+            vec!["minio-0", "splitter-0"]
+        );
+        assert_eq!(
+            get_pod_names(
+                &cluster
+                    .list_pods("tless", "tless.workflows/name!=minio")
+                    .await
+            ),
+            vec!["splitter-0"]
+        );
+        assert!(cluster.list_pods("default", "").await.is_empty());
+
+        // Deleting an object terminates its pod
+        cluster.delete(WORKFLOW_MANIFEST).await;
+        assert_eq!(
+            get_pod_names(&cluster.list_pods("tless", "").await),
+            vec!["minio-0"]
+        );
+        assert_eq!(
+            cluster
+                .wait_for_workloads(&workloads, time::Duration::ZERO)
+                .await
+                .len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn fake_knative_pods_scale_to_zero_and_back_up() {
+        let cluster = FakeCluster::new(time::Duration::from_millis(100));
+        cluster.apply(COMMON_MANIFEST).await;
+        cluster.apply(WORKFLOW_MANIFEST).await;
+
+        tokio::time::sleep(time::Duration::from_millis(200)).await;
+        assert_eq!(
+            get_pod_names(&cluster.list_pods("tless", "").await),
+            vec!["minio-0"]
+        );
+
+        // Events through a port-forward bring up a new pod. We send two on
+        // the same connection, like a client that keeps connections alive
+        let port_forward = cluster
+            .port_forward("knative-eventing", "imc-dispatcher", 80)
+            .await;
+        let client = reqwest::Client::new();
+        for body in ["first", "second"] {
+            let response = client
+                .post(&port_forward.url)
+                .header(reqwest::header::HOST, "ingress-kn-channel")
+                .body(body)
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), reqwest::StatusCode::ACCEPTED);
+        }
+
+        let events: Vec<(String, String)> = cluster
+            .get_events()
+            .into_iter()
+            .map(|event| (event.host, event.body))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                ("ingress-kn-channel".to_string(), "first".to_string()),
+                ("ingress-kn-channel".to_string(), "second".to_string()),
+            ]
+        );
+        assert_eq!(
+            get_pod_names(&cluster.list_pods("tless", "").await),
+            vec!["minio-0", "splitter-1"]
+        );
+        let timelines = cluster
+            .get_pod_timelines("tless", "tless.workflows/name=word-count")
+            .await;
+        assert_eq!(timelines.len(), 1);
+        assert_eq!(
+            timelines[0].conditions["Ready"],
+            timelines[0].created.unwrap()
+        );
+
+        tokio::time::sleep(time::Duration::from_millis(200)).await;
+        assert_eq!(
+            get_pod_names(&cluster.list_pods("tless", "").await),
+            vec!["minio-0"]
+        );
+
+        // Dropping the port-forward stops it
+        let url = port_forward.url.clone();
+        drop(port_forward);
+        tokio::task::yield_now().await;
+        assert!(reqwest::Client::new()
+            .post(&url)
+            .body("third")
+            .send()
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,468 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::{env, fmt};
+
+// FIXME(tless-prod): symmetric key is currently hardcoded. In production it
+// would be given to the user upon registration
+pub static DEMO_SYM_KEY: [u8; 32] = [
+    0xf0, 0x0d, 0x48, 0x2e, 0xca, 0x21, 0xfb, 0x13, 0xec, 0xf0, 0x01, 0x48, 0xba, 0x60, 0x01, 0x76,
+    0x6e, 0x56, 0xbb, 0xa5, 0xff, 0x9b, 0x11, 0x9d, 0xd6, 0xfa, 0x96, 0x39, 0x2b, 0x7c, 0x1a, 0x0d,
+];
+
+// Size of the AES-GCM nonce we prepend to every cipher-text
+const NONCE_SIZE: usize = 12;
+// Size of the AES-GCM authentication tag appended to every cipher-text
+const TAG_SIZE: usize = 16;
+
+// We encrypt objects in chunks of this many bytes of plain-text, so that
+// encrypting or decrypting an object only holds one chunk in memory
+pub const ENVELOPE_CHUNK_SIZE: usize = 1 << 20;
+
+// Encrypted objects start with a random nonce prefix. The nonce of each chunk
+// is the prefix, the chunk index (4 bytes), and a flag marking the last chunk
+// (1 byte), so that chunks can not be re-ordered, dropped, or truncated
+// without decryption failing
+const NONCE_PREFIX_SIZE: usize = 7;
+
+/// Wrapping information for an envelope-encrypted object. We store it as
+/// S3 user metadata on the object itself, so that encrypting a dataset does
+/// not add extra keys under its prefix
+#[derive(Debug)]
+pub struct EnvelopeMetadata {
+    /// Per-object data key, encrypted with the user key (nonce || ct)
+    pub wrapped_key: Vec<u8>,
+    /// Truncated hash of the user key that wrapped the data key
+    pub key_id: String,
+    /// Whether the object was encrypted in chunks, or as a whole (only
+    /// objects uploaded with the first version of the envelope)
+    pub chunked: bool,
+}
+
+impl EnvelopeMetadata {
+    pub const VERSION: &'static str = "2";
+    pub const WHOLE_OBJECT_VERSION: &'static str = "1";
+    pub const VERSION_KEY: &'static str = "tless-envelope-version";
+    pub const WRAPPED_KEY_KEY: &'static str = "tless-envelope-wrapped-key";
+    pub const KEY_ID_KEY: &'static str = "tless-envelope-key-id";
+
+    pub fn to_user_metadata(&self) -> Vec<(String, String)> {
+        let version = match self.chunked {
+            true => Self::VERSION,
+            false => Self::WHOLE_OBJECT_VERSION,
+        };
+
+        vec![
+            (Self::VERSION_KEY.to_string(), version.to_string()),
+            (
+                Self::WRAPPED_KEY_KEY.to_string(),
+                hex::encode(&self.wrapped_key),
+            ),
+            (Self::KEY_ID_KEY.to_string(), self.key_id.clone()),
+        ]
+    }
+
+    /// Parse the wrapping information from the user metadata of an object.
+    /// Depending on the call, S3 returns user metadata keys with or without
+    /// the 'x-amz-meta-' prefix, so we accept both
+    pub fn from_user_metadata(user_metadata: &HashMap<String, String>) -> Option<Self> {
+        let lookup = |key: &str| -> Option<&String> {
+            user_metadata
+                .iter()
+                .find(|(k, _)| {
+                    let k = k.to_lowercase();
+                    k == key || k.strip_prefix("x-amz-meta-") == Some(key)
+                })
+                .map(|(_, v)| v)
+        };
+
+        let chunked = match lookup(Self::VERSION_KEY) {
+            Some(version) if version == Self::VERSION => true,
+            Some(version) if version == Self::WHOLE_OBJECT_VERSION => false,
+            Some(version) => panic!("tlessctl(crypto): unsupported envelope version: {version}"),
+            None => return None,
+        };
+
+        let wrapped_key = hex::decode(lookup(Self::WRAPPED_KEY_KEY)?)
+            .expect("tlessctl(crypto): malformed wrapped key in object metadata");
+        let key_id = lookup(Self::KEY_ID_KEY)?.to_string();
+
+        Some(Self {
+            wrapped_key,
+            key_id,
+            chunked,
+        })
+    }
+}
+
+/// Nonce of the chunk with a given index in an envelope-encrypted object
+fn get_chunk_nonce(
+    prefix: &[u8; NONCE_PREFIX_SIZE],
+    index: u32,
+    is_last: bool,
+) -> [u8; NONCE_SIZE] {
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce[..NONCE_PREFIX_SIZE].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_SIZE..NONCE_SIZE - 1].copy_from_slice(&index.to_be_bytes());
+    nonce[NONCE_SIZE - 1] = is_last as u8;
+    nonce
+}
+
+/// Encrypt an object as we read it. We only buffer one chunk of plain-text
+/// at a time, and the cipher-text starts with the nonce prefix
+pub struct EnvelopeEncryptor {
+    cipher: Aes256Gcm,
+    prefix: [u8; NONCE_PREFIX_SIZE],
+    index: u32,
+    buffer: Vec<u8>,
+}
+
+impl EnvelopeEncryptor {
+    fn encrypt_chunk(&mut self, num_bytes: usize, is_last: bool) -> Vec<u8> {
+        let nonce = get_chunk_nonce(&self.prefix, self.index, is_last);
+        let ct = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), &self.buffer[..num_bytes])
+            .expect("tlessctl(crypto): error encrypting payload");
+        self.buffer.drain(..num_bytes);
+
+        let mut encrypted = Vec::with_capacity(NONCE_PREFIX_SIZE + ct.len());
+        if self.index == 0 {
+            encrypted.extend_from_slice(&self.prefix);
+        }
+        encrypted.extend_from_slice(&ct);
+
+        self.index = self
+            .index
+            .checked_add(1)
+            .expect("tlessctl(crypto): object too large to encrypt");
+        encrypted
+    }
+
+    /// Feed the next bytes of plain-text, and return the cipher-text we can
+    /// produce so far
+    pub fn update(&mut self, plain_text: &[u8]) -> Vec<u8> {
+        self.buffer.extend_from_slice(plain_text);
+
+        // A full chunk is only the last one if nothing follows it, so we
+        // hold it back until we see more plain-text, or finish
+        let mut cipher_text = Vec::new();
+        while self.buffer.len() > ENVELOPE_CHUNK_SIZE {
+            cipher_text.extend(self.encrypt_chunk(ENVELOPE_CHUNK_SIZE, false));
+        }
+
+        cipher_text
+    }
+
+    /// Encrypt the remaining plain-text as the last chunk, which is empty
+    /// for empty objects
+    pub fn finish(mut self) -> Vec<u8> {
+        let num_bytes = self.buffer.len();
+        self.encrypt_chunk(num_bytes, true)
+    }
+}
+
+/// Errors decrypting an object, e.g. because it is truncated or corrupted
+#[derive(Debug)]
+pub enum EnvelopeError {
+    TooShort,
+    Decrypt,
+}
+
+impl fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EnvelopeError::TooShort => write!(f, "cipher-text too short"),
+            EnvelopeError::Decrypt => {
+                write!(
+                    f,
+                    "error decrypting payload (wrong key, or corrupted object?)"
+                )
+            }
+        }
+    }
+}
+
+/// Decrypt an envelope-encrypted object as we receive it. We only buffer
+/// one chunk of cipher-text at a time, except for objects encrypted as a
+/// whole, which we can only decrypt once we have all of it
+pub struct EnvelopeDecryptor {
+    cipher: Aes256Gcm,
+    chunked: bool,
+    buffer: Vec<u8>,
+    prefix: Option<[u8; NONCE_PREFIX_SIZE]>,
+    index: u32,
+}
+
+impl EnvelopeDecryptor {
+    fn decrypt_chunk(&mut self, num_bytes: usize, is_last: bool) -> Result<Vec<u8>, EnvelopeError> {
+        let prefix = self.prefix.unwrap();
+        let nonce = get_chunk_nonce(&prefix, self.index, is_last);
+        let plain_text = self
+            .cipher
+            .decrypt(Nonce::from_slice(&nonce), &self.buffer[..num_bytes])
+            .map_err(|_| EnvelopeError::Decrypt)?;
+
+        self.buffer.drain(..num_bytes);
+        self.index += 1;
+        Ok(plain_text)
+    }
+
+    /// Feed the next bytes of cipher-text, and return the plain-text we can
+    /// decrypt so far
+    pub fn update(&mut self, cipher_text: &[u8]) -> Result<Vec<u8>, EnvelopeError> {
+        self.buffer.extend_from_slice(cipher_text);
+        if !self.chunked {
+            return Ok(Vec::new());
+        }
+
+        if self.prefix.is_none() {
+            if self.buffer.len() < NONCE_PREFIX_SIZE {
+                return Ok(Vec::new());
+            }
+            self.prefix = Some(self.buffer[..NONCE_PREFIX_SIZE].try_into().unwrap());
+            self.buffer.drain(..NONCE_PREFIX_SIZE);
+        }
+
+        // A full chunk is only the last one if nothing follows it, so we
+        // hold it back until we see more cipher-text, or finish
+        let mut plain_text = Vec::new();
+        while self.buffer.len() > ENVELOPE_CHUNK_SIZE + TAG_SIZE {
+            plain_text.extend(self.decrypt_chunk(ENVELOPE_CHUNK_SIZE + TAG_SIZE, false)?);
+        }
+
+        Ok(plain_text)
+    }
+
+    /// Decrypt the remaining cipher-text, once we have fed all of it
+    pub fn finish(mut self) -> Result<Vec<u8>, EnvelopeError> {
+        if !self.chunked {
+            return Crypto::try_open_with(&self.cipher, &self.buffer);
+        }
+
+        if self.prefix.is_none() || self.buffer.len() < TAG_SIZE {
+            return Err(EnvelopeError::TooShort);
+        }
+
+        let num_bytes = self.buffer.len();
+        self.decrypt_chunk(num_bytes, true)
+    }
+}
+
+#[derive(Debug)]
+pub struct Crypto {}
+
+impl Crypto {
+    /// Get the user key used to wrap per-object data keys. It can be set as
+    /// a hex string in TLESS_USER_KEY, and defaults to the demo key
+    pub fn get_user_key() -> [u8; 32] {
+        match env::var("TLESS_USER_KEY") {
+            Ok(value) => hex::decode(value.trim())
+                .expect("tlessctl(crypto): TLESS_USER_KEY must be a hex string")
+                .try_into()
+                .expect("tlessctl(crypto): TLESS_USER_KEY must be 32 bytes long"),
+            Err(env::VarError::NotPresent) => DEMO_SYM_KEY,
+            Err(e) => panic!("tlessctl(crypto): failed to read env. var: {e}"),
+        }
+    }
+
+    fn get_key_id(user_key: &[u8; 32]) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(user_key);
+        hex::encode(&hasher.finalize()[..8])
+    }
+
+    /// Encrypt a byte array and prepend the nonce to the cipher-text
+    pub fn seal(key: &[u8; 32], plain_text: &[u8]) -> Vec<u8> {
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ct = cipher
+            .encrypt(&nonce, plain_text)
+            .expect("tlessctl(crypto): error encrypting payload");
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ct);
+        sealed
+    }
+
+    /// Decrypt a byte array produced by seal
+    pub fn open(key: &[u8; 32], sealed: &[u8]) -> Vec<u8> {
+        Self::open_with(&Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)), sealed)
+    }
+
+    fn try_open_with(cipher: &Aes256Gcm, sealed: &[u8]) -> Result<Vec<u8>, EnvelopeError> {
+        if sealed.len() < NONCE_SIZE {
+            return Err(EnvelopeError::TooShort);
+        }
+
+        let (nonce, ct) = sealed.split_at(NONCE_SIZE);
+        cipher
+            .decrypt(Nonce::from_slice(nonce), ct)
+            .map_err(|_| EnvelopeError::Decrypt)
+    }
+
+    fn open_with(cipher: &Aes256Gcm, sealed: &[u8]) -> Vec<u8> {
+        Self::try_open_with(cipher, sealed).unwrap_or_else(|e| panic!("tlessctl(crypto): {e}"))
+    }
+
+    /// Size of the cipher-text of an object with a given plain-text size.
+    /// Every chunk carries a tag, and even an empty object has one chunk
+    pub fn get_envelope_size(plain_text_size: u64) -> u64 {
+        let chunk_size = ENVELOPE_CHUNK_SIZE as u64;
+        let num_chunks = std::cmp::max(1, plain_text_size.div_ceil(chunk_size));
+        NONCE_PREFIX_SIZE as u64 + plain_text_size + num_chunks * TAG_SIZE as u64
+    }
+
+    /// Start encrypting an object with a fresh data key, and wrap the data
+    /// key with the user key
+    pub fn envelope_encrypt() -> (EnvelopeEncryptor, EnvelopeMetadata) {
+        let user_key = Self::get_user_key();
+        let data_key: [u8; 32] = Aes256Gcm::generate_key(&mut OsRng).into();
+
+        let encryptor = EnvelopeEncryptor {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&data_key)),
+            prefix: rand::thread_rng().gen(),
+            index: 0,
+            buffer: Vec::new(),
+        };
+        let metadata = EnvelopeMetadata {
+            wrapped_key: Self::seal(&user_key, &data_key),
+            key_id: Self::get_key_id(&user_key),
+            chunked: true,
+        };
+
+        (encryptor, metadata)
+    }
+
+    pub fn envelope_decrypt(metadata: &EnvelopeMetadata) -> EnvelopeDecryptor {
+        let user_key = Self::get_user_key();
+        if metadata.key_id != Self::get_key_id(&user_key) {
+            panic!(
+                "tlessctl(crypto): object was encrypted with a different user key (id: {})",
+                metadata.key_id
+            );
+        }
+
+        let data_key: [u8; 32] = Self::open(&user_key, &metadata.wrapped_key)
+            .try_into()
+            .expect("tlessctl(crypto): unwrapped data key has the wrong size");
+
+        EnvelopeDecryptor {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&data_key)),
+            chunked: metadata.chunked,
+            buffer: Vec::new(),
+            prefix: None,
+            index: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_plain_text(num_bytes: usize) -> Vec<u8> {
+        (0..num_bytes).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Encrypt feeding the plain-text in pieces that do not line up with
+    /// the chunks, like reads from a file
+    fn encrypt(plain_text: &[u8]) -> (Vec<u8>, EnvelopeMetadata) {
+        let (mut encryptor, metadata) = Crypto::envelope_encrypt();
+        let mut cipher_text = Vec::new();
+        for piece in plain_text.chunks(65_537) {
+            cipher_text.extend(encryptor.update(piece));
+        }
+        cipher_text.extend(encryptor.finish());
+        (cipher_text, metadata)
+    }
+
+    /// Decrypt feeding the cipher-text in pieces that do not line up with
+    /// the chunks, like the parts of an S3 download
+    fn decrypt(cipher_text: &[u8], metadata: &EnvelopeMetadata) -> Result<Vec<u8>, EnvelopeError> {
+        let mut decryptor = Crypto::envelope_decrypt(metadata);
+        let mut plain_text = Vec::new();
+        for piece in cipher_text.chunks(100_003) {
+            plain_text.extend(decryptor.update(piece)?);
+        }
+        plain_text.extend(decryptor.finish()?);
+        Ok(plain_text)
+    }
+
+    #[test]
+    fn envelope_round_trip() {
+        for num_bytes in [
+            0,
+            1,
+            ENVELOPE_CHUNK_SIZE - 1,
+            ENVELOPE_CHUNK_SIZE,
+            ENVELOPE_CHUNK_SIZE + 1,
+            3 * ENVELOPE_CHUNK_SIZE + 17,
+        ] {
+            let plain_text = get_plain_text(num_bytes);
+            let (cipher_text, metadata) = encrypt(&plain_text);
+
+            assert_eq!(
+                cipher_text.len() as u64,
+                Crypto::get_envelope_size(num_bytes as u64)
+            );
+            assert_eq!(decrypt(&cipher_text, &metadata).unwrap(), plain_text);
+        }
+    }
+
+    #[test]
+    fn envelope_metadata_round_trip() {
+        let (_, metadata) = encrypt(b"hello");
+        let user_metadata: HashMap<String, String> = metadata
+            .to_user_metadata()
+            .into_iter()
+            .map(|(key, value)| (format!("X-Amz-Meta-{key}"), value))
+            .collect();
+
+        let parsed = EnvelopeMetadata::from_user_metadata(&user_metadata).unwrap();
+        assert_eq!(parsed.wrapped_key, metadata.wrapped_key);
+        assert_eq!(parsed.key_id, metadata.key_id);
+        assert!(parsed.chunked);
+    }
+
+    #[test]
+    fn envelope_decrypts_whole_objects() {
+        let plain_text = get_plain_text(1000);
+        let (_, mut metadata) = encrypt(&[]);
+        let user_key = Crypto::get_user_key();
+        let data_key: [u8; 32] = Crypto::open(&user_key, &metadata.wrapped_key)
+            .try_into()
+            .unwrap();
+        metadata.chunked = false;
+
+        let cipher_text = Crypto::seal(&data_key, &plain_text);
+        assert_eq!(decrypt(&cipher_text, &metadata).unwrap(), plain_text);
+    }
+
+    #[test]
+    fn envelope_detects_truncation() {
+        let plain_text = get_plain_text(2 * ENVELOPE_CHUNK_SIZE + 1);
+        let (mut cipher_text, metadata) = encrypt(&plain_text);
+
+        // Drop the last chunk, so that the previous one looks like the last
+        cipher_text.truncate(cipher_text.len() - 1 - TAG_SIZE);
+        assert!(matches!(
+            decrypt(&cipher_text, &metadata),
+            Err(EnvelopeError::Decrypt)
+        ));
+    }
+
+    #[test]
+    fn envelope_detects_corruption() {
+        let plain_text = get_plain_text(ENVELOPE_CHUNK_SIZE + 1);
+        let (mut cipher_text, metadata) = encrypt(&plain_text);
+
+        cipher_text[NONCE_PREFIX_SIZE] ^= 1;
+        assert!(matches!(
+            decrypt(&cipher_text, &metadata),
+            Err(EnvelopeError::Decrypt)
+        ));
+    }
+}
@@ -1,6 +1,5 @@
-use crate::tasks::s3::S3;
-use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
-use aes_gcm::{Aes256Gcm, Key};
+use crate::tasks::crypto::{Crypto, DEMO_SYM_KEY};
+use crate::tasks::storage::Storage;
 use rabe;
 use serde::{Deserialize, Serialize};
 use serde_yaml;
@@ -8,13 +7,6 @@ use sha2::{Digest, Sha256};
 use std::fs::File;
 use std::io::Read;
 
-// FIXME(tless-prod): symmetric key is currently hardcoded. In production it
-// would be given to the user upon registration
-static DEMO_SYM_KEY: [u8; 32] = [
-    0xf0, 0x0d, 0x48, 0x2e, 0xca, 0x21, 0xfb, 0x13, 0xec, 0xf0, 0x01, 0x48, 0xba, 0x60, 0x01, 0x76,
-    0x6e, 0x56, 0xbb, 0xa5, 0xff, 0x9b, 0x11, 0x9d, 0xd6, 0xfa, 0x96, 0x39, 0x2b, 0x7c, 0x1a, 0x0d,
-];
-
 // Struct a node in our workflow DAG
 #[derive(Debug, Serialize, Deserialize)]
 struct DagFunc {
@@ -78,10 +70,12 @@ impl Dag {
         hex::encode(result)
     }
 
-    pub async fn upload(wflow_name: &str, yaml_path: &str) {
+    pub async fn upload(storage: &dyn Storage, wflow_name: &str, yaml_path: &str) {
         // Load the given DAG to a byte array, and upload it to storage
         let serialized_dag = Self::read_yaml_and_serialize(yaml_path);
-        S3::upload_bytes("tless", &format!("{wflow_name}/dag"), &serialized_dag).await;
+        storage
+            .put_bytes(&format!("{wflow_name}/dag"), &serialized_dag)
+            .await;
 
         // Calculate the hexstring of the hash of the DAG, to make it one
         // of our attributes for CP-ABE
@@ -106,19 +100,12 @@ impl Dag {
 
         // Encrypt it with the shared symmetric key, so that any TEE can use
         // the CP-ABE encryption/decryption context
-        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&DEMO_SYM_KEY));
-        let ctx_nonce = Aes256Gcm::generate_nonce(&mut OsRng);
-        let ctx_ct = cipher.encrypt(&ctx_nonce, serial_ctx).unwrap();
-        let mut encrypted_ctx = ctx_nonce.to_vec();
-        encrypted_ctx.extend_from_slice(&ctx_ct);
+        let encrypted_ctx = Crypto::seal(&DEMO_SYM_KEY, serial_ctx);
 
         // Serialize and upload context to S3
-        S3::upload_bytes(
-            "tless",
-            &format!("{wflow_name}/crypto/cp-abe-ctx"),
-            &encrypted_ctx,
-        )
-        .await;
+        storage
+            .put_bytes(&format!("{wflow_name}/crypto/cp-abe-ctx"), &encrypted_ctx)
+            .await;
 
         // FIXME(tless-prod): here we define a few values that are crucial in
         // the bootstrapping of the CP-ABE context. This should be set by the
@@ -150,22 +137,14 @@ impl Dag {
         };
 
         // Encapsulate the cipher-text in a symmetric encryption payload
-        let abe_ct_nonce = Aes256Gcm::generate_nonce(&mut OsRng);
-        let abe_ct = cipher
-            .encrypt(&abe_ct_nonce, abe_ct_str.as_bytes())
-            .unwrap();
-        let mut encrypted_abe_ct = abe_ct_nonce.to_vec();
-        encrypted_abe_ct.extend_from_slice(&abe_ct);
+        let encrypted_abe_ct = Crypto::seal(&DEMO_SYM_KEY, abe_ct_str.as_bytes());
 
         // Upload the certificate chain for the first function
         // TODO: right now, they all ready from the same. Must update in
         // accordance with the DAG
-        S3::upload_bytes(
-            "tless",
-            &format!("{wflow_name}/cert-chains/test"),
-            &encrypted_abe_ct,
-        )
-        .await;
+        storage
+            .put_bytes(&format!("{wflow_name}/cert-chains/test"), &encrypted_abe_ct)
+            .await;
 
         // TODO(encrypted-functions): to support encrypted functions, here we
         // would have to keep generating new policies, and encrypting each
@@ -1,21 +1,37 @@
 use crate::env::Env;
+use crate::tasks::cluster::{self, Cluster, ClusterBackend};
 use crate::tasks::docker::{Docker, DockerContainer};
-use crate::tasks::s3::S3;
-use crate::tasks::workflows::{AvailableWorkflow, Workflows};
+use crate::tasks::s3::{WaitForKeyOpts, S3};
+use crate::tasks::storage::{self, Storage};
+use crate::tasks::workflows::{AvailableWorkflow, WorkflowTrigger, Workflows};
 use chrono::{DateTime, Duration, TimeZone, Utc};
 use clap::{Args, ValueEnum};
-use csv::ReaderBuilder;
 use indicatif::{ProgressBar, ProgressStyle};
-use log::{debug, error};
+use log::{debug, error, info, warn};
 use plotters::prelude::*;
-use serde::Deserialize;
 use shell_words;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::str::FromStr;
-use std::{collections::BTreeMap, env, fmt, fs, io::Write, str, thread, time};
-
-static EVAL_BUCKET_NAME: &str = "tless";
+use std::{collections::BTreeMap, env, fmt, fs, io::Write, time};
+
+mod checkpoint;
+mod cold_start_breakdown;
+mod concurrent_runs;
+mod dataset_size;
+mod results;
+mod spec;
+mod stats;
+mod throughput;
+mod utilisation;
+
+pub use spec::ExperimentSpec;
+
+use checkpoint::Checkpoint;
+use cold_start_breakdown::ColdStartRecorder;
+use results::{ExecutionResult, ExecutionStatus, ResultRecord};
+use stats::{FailureRate, SampleStats};
+use utilisation::UtilisationSampler;
 
 #[derive(Clone, Debug, ValueEnum, PartialEq, Eq, PartialOrd, Ord)]
 pub enum EvalBaseline {
@@ -86,6 +102,10 @@ pub enum EvalExperiment {
     E2eLatency,
     E2eLatencyCold,
     ScaleUpLatency,
+    Throughput,
+    ConcurrentRuns,
+    ColdStartBreakdown,
+    DatasetSize,
 }
 
 impl fmt::Display for EvalExperiment {
@@ -94,26 +114,299 @@ impl fmt::Display for EvalExperiment {
             EvalExperiment::E2eLatency => write!(f, "e2e-latency"),
             EvalExperiment::E2eLatencyCold => write!(f, "e2e-latency-cold"),
             EvalExperiment::ScaleUpLatency => write!(f, "scale-up-latency"),
+            EvalExperiment::Throughput => write!(f, "throughput"),
+            EvalExperiment::ConcurrentRuns => write!(f, "concurrent-runs"),
+            EvalExperiment::ColdStartBreakdown => write!(f, "cold-start-breakdown"),
+            EvalExperiment::DatasetSize => write!(f, "dataset-size"),
+        }
+    }
+}
+
+impl FromStr for EvalExperiment {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<EvalExperiment, Self::Err> {
+        match input {
+            "e2e-latency" => Ok(EvalExperiment::E2eLatency),
+            "e2e-latency-cold" => Ok(EvalExperiment::E2eLatencyCold),
+            "scale-up-latency" => Ok(EvalExperiment::ScaleUpLatency),
+            "throughput" => Ok(EvalExperiment::Throughput),
+            "concurrent-runs" => Ok(EvalExperiment::ConcurrentRuns),
+            "cold-start-breakdown" => Ok(EvalExperiment::ColdStartBreakdown),
+            "dataset-size" => Ok(EvalExperiment::DatasetSize),
+            _ => Err(()),
         }
     }
 }
 
+// Defaults of the run arguments, shared with experiment specifications
+const DEFAULT_NUM_REPEATS: u32 = 2;
+const DEFAULT_NUM_WARMUP_REPEATS: u32 = 1;
+const DEFAULT_SCALE_UP_RANGE: u32 = 10;
+const DEFAULT_CONCURRENCY: [u32; 4] = [1, 2, 4, 8];
+const DEFAULT_DURATION_SECS: u64 = 120;
+const DEFAULT_WAIT_TIMEOUT_SECS: u64 = 1800;
+const DEFAULT_DEPLOY_TIMEOUT_SECS: u64 = 600;
+const DEFAULT_NUM_RETRIES: u32 = 1;
+const DEFAULT_STORAGE: &str = "s3://tless";
+const DEFAULT_SAMPLE_INTERVAL_MS: u64 = 1000;
+
 #[derive(Debug, Args)]
 pub struct EvalRunArgs {
     #[arg(short, long, num_args = 1.., value_name = "BASELINE")]
     baseline: Vec<EvalBaseline>,
-    #[arg(long, default_value = "2")]
+    /// Only run these workflows (default: all the experiment supports)
+    #[arg(short, long, num_args = 1.., value_name = "WORKFLOW")]
+    workflow: Vec<AvailableWorkflow>,
+    #[arg(long, default_value_t = DEFAULT_NUM_REPEATS)]
     num_repeats: u32,
-    #[arg(long, default_value = "1")]
+    #[arg(long, default_value_t = DEFAULT_NUM_WARMUP_REPEATS)]
     num_warmup_repeats: u32,
-    #[arg(long, default_value = "10")]
+    #[arg(long, default_value_t = DEFAULT_SCALE_UP_RANGE)]
     scale_up_range: u32,
+    /// Number of clients invoking the workflow at the same time, for each
+    /// step of the throughput experiment, or number of runs we start at the
+    /// same time, for each step of the concurrent-runs experiment
+    #[arg(long, value_delimiter = ',', default_values_t = DEFAULT_CONCURRENCY)]
+    concurrency: Vec<u32>,
+    /// Time we keep the clients busy for, at each concurrency level
+    #[arg(long, default_value_t = DEFAULT_DURATION_SECS)]
+    duration_secs: u64,
+    /// Input sizes (i.e. number of trades, images, or files) for each step
+    /// of the dataset-size experiment (default: per-workflow sizes)
+    #[arg(long, value_delimiter = ',')]
+    input_sizes: Vec<u32>,
+    /// Maximum time a single run may take, from triggering the workflow to
+    /// it finishing, before we record it as timed-out
+    #[arg(long, default_value_t = DEFAULT_WAIT_TIMEOUT_SECS)]
+    wait_timeout_secs: u64,
+    /// Maximum time to wait for the Knative services of a workflow to be
+    /// ready after deploying it
+    #[arg(long, default_value_t = DEFAULT_DEPLOY_TIMEOUT_SECS)]
+    deploy_timeout_secs: u64,
+    /// Number of times we re-try a failed or timed-out run. We record every
+    /// attempt, so that plots can report failure rates
+    #[arg(long, default_value_t = DEFAULT_NUM_RETRIES)]
+    num_retries: u32,
+    /// Poll S3 for completion keys instead of listening to bucket
+    /// notifications
+    #[arg(long)]
+    no_s3_notifications: bool,
+    /// Storage backend for workflow state (s3://<bucket> or file://<path>)
+    #[arg(long, default_value = DEFAULT_STORAGE)]
+    storage: String,
+    /// Envelope-encrypt the workflow datasets we upload (s3:// only).
+    /// Workflow functions can not decrypt them yet, so every run will fail
+    /// until they do
+    #[arg(long)]
+    encrypt_state: bool,
+    /// Kubernetes backend to deploy Knative workflows to. The fake backend
+    /// keeps everything in memory, to check the orchestration of a sweep
+    #[arg(long, default_value_t = ClusterBackend::Kube)]
+    cluster: ClusterBackend,
+    /// URL of the Knative in-memory channel dispatcher, to send the events
+    /// that trigger workflows to. By default, we port-forward to it
+    #[arg(long, value_name = "URL")]
+    trigger_url: Option<String>,
+    /// Keep the outputs of the last run of each workflow, and print
+    /// presigned links to them that expire after this long (e.g. 12h). With
+    /// Knative, links only work until we delete the in-cluster MinIO at the
+    /// end of the experiment
+    #[arg(long, value_name = "EXPIRY")]
+    presign_outputs: Option<String>,
+    /// Record the CPU, memory and network usage of the workflow pods (or of
+    /// the local Faasm processes) during each run
+    #[arg(long)]
+    sample_utilisation: bool,
+    /// Time between utilisation samples
+    #[arg(long, default_value_t = DEFAULT_SAMPLE_INTERVAL_MS)]
+    sample_interval_ms: u64,
+    /// Resume an interrupted run from its checkpoint, skipping the runs that
+    /// already finished and appending to the existing results files
+    #[arg(long)]
+    resume: bool,
+    /// Per-workflow Faasm command lines, overriding the defaults
+    #[arg(skip)]
+    faasm_cmdlines: BTreeMap<AvailableWorkflow, String>,
+    /// Per-workflow input sizes, overriding the defaults
+    #[arg(skip)]
+    workflow_input_sizes: BTreeMap<AvailableWorkflow, Vec<u32>>,
+    /// Experiment specification these arguments come from, if any
+    #[arg(skip)]
+    spec: Option<String>,
 }
 
-pub struct ExecutionResult {
-    start_time: DateTime<Utc>,
-    end_time: DateTime<Utc>,
-    iter: u32,
+impl EvalRunArgs {
+    /// Check that the arguments make sense for an experiment, and return a
+    /// list of errors otherwise
+    fn validate(&self, exp: &EvalExperiment) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.baseline.is_empty() {
+            errors.push("no baselines to run".to_string());
+        }
+        if self.num_repeats == 0 {
+            errors.push("num-repeats must be at least 1".to_string());
+        }
+        if self.wait_timeout_secs == 0 {
+            errors.push("wait-timeout-secs must be at least 1".to_string());
+        }
+        if *exp == EvalExperiment::Throughput || *exp == EvalExperiment::ConcurrentRuns {
+            if self.concurrency.is_empty() || self.concurrency.contains(&0) {
+                errors.push("concurrency levels must be at least 1".to_string());
+            }
+        }
+        if *exp == EvalExperiment::Throughput && self.duration_secs == 0 {
+            errors.push("duration-secs must be at least 1".to_string());
+        }
+        if self.sample_utilisation {
+            // Runs overlap, so we could not tell their usage apart
+            if *exp == EvalExperiment::Throughput || *exp == EvalExperiment::ConcurrentRuns {
+                errors.push(format!("sample-utilisation is not supported for {exp}"));
+            }
+            if self.sample_interval_ms == 0 {
+                errors.push("sample-interval-ms must be at least 1".to_string());
+            }
+        }
+        if *exp == EvalExperiment::ColdStartBreakdown {
+            // Faasm does not run functions in pods
+            for baseline in self.baseline.iter().filter(|b| Eval::is_faasm_baseline(b)) {
+                errors.push(format!("baseline {baseline} does not support {exp}"));
+            }
+        }
+        if *exp == EvalExperiment::DatasetSize {
+            // An input size of 0 stands for the default input
+            for workflow in self.get_workflows(exp) {
+                let input_sizes = self.get_input_sizes(&workflow);
+                if input_sizes.is_empty() || input_sizes.contains(&0) {
+                    errors.push(format!("input sizes for {workflow} must be at least 1"));
+                }
+
+                // We can only build inputs as large as the dataset we take
+                // them from. If we do not have it, uploading fails anyway
+                if let Some(max_size) = Workflows::get_max_input_size(&workflow, &Env::proj_root())
+                {
+                    for input_size in input_sizes.iter().filter(|size| **size > max_size) {
+                        errors.push(format!(
+                            "input size {input_size} for {workflow} is larger than its dataset ({max_size} {})",
+                            Workflows::get_input_unit(&workflow)
+                        ));
+                    }
+                }
+            }
+        }
+        if *exp == EvalExperiment::ScaleUpLatency {
+            // Only FINRA can change its number of functions
+            for workflow in &self.workflow {
+                if *workflow != AvailableWorkflow::Finra {
+                    errors.push(format!("workflow {workflow} does not support {exp}"));
+                }
+            }
+
+            // Must match the range we can plot
+            if !(1..=10).contains(&self.scale_up_range) {
+                errors.push(format!(
+                    "scale-up-range must be between 1 and 10 (got: {})",
+                    self.scale_up_range
+                ));
+            }
+        }
+        if !self.storage.starts_with("s3://") && !self.storage.starts_with("file://") {
+            errors.push(format!(
+                "storage must be s3://<bucket> or file://<path> (got: {})",
+                self.storage
+            ));
+        }
+        if self.encrypt_state && !self.storage.starts_with("s3://") {
+            errors.push(format!(
+                "encrypt-state needs an s3:// storage backend (got: {})",
+                self.storage
+            ));
+        }
+        if let Some(url) = &self.trigger_url {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                errors.push(format!("trigger-url must be an http(s) URL (got: {url})"));
+            }
+        }
+        if let Some(expiry) = &self.presign_outputs {
+            if let Err(e) = S3::try_parse_expiry(expiry) {
+                errors.push(e);
+            }
+        }
+
+        errors
+    }
+
+    /// Workflows to run for an experiment
+    fn get_workflows(&self, exp: &EvalExperiment) -> Vec<AvailableWorkflow> {
+        if !self.workflow.is_empty() {
+            return self.workflow.clone();
+        }
+
+        match exp {
+            // For the scale-up latency, we only run the FINRA workflow
+            EvalExperiment::ScaleUpLatency => vec![AvailableWorkflow::Finra],
+            _ => AvailableWorkflow::iter_variants().cloned().collect(),
+        }
+    }
+
+    /// Number of steps we checkpoint for each workflow and baseline. For
+    /// the throughput experiment, a step is a whole concurrency level, and
+    /// for the concurrent-runs experiment, one repeat at a concurrency level.
+    /// Likewise, for the dataset-size experiment, a step is one repeat at an
+    /// input size
+    fn get_num_steps(&self, exp: &EvalExperiment, workflow: &AvailableWorkflow) -> u32 {
+        match exp {
+            EvalExperiment::Throughput => self.concurrency.len() as u32,
+            EvalExperiment::ConcurrentRuns => self.concurrency.len() as u32 * self.num_repeats,
+            EvalExperiment::DatasetSize => {
+                self.get_input_sizes(workflow).len() as u32 * self.num_repeats
+            }
+            _ => self.num_repeats,
+        }
+    }
+
+    fn get_faasm_cmdline(&self, workflow: &AvailableWorkflow) -> String {
+        match self.faasm_cmdlines.get(workflow) {
+            Some(cmdline) => cmdline.clone(),
+            None => Workflows::get_faasm_cmdline(workflow, 0),
+        }
+    }
+
+    /// Input sizes we sweep for a workflow in the dataset-size experiment
+    fn get_input_sizes(&self, workflow: &AvailableWorkflow) -> Vec<u32> {
+        if !self.input_sizes.is_empty() {
+            return self.input_sizes.clone();
+        }
+
+        match self.workflow_input_sizes.get(workflow) {
+            Some(input_sizes) => input_sizes.clone(),
+            None => Workflows::get_default_input_sizes(workflow),
+        }
+    }
+
+    fn get_utilisation_sampler<'a>(
+        &self,
+        cluster: Option<&'a dyn Cluster>,
+    ) -> Option<UtilisationSampler<'a>> {
+        if !self.sample_utilisation {
+            return None;
+        }
+
+        let interval = time::Duration::from_millis(self.sample_interval_ms);
+        Some(match cluster {
+            Some(cluster) => UtilisationSampler::pods(cluster, interval),
+            None => UtilisationSampler::faasm_processes(interval),
+        })
+    }
+
+    fn wait_for_key_opts(&self) -> WaitForKeyOpts {
+        WaitForKeyOpts {
+            timeout: Some(time::Duration::from_secs(self.wait_timeout_secs)),
+            use_notifications: !self.no_s3_notifications,
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -167,8 +460,12 @@ impl Eval {
         match exp {
             EvalExperiment::E2eLatency
             | EvalExperiment::E2eLatencyCold
-            | EvalExperiment::ScaleUpLatency => {
-                writeln!(file, "Run,TimeMs").expect("invrs(eval): failed to write to file");
+            | EvalExperiment::ScaleUpLatency
+            | EvalExperiment::Throughput
+            | EvalExperiment::ConcurrentRuns
+            | EvalExperiment::ColdStartBreakdown
+            | EvalExperiment::DatasetSize => {
+                results::write_header(&mut file);
             }
         }
     }
@@ -179,6 +476,7 @@ impl Eval {
         baseline: &EvalBaseline,
         result: &ExecutionResult,
         scale_up_factor: u32,
+        sweep_id: &str,
     ) {
         let mut file = fs::OpenOptions::new()
             .read(true)
@@ -195,10 +493,28 @@ impl Eval {
         match exp {
             EvalExperiment::E2eLatency
             | EvalExperiment::E2eLatencyCold
-            | EvalExperiment::ScaleUpLatency => {
+            | EvalExperiment::ScaleUpLatency
+            | EvalExperiment::Throughput
+            | EvalExperiment::ConcurrentRuns
+            | EvalExperiment::ColdStartBreakdown
+            | EvalExperiment::DatasetSize => {
                 let duration: Duration = result.end_time - result.start_time;
-                writeln!(file, "{},{}", result.iter, duration.num_milliseconds())
-                    .expect("invrs(eval): failed to write to file");
+                let record = ResultRecord {
+                    run: result.iter,
+                    time_ms: duration.num_milliseconds().max(0) as u64,
+                    start_time: Some(result.start_time),
+                    end_time: Some(result.end_time),
+                    baseline: Some(baseline.to_string()),
+                    workflow: Some(workflow.to_string()),
+                    scale_up_factor: Some(scale_up_factor),
+                    warmup: result.warmup,
+                    status: result.status.clone(),
+                    run_id: Some(result.run_id.clone()),
+                    concurrency: Some(result.concurrency),
+                    input_size: Some(result.input_size),
+                    sweep_id: Some(sweep_id.to_string()),
+                };
+                results::write_record(&mut file, &record);
             }
         }
     }
@@ -240,61 +556,6 @@ impl Eval {
     // Run with Knative Functions
     // ------------------------------------------------------------------------
 
-    fn get_kubectl_cmd() -> String {
-        // For the moment, we literally run the `kubectl` command installed
-        // as part of `coco-serverless`. We may change this in the future
-        match env::var("COCO_SOURCE") {
-            Ok(value) => format!("{value}/bin/kubectl"),
-            Err(_) => panic!("invrs(eval): failed to read COCO_SOURCE env. var"),
-        }
-    }
-
-    fn run_kubectl_cmd(cmd: &str) -> String {
-        debug!("{}(eval): running kubectl command: {cmd}", Env::SYS_NAME);
-        let args: Vec<&str> = cmd.split_whitespace().collect();
-
-        let output = Command::new(Self::get_kubectl_cmd())
-            .args(&args[0..])
-            .output()
-            .expect("invrs(eval): failed to execute kubectl command");
-
-        String::from_utf8(output.stdout)
-            .expect("invrs(eval): failed to convert kube command output to string")
-    }
-
-    fn wait_for_pods(namespace: &str, label: &str, num_expected: usize) {
-        loop {
-            thread::sleep(time::Duration::from_secs(2));
-
-            let output = Self::run_kubectl_cmd(&format!("-n {namespace} get pods -l {label} -o jsonpath='{{..status.conditions[?(@.type==\"Ready\")].status}}'"));
-            let values: Vec<&str> = output.split_whitespace().collect();
-
-            debug!(
-                "{}(eval): waiting for {num_expected} pods (label: {label}) to be ready...",
-                Env::SYS_NAME
-            );
-            if values.len() != num_expected {
-                debug!(
-                    "{}(eval): not enough pods: {} != {num_expected}",
-                    Env::SYS_NAME,
-                    values.len()
-                );
-                continue;
-            }
-
-            if !values.iter().all(|&item| item == "'True'") {
-                debug!("{}(eval): not enough pods in 'Ready' state", Env::SYS_NAME);
-                continue;
-            }
-
-            break;
-        }
-    }
-
-    fn wait_for_pod(namespace: &str, label: &str) {
-        Self::wait_for_pods(namespace, label, 1);
-    }
-
     fn template_yaml(yaml_path: PathBuf, env_vars: BTreeMap<&str, &str>) -> String {
         let yaml_content = fs::read_to_string(yaml_path).expect("invrs(eval): failed to read yaml");
 
@@ -325,12 +586,12 @@ impl Eval {
         String::from_utf8(result.stdout).expect("Failed to convert envsubst output to string")
     }
 
-    fn deploy_workflow(workflow: &AvailableWorkflow, baseline: &EvalBaseline) {
+    fn get_workflow_manifest(workflow: &AvailableWorkflow, baseline: &EvalBaseline) -> String {
         let mut workflow_yaml = Workflows::get_root();
         workflow_yaml.push(format!("{workflow}"));
         workflow_yaml.push("knative");
         workflow_yaml.push("workflow.yaml");
-        let templated_yaml = Self::template_yaml(
+        Self::template_yaml(
             workflow_yaml,
             BTreeMap::from([
                 (
@@ -351,256 +612,285 @@ impl Eval {
                     },
                 ),
             ]),
-        );
+        )
+    }
 
-        let mut kubectl = Command::new(Self::get_kubectl_cmd())
-            .arg("apply")
-            .arg("-f")
-            .arg("-")
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()
-            .expect("invrs(eval): failed to start kubectl apply");
+    fn get_common_manifest() -> String {
+        let mut k8s_common_path = Workflows::get_root();
+        k8s_common_path.push("k8s_common.yaml");
+        fs::read_to_string(k8s_common_path).expect("tlessctl(eval): failed to read k8s_common.yaml")
+    }
 
-        kubectl
-            .stdin
-            .as_mut()
-            .expect("invrs(eval): failed to open stdin for kubectl")
-            .write_all(templated_yaml.as_bytes())
-            .expect("invrs(eval): failed to feed kubectl through stdin");
+    /// Deploy a workflow, and wait for all its workloads (as listed in its
+    /// manifest) to be ready. Returns false if any of them is not ready in
+    /// time
+    async fn deploy_workflow(
+        cluster: &dyn Cluster,
+        workflow: &AvailableWorkflow,
+        baseline: &EvalBaseline,
+        timeout: time::Duration,
+    ) -> bool {
+        let manifest = Self::get_workflow_manifest(workflow, baseline);
+        cluster.apply(&manifest).await;
+
+        let workloads = cluster::get_workloads(&manifest);
+        debug!(
+            "{}(eval): waiting for {} workloads of {workflow} to be ready",
+            Env::SYS_NAME,
+            workloads.len()
+        );
 
-        // Check if the kubectl command succeeded
-        kubectl
-            .wait_with_output()
-            .expect("invrs(eval): failed to run kubectl command");
-
-        // Specific per-workflow wait command
-        match workflow {
-            AvailableWorkflow::Finra => {
-                Self::wait_for_pod("tless", "tless.workflows/name=finra-fetch-private");
-                Self::wait_for_pod("tless", "tless.workflows/name=finra-fetch-public");
-                Self::wait_for_pod("tless", "tless.workflows/name=finra-merge");
-            }
-            AvailableWorkflow::MlTraining => {
-                Self::wait_for_pod("tless", "tless.workflows/name=ml-training-partition");
-                Self::wait_for_pod("tless", "tless.workflows/name=ml-training-validation");
-            }
-            AvailableWorkflow::MlInference => {
-                Self::wait_for_pod("tless", "tless.workflows/name=ml-inference-load");
-                Self::wait_for_pod("tless", "tless.workflows/name=ml-inference-partition");
-            }
-            AvailableWorkflow::WordCount => {
-                Self::wait_for_pod("tless", "tless.workflows/name=word-count-splitter");
-                Self::wait_for_pod("tless", "tless.workflows/name=word-count-reducer");
+        let not_ready = cluster.wait_for_workloads(&workloads, timeout).await;
+        if !not_ready.is_empty() {
+            error!(
+                "{}(eval): {} workloads of {workflow} not ready after {timeout:?}:",
+                Env::SYS_NAME,
+                not_ready.len()
+            );
+            for workload in &not_ready {
+                error!("{}(eval): - {workload}", Env::SYS_NAME);
             }
+            return false;
         }
+
+        true
     }
 
-    fn delete_workflow(workflow: &AvailableWorkflow, baseline: &EvalBaseline) {
-        // Common deploy mechanism
-        let mut workflow_yaml = Workflows::get_root();
-        workflow_yaml.push(format!("{workflow}"));
-        workflow_yaml.push("knative");
-        workflow_yaml.push("workflow.yaml");
-        let templated_yaml = Self::template_yaml(
-            workflow_yaml,
-            BTreeMap::from([
-                (
-                    "RUNTIME_CLASS_NAME",
-                    match baseline {
-                        EvalBaseline::Knative => "kata-qemu",
-                        EvalBaseline::CcKnative | EvalBaseline::TlessKnative => "kata-qemu-sev",
-                        _ => panic!("woops"),
-                    },
-                ),
-                ("TLESS_VERSION", &Env::get_version().unwrap()),
-                (
-                    "TLESS_MODE",
-                    match baseline {
-                        EvalBaseline::Knative | EvalBaseline::CcKnative => "off",
-                        EvalBaseline::TlessKnative => "on",
-                        _ => panic!("woops"),
-                    },
-                ),
-            ]),
-        );
+    async fn delete_workflow(
+        cluster: &dyn Cluster,
+        workflow: &AvailableWorkflow,
+        baseline: &EvalBaseline,
+    ) {
+        cluster
+            .delete(&Self::get_workflow_manifest(workflow, baseline))
+            .await;
+    }
 
-        let mut kubectl = Command::new(Self::get_kubectl_cmd())
-            .arg("delete")
-            .arg("--wait=true")
-            .arg("--cascade=foreground")
-            .arg("-f")
-            .arg("-")
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()
-            .expect("invrs(eval): failed to start kubectl apply");
+    /// Wait until all workflow pods are gone, and only MinIO is left.
+    /// Returns false if there are pods left after the timeout
+    async fn wait_for_scale_to_zero(
+        cluster: &dyn Cluster,
+        timeout: Option<time::Duration>,
+    ) -> bool {
+        let wait = async {
+            loop {
+                let pods = cluster
+                    .list_pods("tless", "tless.workflows/name!=minio")
+                    .await;
+                debug!(
+                    "tlessctl: waiting for a scale-down: {} pods left",
+                    pods.len()
+                );
 
-        kubectl
-            .stdin
-            .as_mut()
-            .expect("invrs(eval): failed to open stdin for kubectl")
-            .write_all(templated_yaml.as_bytes())
-            .expect("invrs(eval): failed to feed kubectl through stdin");
+                if pods.is_empty() {
+                    break;
+                }
 
-        kubectl
-            .wait_with_output()
-            .expect("invrs(eval): failed to run kubectl command");
+                tokio::time::sleep(time::Duration::from_secs(2)).await;
+            }
+        };
+
+        match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, wait).await.is_ok(),
+            None => {
+                wait.await;
+                true
+            }
+        }
     }
 
-    async fn wait_for_scale_to_zero() {
-        loop {
-            let output = Self::run_kubectl_cmd(&format!("-n tless get pods -o jsonpath={{..status.conditions[?(@.type==\"Ready\")].status}}"));
-            debug!("tlessctl: waiting for a scale-down: out: {output}");
-            let values: Vec<&str> = output.split_whitespace().collect();
+    /// Whether we should re-try a run after an attempt
+    fn should_retry(
+        workflow: &AvailableWorkflow,
+        iter: u32,
+        result: &ExecutionResult,
+        attempt: u32,
+        num_retries: u32,
+    ) -> bool {
+        if result.status == ExecutionStatus::Ok {
+            return false;
+        }
 
-            if values.len() == 1 {
-                break;
+        if attempt >= num_retries {
+            error!(
+                "{}(eval): run {iter} of {workflow} {} after {} attempt(s), moving on",
+                Env::SYS_NAME,
+                result.status,
+                attempt + 1
+            );
+            return false;
+        }
+
+        warn!(
+            "{}(eval): run {iter} of {workflow} {}, re-trying ({}/{num_retries})",
+            Env::SYS_NAME,
+            result.status,
+            attempt + 1
+        );
+        true
+    }
+
+    /// Run a command to completion, killing it if it takes longer than the
+    /// timeout. Errors are returned as the status of the run they belong to,
+    /// so that one bad run does not bring down a whole sweep
+    async fn run_cmd_with_timeout(
+        mut cmd: tokio::process::Command,
+        timeout: time::Duration,
+    ) -> Result<String, ExecutionStatus> {
+        let program = cmd.as_std().get_program().to_string_lossy().to_string();
+        cmd.kill_on_drop(true);
+
+        let output = match tokio::time::timeout(timeout, cmd.output()).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => {
+                error!("{}(eval): failed to execute {program}: {e}", Env::SYS_NAME);
+                return Err(ExecutionStatus::Failed);
+            }
+            Err(_) => {
+                error!(
+                    "{}(eval): {program} timed out after {timeout:?}",
+                    Env::SYS_NAME
+                );
+                return Err(ExecutionStatus::TimedOut);
             }
+        };
 
-            thread::sleep(time::Duration::from_secs(2));
+        if !output.status.success() {
+            error!(
+                "{}(eval): {program} exited with error ({}): {}",
+                Env::SYS_NAME,
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            );
+            return Err(ExecutionStatus::Failed);
         }
+
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
     }
 
-    /// Run workflow once, and return result depending on the experiment
+    /// Run workflow once, and return result depending on the experiment.
+    /// If the workflow does not finish, the status in the result says why
     async fn run_workflow_once(
         workflow: &AvailableWorkflow,
         exp: &EvalExperiment,
         scale_up_factor: u32,
+        storage: &dyn Storage,
+        cluster: &dyn Cluster,
+        trigger: &WorkflowTrigger,
+        wait_opts: &WaitForKeyOpts,
+        run_id: &str,
+        keep_outputs: bool,
     ) -> ExecutionResult {
         let mut exp_result = ExecutionResult {
-            start_time: Utc::now(),
-            end_time: Utc::now(),
-            iter: 0,
+            run_id: run_id.to_string(),
+            ..Default::default()
         };
 
-        // Common trigger mechanism
-        let mut trigger_cmd = Workflows::get_root();
-        trigger_cmd.push(format!("{workflow}"));
-        trigger_cmd.push("knative");
-        trigger_cmd.push("curl_cmd.sh");
-        let output = match exp {
-            EvalExperiment::ScaleUpLatency => Command::new(trigger_cmd.clone())
-                .env("OVERRIDE_NUM_AUDIT_FUNCS", scale_up_factor.to_string())
-                .output()
-                .expect("tlessctl(eval): failed to execute trigger command"),
-            _ => Command::new(trigger_cmd.clone())
-                .output()
-                .expect("tlessctl(eval): failed to execute trigger command"),
-        };
-
-        match output.status.code() {
-            Some(0) => {
-                debug!("{trigger_cmd:?}: executed succesfully");
-            }
-            Some(code) => {
-                let stderr = str::from_utf8(&output.stderr)
-                    .unwrap_or("tlessctl(eval): failed to get stderr");
-                panic!("{trigger_cmd:?}: exited with error (code: {code}): {stderr}");
-            }
-            None => {
-                let stderr = str::from_utf8(&output.stderr)
-                    .unwrap_or("tlessctl(eval): failed to get stderr");
-                panic!("{trigger_cmd:?}: failed: {stderr}");
-            }
-        };
-
-        // Specific per-workflow completion detection
-        match workflow {
-            AvailableWorkflow::Finra => {
-                let result_key = format!("{workflow}/outputs/merge/results.txt");
-
-                match S3::wait_for_key(EVAL_BUCKET_NAME, result_key.as_str()).await {
-                    Some(time) => {
-                        exp_result.end_time = time;
-                        S3::clear_object(EVAL_BUCKET_NAME, result_key.as_str()).await;
-
-                        // For FINRA we also need to delete two other files
-                        // that we await on throughout workflow execution
-                        S3::clear_object(EVAL_BUCKET_NAME, "finra/outputs/fetch-public/trades")
-                            .await;
-                        S3::clear_object(EVAL_BUCKET_NAME, "finra/outputs/fetch-private/portfolio")
-                            .await;
-                    }
-                    None => error!("invrs(eval): timed-out waiting for FINRA workload to finish"),
-                }
-            }
-            AvailableWorkflow::MlTraining => {
-                let result_key = format!("{workflow}/outputs/done.txt");
-
-                match S3::wait_for_key(EVAL_BUCKET_NAME, result_key.as_str()).await {
-                    Some(time) => {
-                        exp_result.end_time = time;
-                        S3::clear_object(EVAL_BUCKET_NAME, result_key.as_str()).await;
-                    }
-                    None => {
-                        error!("invrs(eval): timed-out waiting for ML training workload to finish")
-                    }
-                }
-            }
-            AvailableWorkflow::MlInference => {
-                // ML Inference finishes off in a scale-out, so we need to
-                // wait for as many functions as we have invoked
+        // Scaling down after a run gets its own time budget
+        let scale_down_timeout = wait_opts.timeout;
+
+        // Common trigger mechanism. The trigger counts towards the time
+        // budget of the run, so we only wait for the remainder
+        if let Err(e) = trigger.trigger(workflow, scale_up_factor, 0, run_id).await {
+            error!("{}(eval): failed to trigger {workflow}: {e}", Env::SYS_NAME);
+            exp_result.end_time = Utc::now();
+            exp_result.status = ExecutionStatus::Failed;
+            storage
+                .delete_prefix(&Workflows::get_exec_tokens_prefix(workflow, run_id))
+                .await;
+            return exp_result;
+        }
 
-                match S3::wait_for_key(
-                    EVAL_BUCKET_NAME,
-                    format!("{workflow}/outputs/predict/done.txt").as_str(),
-                )
-                .await
-                {
-                    Some(time) => {
-                        exp_result.end_time = time;
-                        // Remove all the outputs directory
-                        S3::clear_dir(
-                            EVAL_BUCKET_NAME.to_string(),
-                            "ml-inference/outputs".to_string(),
-                        )
-                        .await;
-                    }
-                    None => {
-                        error!("invrs(eval): timed-out waiting for ML training workload to finish")
-                    }
-                }
-            }
-            AvailableWorkflow::WordCount => {
-                // First wait for the result key
-                let result_key = format!("{workflow}/outputs/aggregated-results.txt");
-
-                match S3::wait_for_key(EVAL_BUCKET_NAME, result_key.as_str()).await {
-                    Some(time) => {
-                        // If succesful, remove the result key
-                        exp_result.end_time = time;
-                        S3::clear_object(EVAL_BUCKET_NAME, result_key.as_str()).await;
-                    }
-                    None => {
-                        error!("invrs(eval): timed-out waiting for Word Count workload to finish")
-                    }
-                }
+        let mut wait_opts = wait_opts.clone();
+        wait_opts.timeout = wait_opts.timeout.map(|timeout| {
+            let elapsed = (Utc::now() - exp_result.start_time)
+                .to_std()
+                .unwrap_or_default();
+            timeout.saturating_sub(elapsed)
+        });
+
+        // All workflows write their final output last, even the ones that
+        // finish off in a scale-out (e.g. ML inference)
+        let result_key = Workflows::get_output_key(workflow, run_id);
+        match storage.wait_for_key(&result_key, &wait_opts).await {
+            Ok(time) => exp_result.end_time = time,
+            Err(e) => {
+                error!(
+                    "{}(eval): error waiting for {workflow} to finish: {e}",
+                    Env::SYS_NAME
+                );
+                exp_result.end_time = Utc::now();
+                exp_result.status = ExecutionStatus::from(&e);
             }
         }
 
-        // Common-clean-up
-        S3::clear_dir(
-            EVAL_BUCKET_NAME.to_string(),
-            "{workflow}/exec-tokens".to_string(),
-        )
-        .await;
+        // Common-clean-up. All the state of the run lives under its run id,
+        // including partial outputs if the run did not finish
+        Self::cleanup_run(workflow, storage, run_id, keep_outputs).await;
 
         // Per-experiment, per-workflow clean-up
         match exp {
-            EvalExperiment::E2eLatencyCold => {
+            EvalExperiment::E2eLatencyCold | EvalExperiment::ColdStartBreakdown => {
                 debug!("tlesssctl: {exp}: waiting for scale-to-zero...");
-                Self::wait_for_scale_to_zero().await;
+                if !Self::wait_for_scale_to_zero(cluster, scale_down_timeout).await {
+                    // The next run would not start cold, so we do not count
+                    // this one either
+                    error!(
+                        "{}(eval): {workflow} did not scale to zero after {scale_down_timeout:?}",
+                        Env::SYS_NAME
+                    );
+                    if exp_result.status == ExecutionStatus::Ok {
+                        exp_result.status = ExecutionStatus::TimedOut;
+                    }
+                }
             }
             _ => debug!("tlessctl: {exp}: noting to clean-up after single execution"),
         }
 
         // Cautionary sleep between runs
-        thread::sleep(time::Duration::from_secs(5));
+        tokio::time::sleep(time::Duration::from_secs(5)).await;
 
-        return exp_result;
+        exp_result
+    }
+
+    /// Remove the state that one execution of a workflow leaves behind. We
+    /// only keep its outputs if asked to
+    async fn cleanup_run(
+        workflow: &AvailableWorkflow,
+        storage: &dyn Storage,
+        run_id: &str,
+        keep_outputs: bool,
+    ) {
+        storage
+            .delete_prefix(&Workflows::get_exec_tokens_prefix(workflow, run_id))
+            .await;
+        if !keep_outputs {
+            storage
+                .delete_prefix(&Workflows::get_outputs_prefix(workflow, run_id))
+                .await;
+        }
+    }
+
+    /// Print presigned links to the final output of a workflow execution, so
+    /// that it can be shared with people without MinIO credentials
+    async fn print_output_links(
+        workflow: &AvailableWorkflow,
+        run_id: &str,
+        storage: &dyn Storage,
+        expiry: &str,
+    ) {
+        let output_key = Workflows::get_output_key(workflow, run_id);
+        match storage
+            .presign_get(&output_key, S3::parse_expiry(expiry))
+            .await
+        {
+            Some(url) => println!("{workflow}: {output_key}: {url}"),
+            None => warn!(
+                "{}(eval): storage backend {} does not support presigned links (key: {output_key})",
+                Env::SYS_NAME,
+                storage.url()
+            ),
+        }
     }
 
     async fn run_knative_experiment(
@@ -608,47 +898,115 @@ impl Eval {
         args: &EvalRunArgs,
         args_offset: usize,
         scale_up_factor: u32,
+        checkpoint: &mut Checkpoint,
     ) {
         let baseline = args.baseline[args_offset].clone();
+        let storage = storage::from_url(&args.storage);
+        let wait_opts = args.wait_for_key_opts();
+        debug!(
+            "{}(eval): using storage backend: {}",
+            Env::SYS_NAME,
+            storage.url()
+        );
+
+        // Skip the workflows we finished before being interrupted
+        let workflows: Vec<AvailableWorkflow> = args
+            .get_workflows(exp)
+            .into_iter()
+            .filter(|workflow| {
+                let key = Checkpoint::get_key(workflow, &baseline, scale_up_factor);
+                !checkpoint.is_done(&key, args.get_num_steps(exp, workflow))
+            })
+            .collect();
+        if workflows.is_empty() {
+            info!(
+                "{}(eval): nothing left to run for {baseline} (scale-up factor: {scale_up_factor})",
+                Env::SYS_NAME
+            );
+            return;
+        }
 
         // First, deploy the common services
-        let mut k8s_common_path = Workflows::get_root();
-        k8s_common_path.push("k8s_common.yaml");
-        Self::run_kubectl_cmd(&format!("apply -f {}", k8s_common_path.display()));
+        let cluster = cluster::from_backend(&args.cluster).await;
+        cluster.apply(&Self::get_common_manifest()).await;
 
         // Wait for the MinIO pod to be ready
-        Self::wait_for_pod("tless", "tless.workflows/name=minio");
+        cluster
+            .wait_for_pods("tless", "tless.workflows/name=minio", 1)
+            .await;
 
         // Get the MinIO URL
-        let minio_url = Self::run_kubectl_cmd("-n tless get services -o jsonpath={.items[?(@.metadata.name==\"minio\")].spec.clusterIP}");
+        let minio_url = cluster
+            .get_service_cluster_ip("tless", "minio")
+            .await
+            .expect("tlessctl(eval): MinIO service has no cluster IP");
         unsafe {
             env::set_var("MINIO_URL", minio_url);
         }
 
-        // Upload the state for all workflows for the experiment
-        let workflow_iter = match exp {
-            // For the scale-up latency, we only run the FINRA workflow
-            EvalExperiment::ScaleUpLatency => [AvailableWorkflow::Finra].iter(),
-            _ => AvailableWorkflow::iter_variants(),
+        // Work-out where to send the events that trigger each workflow. The
+        // port-forward, if any, lives until the end of the experiment
+        let mut _port_forward = None;
+        let trigger_url = match &args.trigger_url {
+            Some(url) => url.clone(),
+            None => {
+                let port_forward = cluster
+                    .port_forward("knative-eventing", "imc-dispatcher", 80)
+                    .await;
+                let url = port_forward.url.clone();
+                _port_forward = Some(port_forward);
+                url
+            }
         };
-        // Workflows::upload_workflow_state(&AvailableWorkflow::MlInference, EVAL_BUCKET_NAME, true).await;
-        let pb = Self::get_progress_bar(
-            workflow_iter.len().try_into().unwrap(),
-            exp,
-            &baseline,
-            "state",
-        );
-        for workflow in workflow_iter.clone() {
-            Workflows::upload_workflow_state(workflow, EVAL_BUCKET_NAME, true).await;
+        let trigger = WorkflowTrigger::new(Some(trigger_url));
+
+        // Upload the state for all workflows for the experiment
+        let pb =
+            Self::get_progress_bar(workflows.len().try_into().unwrap(), exp, &baseline, "state");
+        for workflow in &workflows {
+            Workflows::upload_workflow_state(
+                workflow,
+                storage.as_ref(),
+                &Env::proj_root(),
+                true,
+                args.encrypt_state,
+            )
+            .await;
+            if *exp == EvalExperiment::DatasetSize {
+                for input_size in args.get_input_sizes(workflow) {
+                    Workflows::upload_workflow_input(
+                        workflow,
+                        storage.as_ref(),
+                        &Env::proj_root(),
+                        input_size,
+                        args.encrypt_state,
+                    )
+                    .await;
+                }
+            }
             pb.inc(1);
         }
         pb.finish();
 
+        let sampler = args.get_utilisation_sampler(Some(cluster.as_ref()));
+        let recorder = (*exp == EvalExperiment::ColdStartBreakdown)
+            .then(|| ColdStartRecorder::new(cluster.as_ref()));
+
         // Execute each workload individually
         // for workflow in vec![&AvailableWorkflow::MlInference] {
-        for workflow in workflow_iter.clone() {
-            // Initialise result file
-            Self::init_data_file(workflow, &exp, &baseline, scale_up_factor);
+        for workflow in &workflows {
+            // Initialise result file, unless we are resuming it
+            let key = Checkpoint::get_key(workflow, &baseline, scale_up_factor);
+            let progress = checkpoint.get(&key);
+            if !checkpoint.is_started(&key) {
+                Self::init_data_file(workflow, &exp, &baseline, scale_up_factor);
+                if args.sample_utilisation {
+                    Self::init_utilisation_file(workflow, &exp, &baseline, scale_up_factor);
+                }
+                if *exp == EvalExperiment::ColdStartBreakdown {
+                    Self::init_cold_start_file(workflow, &baseline);
+                }
+            }
 
             // Prepare progress bar for each different experiment
             let mut workflow_str = format!("{workflow}");
@@ -656,50 +1014,159 @@ impl Eval {
                 workflow_str = format!("{workflow}-{scale_up_factor}");
             }
             let pb = Self::get_progress_bar(
-                args.num_repeats.into(),
+                args.get_num_steps(exp, workflow).into(),
                 exp,
                 &baseline,
                 workflow_str.as_str(),
             );
+            pb.set_position(progress.num_repeats.into());
 
             // Deploy workflow
-            Self::deploy_workflow(workflow, &baseline);
-
-            // Do warm-up rounds
-            for _ in 0..args.num_warmup_repeats {
-                Self::run_workflow_once(workflow, exp, scale_up_factor).await;
-                S3::clear_dir(
-                    EVAL_BUCKET_NAME.to_string(),
-                    format!("{workflow}/exec-tokens"),
+            let deploy_timeout = time::Duration::from_secs(args.deploy_timeout_secs);
+            if !Self::deploy_workflow(cluster.as_ref(), workflow, &baseline, deploy_timeout).await {
+                // Skip the workflow, but keep going with the rest. As we have
+                // not recorded any runs, resuming will re-try it
+                error!(
+                    "{}(eval): skipping {workflow_str} for {baseline}",
+                    Env::SYS_NAME
+                );
+                Self::delete_workflow(cluster.as_ref(), workflow, &baseline).await;
+                pb.abandon();
+                continue;
+            }
+
+            // Do warm-up rounds (we record them, but plots ignore them)
+            for i in progress.num_warmups..args.num_warmup_repeats {
+                let mut result = Self::run_workflow_once(
+                    workflow,
+                    exp,
+                    scale_up_factor,
+                    storage.as_ref(),
+                    cluster.as_ref(),
+                    &trigger,
+                    &wait_opts,
+                    &Workflows::new_run_id(),
+                    false,
                 )
                 .await;
+
+                result.iter = i;
+                result.warmup = true;
+                Self::write_result_to_file(
+                    workflow,
+                    &exp,
+                    &baseline,
+                    &result,
+                    scale_up_factor,
+                    &checkpoint.run_id,
+                );
+                checkpoint.record_warmup(&key);
             }
 
             // Do actual experiment
-            for i in 0..args.num_repeats {
-                let mut result = Self::run_workflow_once(workflow, exp, scale_up_factor).await;
-                S3::clear_dir(
-                    EVAL_BUCKET_NAME.to_string(),
-                    format!("{workflow}/exec-tokens"),
+            let invoke = |input_size| {
+                Self::run_knative_workflow_under_load(
+                    workflow,
+                    storage.as_ref(),
+                    &trigger,
+                    &wait_opts,
+                    input_size,
                 )
-                .await;
-                result.iter = i;
-                Self::write_result_to_file(workflow, &exp, &baseline, &result, scale_up_factor);
+            };
+            let mut output_run_id = None;
+            if *exp == EvalExperiment::Throughput {
+                Self::run_throughput(workflow, &baseline, args, checkpoint, &pb, || invoke(0))
+                    .await;
+            } else if *exp == EvalExperiment::ConcurrentRuns {
+                Self::run_concurrent_runs(workflow, &baseline, args, checkpoint, &pb, || invoke(0))
+                    .await;
+            } else if *exp == EvalExperiment::DatasetSize {
+                let sampler = sampler.as_ref();
+                Self::run_dataset_size(workflow, &baseline, args, checkpoint, &pb, sampler, invoke)
+                    .await;
+            } else {
+                for i in progress.num_repeats..args.num_repeats {
+                    let keep_outputs = args.presign_outputs.is_some() && i == args.num_repeats - 1;
+
+                    // Retry failed runs, but record every attempt, with its
+                    // status, so that plots can report failure rates. When
+                    // resuming, we skip the attempts we have already recorded
+                    let first_attempt = checkpoint.get(&key).num_attempts;
+                    for attempt in first_attempt..=args.num_retries {
+                        let run_id = Workflows::new_run_id();
+                        let run = Self::run_workflow_once(
+                            workflow,
+                            exp,
+                            scale_up_factor,
+                            storage.as_ref(),
+                            cluster.as_ref(),
+                            &trigger,
+                            &wait_opts,
+                            &run_id,
+                            keep_outputs,
+                        );
+                        let run = async {
+                            match &recorder {
+                                Some(recorder) => recorder.record_during(run).await,
+                                None => (run.await, Vec::new()),
+                            }
+                        };
+                        let ((mut result, pods), samples) = match &sampler {
+                            Some(sampler) => sampler.sample_during(i, run).await,
+                            None => (run.await, Vec::new()),
+                        };
+                        if keep_outputs {
+                            output_run_id = Some(run_id);
+                        }
+
+                        result.iter = i;
+                        Self::write_result_to_file(
+                            workflow,
+                            &exp,
+                            &baseline,
+                            &result,
+                            scale_up_factor,
+                            &checkpoint.run_id,
+                        );
+                        if args.sample_utilisation {
+                            Self::write_utilisation_samples(
+                                workflow,
+                                &exp,
+                                &baseline,
+                                scale_up_factor,
+                                &result,
+                                &samples,
+                            );
+                        }
+                        if recorder.is_some() {
+                            Self::write_cold_starts(workflow, &baseline, &result, &pods);
+                        }
 
-                pb.inc(1);
+                        if !Self::should_retry(workflow, i, &result, attempt, args.num_retries) {
+                            break;
+                        }
+                        checkpoint.record_attempt(&key);
+                    }
+                    checkpoint.record_repeat(&key);
+
+                    pb.inc(1);
+                }
             }
 
             // Delete workflow
-            Self::delete_workflow(workflow, &baseline);
+            Self::delete_workflow(cluster.as_ref(), workflow, &baseline).await;
 
             // Finish progress bar
             pb.finish();
+
+            if let (Some(expiry), Some(run_id)) = (&args.presign_outputs, &output_run_id) {
+                Self::print_output_links(workflow, run_id, storage.as_ref(), expiry).await;
+            }
         }
 
-        // Experiment-wide clean-up
-        let mut k8s_common_path = Workflows::get_root();
-        k8s_common_path.push("k8s_common.yaml");
-        Self::run_kubectl_cmd(&format!("delete -f {}", k8s_common_path.display()));
+        // Experiment-wide clean-up. We have already printed the links to the
+        // outputs, and they stop working once MinIO goes down
+        cluster.delete(&Self::get_common_manifest()).await;
     }
 
     // ------------------------------------------------------------------------
@@ -740,12 +1207,72 @@ impl Eval {
         }
     }
 
-    fn epoch_ts_to_datetime(epoch_str: &str) -> DateTime<Utc> {
-        let epoch_seconds: f64 = epoch_str.parse().unwrap();
+    fn epoch_ts_to_datetime(epoch_str: &str) -> Option<DateTime<Utc>> {
+        let epoch_seconds: f64 = epoch_str.trim().parse().ok()?;
         let secs = epoch_seconds as i64;
         let nanos = ((epoch_seconds - secs as f64) * 1_000_000_000.0) as u32;
 
-        Utc.timestamp_opt(secs, nanos).single().unwrap()
+        Utc.timestamp_opt(secs, nanos).single()
+    }
+
+    /// Parse the start-end-ts output format of 'faasmctl invoke'
+    fn parse_faasmctl_output(output: &str) -> Option<ExecutionResult> {
+        let (start_ts, end_ts) = output.trim_end().split_once(",")?;
+        Some(ExecutionResult {
+            start_time: Self::epoch_ts_to_datetime(start_ts)?,
+            end_time: Self::epoch_ts_to_datetime(end_ts)?,
+            ..Default::default()
+        })
+    }
+
+    /// Command to invoke a workflow in Faasm. Drivers take the run id as the
+    /// last argument of their command line
+    fn get_faasmctl_invoke_cmd(
+        workflow: &AvailableWorkflow,
+        faasm_cmdline: &str,
+        run_id: &str,
+    ) -> String {
+        format!(
+            "invoke {workflow} driver --cmdline \"{faasm_cmdline} {run_id}\" --output-format start-end-ts"
+        )
+    }
+
+    /// Invoke a workflow in Faasm once. If the invocation fails, or we can
+    /// not make sense of its output, the status in the result says so
+    async fn run_faasm_workflow_once(
+        faasmctl_cmd: &str,
+        timeout: time::Duration,
+    ) -> ExecutionResult {
+        debug!("invrs(eval): executing faasmctl command: {faasmctl_cmd}");
+        let mut cmd = tokio::process::Command::new("faasmctl");
+        cmd.args(shell_words::split(faasmctl_cmd).unwrap());
+
+        let start_time = Utc::now();
+        let output = match Self::run_cmd_with_timeout(cmd, timeout).await {
+            Ok(output) => output,
+            Err(status) => {
+                return ExecutionResult {
+                    start_time,
+                    status,
+                    ..Default::default()
+                }
+            }
+        };
+
+        match Self::parse_faasmctl_output(&output) {
+            Some(result) => result,
+            None => {
+                error!(
+                    "{}(eval): failed to parse faasmctl output: {output}",
+                    Env::SYS_NAME
+                );
+                ExecutionResult {
+                    start_time,
+                    status: ExecutionStatus::Failed,
+                    ..Default::default()
+                }
+            }
+        }
     }
 
     async fn run_faasm_experiment(
@@ -753,6 +1280,7 @@ impl Eval {
         args: &EvalRunArgs,
         args_offset: usize,
         scale_up_factor: u32,
+        checkpoint: &mut Checkpoint,
     ) {
         let baseline = args.baseline[args_offset].clone();
 
@@ -777,46 +1305,77 @@ impl Eval {
         // Self::run_faasmctl_cmd("deploy.k8s --workers=4");
 
         // Second, work-out the MinIO URL
+        let storage = storage::from_url(&args.storage);
         let mut minio_url = Self::run_faasmctl_cmd("s3.get-url");
         minio_url = minio_url.strip_suffix("\n").unwrap().to_string();
         unsafe {
             env::set_var("MINIO_URL", minio_url);
         }
 
-        async fn cleanup_single_execution(workflow: &AvailableWorkflow, exp: &EvalExperiment) {
-            S3::clear_dir(
-                EVAL_BUCKET_NAME.to_string(),
-                format!("{workflow}/exec-tokens"),
-            )
-            .await;
+        async fn cleanup_single_execution(
+            workflow: &AvailableWorkflow,
+            exp: &EvalExperiment,
+            storage: &dyn Storage,
+            run_id: &str,
+            keep_outputs: bool,
+        ) {
+            Eval::cleanup_run(workflow, storage, run_id, keep_outputs).await;
 
             match exp {
                 EvalExperiment::E2eLatencyCold => {
                     debug!("Flushing Faasm workers and sleeping...");
                     Eval::run_faasmctl_cmd("flush.workers");
-                    thread::sleep(time::Duration::from_secs(2));
+                    tokio::time::sleep(time::Duration::from_secs(2)).await;
                 }
                 _ => debug!("nothing to do"),
             }
         }
 
-        // Work-out the workflows to execute for each experiment
-        let workflow_iter = match exp {
-            // For the scale-up latency, we only run the FINRA workflow
-            EvalExperiment::ScaleUpLatency => [AvailableWorkflow::Finra].iter(),
-            _ => AvailableWorkflow::iter_variants(),
-        };
+        let run_timeout = time::Duration::from_secs(args.wait_timeout_secs);
+
+        // Work-out the workflows to execute for each experiment, skipping the
+        // ones we finished before being interrupted
+        let workflows: Vec<AvailableWorkflow> = args
+            .get_workflows(exp)
+            .into_iter()
+            .filter(|workflow| {
+                let key = Checkpoint::get_key(workflow, &baseline, scale_up_factor);
+                !checkpoint.is_done(&key, args.get_num_steps(exp, workflow))
+            })
+            .collect();
+        if workflows.is_empty() {
+            info!(
+                "{}(eval): nothing left to run for {baseline} (scale-up factor: {scale_up_factor})",
+                Env::SYS_NAME
+            );
+            return;
+        }
 
         // Upload the state for all workflows
         // TODO: undo me
-        let pb = Self::get_progress_bar(
-            workflow_iter.len().try_into().unwrap(),
-            exp,
-            &baseline,
-            "state",
-        );
-        for workflow in workflow_iter.clone() {
-            Workflows::upload_workflow_state(workflow, EVAL_BUCKET_NAME, true).await;
+        let pb =
+            Self::get_progress_bar(workflows.len().try_into().unwrap(), exp, &baseline, "state");
+        for workflow in &workflows {
+            Workflows::upload_workflow_state(
+                workflow,
+                storage.as_ref(),
+                &Env::proj_root(),
+                true,
+                args.encrypt_state,
+            )
+            .await;
+            if *exp == EvalExperiment::DatasetSize {
+                for input_size in args.get_input_sizes(workflow) {
+                    Workflows::upload_workflow_input(
+                        workflow,
+                        storage.as_ref(),
+                        &Env::proj_root(),
+                        input_size,
+                        args.encrypt_state,
+                    )
+                    .await;
+                }
+            }
             pb.inc(1);
         }
         pb.finish();
@@ -825,78 +1384,257 @@ impl Eval {
         // TODO: add progress bar
         // Self::upload_wasm();
 
+        let sampler = args.get_utilisation_sampler(None);
+
         // Invoke each workflow
-        for workflow in workflow_iter.clone() {
-            let mut faasm_cmdline = Workflows::get_faasm_cmdline(workflow).to_string();
+        for workflow in &workflows {
+            let mut faasm_cmdline = args.get_faasm_cmdline(workflow);
             if *exp == EvalExperiment::ScaleUpLatency {
                 faasm_cmdline = format!("finra/yfinance.csv {scale_up_factor}");
             }
 
-            // Initialise result file
-            Self::init_data_file(workflow, &exp, &baseline, scale_up_factor);
+            // Initialise result file, unless we are resuming it
+            let key = Checkpoint::get_key(workflow, &baseline, scale_up_factor);
+            let progress = checkpoint.get(&key);
+            if !checkpoint.is_started(&key) {
+                Self::init_data_file(workflow, &exp, &baseline, scale_up_factor);
+                if args.sample_utilisation {
+                    Self::init_utilisation_file(workflow, &exp, &baseline, scale_up_factor);
+                }
+            }
 
             // Prepare progress bar for each different experiment
             let mut workflow_str = format!("{workflow}");
             if scale_up_factor > 0 {
                 workflow_str = format!("{workflow}-{scale_up_factor}");
             }
-            let pb = Self::get_progress_bar(args.num_repeats.into(), exp, &baseline, &workflow_str);
-
-            let faasmctl_cmd = format!(
-                "invoke {workflow} driver --cmdline \"{faasm_cmdline}\" --output-format start-end-ts"
+            let pb = Self::get_progress_bar(
+                args.get_num_steps(exp, workflow).into(),
+                exp,
+                &baseline,
+                &workflow_str,
             );
-            // Do warm-up rounds
-            for _ in 0..args.num_warmup_repeats {
-                Self::run_faasmctl_cmd(&faasmctl_cmd);
-                cleanup_single_execution(workflow, exp).await;
+            pb.set_position(progress.num_repeats.into());
+
+            // Do warm-up rounds (we record them, but plots ignore them)
+            for i in progress.num_warmups..args.num_warmup_repeats {
+                let run_id = Workflows::new_run_id();
+                let faasmctl_cmd = Self::get_faasmctl_invoke_cmd(workflow, &faasm_cmdline, &run_id);
+                let mut result = Self::run_faasm_workflow_once(&faasmctl_cmd, run_timeout).await;
+                result.iter = i;
+                result.warmup = true;
+                result.run_id = run_id.clone();
+                Self::write_result_to_file(
+                    workflow,
+                    &exp,
+                    &baseline,
+                    &result,
+                    scale_up_factor,
+                    &checkpoint.run_id,
+                );
+                checkpoint.record_warmup(&key);
+
+                cleanup_single_execution(workflow, exp, storage.as_ref(), &run_id, false).await;
             }
 
+            // Each input size has its own command line, which overrides do
+            // not apply to
+            let sized_cmdlines: BTreeMap<u32, String> = match exp {
+                EvalExperiment::DatasetSize => args
+                    .get_input_sizes(workflow)
+                    .into_iter()
+                    .map(|size| (size, Workflows::get_faasm_cmdline(workflow, size)))
+                    .collect(),
+                _ => BTreeMap::new(),
+            };
+
             // Do actual experiment
-            for i in 0..args.num_repeats {
-                let mut output = Self::run_faasmctl_cmd(&faasmctl_cmd);
-                output = output.strip_suffix("\n").unwrap().to_string();
-
-                let ts = output.split(",").collect::<Vec<&str>>();
-                let result = ExecutionResult {
-                    start_time: Self::epoch_ts_to_datetime(ts[0]),
-                    end_time: Self::epoch_ts_to_datetime(ts[1]),
-                    iter: i,
+            let invoke = |input_size| {
+                let cmdline = match input_size {
+                    0 => &faasm_cmdline,
+                    n => &sized_cmdlines[&n],
                 };
+                Self::run_faasm_workflow_under_load(
+                    workflow,
+                    cmdline,
+                    storage.as_ref(),
+                    run_timeout,
+                )
+            };
+            let mut output_run_id = None;
+            if *exp == EvalExperiment::Throughput {
+                Self::run_throughput(workflow, &baseline, args, checkpoint, &pb, || invoke(0))
+                    .await;
+            } else if *exp == EvalExperiment::ConcurrentRuns {
+                Self::run_concurrent_runs(workflow, &baseline, args, checkpoint, &pb, || invoke(0))
+                    .await;
+            } else if *exp == EvalExperiment::DatasetSize {
+                let sampler = sampler.as_ref();
+                Self::run_dataset_size(workflow, &baseline, args, checkpoint, &pb, sampler, invoke)
+                    .await;
+            } else {
+                for i in progress.num_repeats..args.num_repeats {
+                    let keep_outputs = args.presign_outputs.is_some() && i == args.num_repeats - 1;
+
+                    // Retry failed runs, but record every attempt
+                    let first_attempt = checkpoint.get(&key).num_attempts;
+                    for attempt in first_attempt..=args.num_retries {
+                        let run_id = Workflows::new_run_id();
+                        let faasmctl_cmd =
+                            Self::get_faasmctl_invoke_cmd(workflow, &faasm_cmdline, &run_id);
+                        let run = Self::run_faasm_workflow_once(&faasmctl_cmd, run_timeout);
+                        let (mut result, samples) = match &sampler {
+                            Some(sampler) => sampler.sample_during(i, run).await,
+                            None => (run.await, Vec::new()),
+                        };
+                        result.iter = i;
+                        result.run_id = run_id.clone();
+                        Self::write_result_to_file(
+                            workflow,
+                            &exp,
+                            &baseline,
+                            &result,
+                            scale_up_factor,
+                            &checkpoint.run_id,
+                        );
+                        if args.sample_utilisation {
+                            Self::write_utilisation_samples(
+                                workflow,
+                                &exp,
+                                &baseline,
+                                scale_up_factor,
+                                &result,
+                                &samples,
+                            );
+                        }
 
-                Self::write_result_to_file(workflow, &exp, &baseline, &result, scale_up_factor);
+                        // Clean-up
+                        cleanup_single_execution(
+                            workflow,
+                            exp,
+                            storage.as_ref(),
+                            &run_id,
+                            keep_outputs,
+                        )
+                        .await;
+                        if keep_outputs {
+                            output_run_id = Some(run_id);
+                        }
 
-                // Clean-up
-                cleanup_single_execution(workflow, exp).await;
+                        if !Self::should_retry(workflow, i, &result, attempt, args.num_retries) {
+                            break;
+                        }
+                        checkpoint.record_attempt(&key);
+                    }
+                    checkpoint.record_repeat(&key);
 
-                pb.inc(1);
+                    pb.inc(1);
+                }
             }
 
             // Finish progress bar
             pb.finish();
+
+            if let (Some(expiry), Some(run_id)) = (&args.presign_outputs, &output_run_id) {
+                Self::print_output_links(workflow, run_id, storage.as_ref(), expiry).await;
+            }
         }
     }
 
     pub async fn run(exp: &EvalExperiment, args: &EvalRunArgs) {
+        // Validate the arguments before we spend time running anything
+        let errors = args.validate(exp);
+        if !errors.is_empty() {
+            panic!(
+                "{}(eval): invalid arguments for {exp}:\n - {}",
+                Env::SYS_NAME,
+                errors.join("\n - ")
+            );
+        }
+        if args.encrypt_state {
+            warn!(
+                "{}(eval): workflow functions can not decrypt encrypted state yet, so runs will fail",
+                Env::SYS_NAME
+            );
+        }
+
+        // Identify all the results we record in this invocation. When
+        // resuming, we keep the run id of the interrupted run
+        let checkpoint_path = PathBuf::from(format!(
+            "{}/{exp}/data/checkpoint.json",
+            Self::get_root().display()
+        ));
+        let checkpoint = match args.resume {
+            true => Checkpoint::load(&checkpoint_path),
+            false => None,
+        };
+        let mut checkpoint = match checkpoint {
+            Some(checkpoint) => {
+                info!(
+                    "{}(eval): resuming {exp} (run id: {})",
+                    Env::SYS_NAME,
+                    checkpoint.run_id
+                );
+                checkpoint
+            }
+            None => {
+                if args.resume {
+                    warn!(
+                        "{}(eval): no checkpoint at {}, starting from scratch",
+                        Env::SYS_NAME,
+                        checkpoint_path.display()
+                    );
+                }
+
+                let run_id = Utc::now().format("%Y%m%d-%H%M%S").to_string();
+                info!("{}(eval): starting {exp} (run id: {run_id})", Env::SYS_NAME);
+                Checkpoint::new(&checkpoint_path, &run_id)
+            }
+        };
+        let run_id = checkpoint.run_id.clone();
+
+        // Keep the spec next to the results it produced
+        if let Some(spec) = &args.spec {
+            let data_dir = format!("{}/{exp}/data", Self::get_root().display());
+            fs::create_dir_all(&data_dir).unwrap();
+            fs::write(format!("{data_dir}/spec-{run_id}.yaml"), spec)
+                .expect("tlessctl(eval): failed to write spec copy");
+        }
+
         for i in 0..args.baseline.len() {
             match args.baseline[i] {
                 EvalBaseline::Knative | EvalBaseline::CcKnative | EvalBaseline::TlessKnative => {
                     match exp {
                         EvalExperiment::ScaleUpLatency => {
                             for scale_up_factor in 1..(args.scale_up_range + 1) {
-                                Self::run_knative_experiment(exp, args, i, scale_up_factor).await;
+                                Self::run_knative_experiment(
+                                    exp,
+                                    args,
+                                    i,
+                                    scale_up_factor,
+                                    &mut checkpoint,
+                                )
+                                .await;
                             }
                         }
-                        _ => Self::run_knative_experiment(exp, args, i, 0).await,
+                        _ => Self::run_knative_experiment(exp, args, i, 0, &mut checkpoint).await,
                     }
                 }
                 EvalBaseline::Faasm | EvalBaseline::SgxFaasm | EvalBaseline::TlessFaasm => {
                     match exp {
                         EvalExperiment::ScaleUpLatency => {
                             for scale_up_factor in 1..(args.scale_up_range + 1) {
-                                Self::run_faasm_experiment(exp, args, i, scale_up_factor).await;
+                                Self::run_faasm_experiment(
+                                    exp,
+                                    args,
+                                    i,
+                                    scale_up_factor,
+                                    &mut checkpoint,
+                                )
+                                .await;
                             }
                         }
-                        _ => Self::run_faasm_experiment(exp, args, i, 0).await,
+                        _ => Self::run_faasm_experiment(exp, args, i, 0, &mut checkpoint).await,
                     }
                 }
             }
@@ -915,14 +1653,6 @@ impl Eval {
     }
 
     fn plot_e2e_latency(exp: &EvalExperiment, data_files: &Vec<PathBuf>) {
-        #[derive(Debug, Deserialize)]
-        #[serde(rename_all = "PascalCase")]
-        struct Record {
-            #[allow(dead_code)]
-            run: u32,
-            time_ms: u64,
-        }
-
         // Initialize the structure to hold the data
         let mut data = BTreeMap::<AvailableWorkflow, BTreeMap<EvalBaseline, f64>>::new();
         for workflow in AvailableWorkflow::iter_variants() {
@@ -933,6 +1663,9 @@ impl Eval {
             data.insert(workflow.clone(), inner_map);
         }
 
+        let mut summary = BTreeMap::<(AvailableWorkflow, EvalBaseline), SampleStats>::new();
+        let mut failures = BTreeMap::<(AvailableWorkflow, EvalBaseline), FailureRate>::new();
+
         let num_workflows = AvailableWorkflow::iter_variants().len();
         let num_baselines = EvalBaseline::iter_variants().len();
         let mut y_max = 0.0;
@@ -955,28 +1688,53 @@ impl Eval {
                 .parse()
                 .unwrap();
 
-            // Open the CSV and deserialize records
-            let mut reader = ReaderBuilder::new()
-                .has_headers(true)
-                .from_path(csv_file)
-                .unwrap();
-            let mut total_time = 0;
-            let mut count = 0;
-
-            for result in reader.deserialize() {
-                let record: Record = result.unwrap();
-                total_time += record.time_ms;
-                count += 1;
-            }
+            // Read the records, skipping warm-ups and failed runs
+            let records = results::read_results(csv_file);
+            failures.insert(
+                (wflow.clone(), baseline.clone()),
+                FailureRate::from_records(&records),
+            );
+            let samples: Vec<f64> = records
+                .iter()
+                .filter(|record| record.is_measurement())
+                .map(|record| record.time_ms as f64)
+                .collect();
+            let stats = match SampleStats::from_samples(&samples) {
+                Some(stats) => stats,
+                None => {
+                    warn!(
+                        "{}(eval): no succesful runs in {}",
+                        Env::SYS_NAME,
+                        csv_file.display()
+                    );
+                    continue;
+                }
+            };
 
             let average_time = data.get_mut(&wflow).unwrap().get_mut(&baseline).unwrap();
-            *average_time = total_time as f64 / count as f64;
+            *average_time = stats.mean;
 
             if *average_time > y_max {
                 y_max = *average_time;
             }
+
+            summary.insert((wflow, baseline), stats);
         }
 
+        let summary_rows: Vec<(String, SampleStats)> = summary
+            .iter()
+            .map(|((workflow, baseline), stats)| (format!("{workflow}/{baseline}"), stats.clone()))
+            .collect();
+        stats::print_summary_table(&format!("{exp}"), "ms", &summary_rows);
+
+        let failure_rows: Vec<(String, FailureRate)> = failures
+            .iter()
+            .map(|((workflow, baseline), failure_rate)| {
+                (format!("{workflow}/{baseline}"), failure_rate.clone())
+            })
+            .collect();
+        stats::print_failure_table(&format!("{exp}"), &failure_rows);
+
         let mut plot_path = env::current_dir().expect("invrs: failed to get current directory");
         plot_path.push("eval");
         plot_path.push(format!("{exp}"));
@@ -1099,6 +1857,32 @@ impl Eval {
                     .unwrap();
             }
 
+            // Add error bars with the 95% confidence interval of the mean,
+            // normalised like the bars. We draw them in pixel coordinates,
+            // as bars span a whole unit of the x axis
+            for (x, baseline) in (0..).zip(workflow_data.keys()) {
+                let y_ref = match Self::is_faasm_baseline(baseline) {
+                    true => y_faasm,
+                    false => y_knative,
+                };
+                let stats = match summary.get(&(workflow.clone(), baseline.clone())) {
+                    Some(stats) if y_ref > 0.0 => stats.scale(1.0 / y_ref),
+                    _ => continue,
+                };
+
+                let (x_left, y_low) = chart.backend_coord(&(x_orig + x, stats.ci_low));
+                let (x_right, y_high) = chart.backend_coord(&(x_orig + x + 1, stats.ci_high));
+                let x_mid = (x_left + x_right) / 2;
+                for line in [
+                    vec![(x_mid, y_low), (x_mid, y_high)],
+                    vec![(x_mid - 4, y_low), (x_mid + 4, y_low)],
+                    vec![(x_mid - 4, y_high), (x_mid + 4, y_high)],
+                ] {
+                    root.draw(&PathElement::new(line, BLACK.stroke_width(1)))
+                        .unwrap();
+                }
+            }
+
             // Add label for the workflow
             let x_workflow_label = x_orig + num_baselines / 2 - 1;
             let label_px_coordinate = chart
@@ -1170,21 +1954,14 @@ impl Eval {
     }
 
     fn plot_scale_up_latency(data_files: &Vec<PathBuf>) {
-        #[derive(Debug, Deserialize)]
-        #[serde(rename_all = "PascalCase")]
-        struct Record {
-            #[allow(dead_code)]
-            run: u32,
-            time_ms: u64,
-        }
-
         const NUM_MAX_FUNCS: usize = 10;
 
-        // Collect data
-        let mut data = BTreeMap::<EvalBaseline, [u64; NUM_MAX_FUNCS]>::new();
+        // Collect data (in seconds)
+        let mut data = BTreeMap::<EvalBaseline, [Option<SampleStats>; NUM_MAX_FUNCS]>::new();
         for baseline in EvalBaseline::iter_variants() {
-            data.insert(baseline.clone(), [0; NUM_MAX_FUNCS]);
+            data.insert(baseline.clone(), Default::default());
         }
+        let mut failures = BTreeMap::<(EvalBaseline, usize), FailureRate>::new();
 
         for csv_file in data_files {
             let file_name = csv_file
@@ -1200,22 +1977,19 @@ impl Eval {
             let _workload: &str = workload_parts[0];
             let scale_up_factor: usize = workload_parts[1].parse().unwrap();
 
-            // Open the CSV and deserialize records
-            let mut reader = ReaderBuilder::new()
-                .has_headers(true)
-                .from_path(csv_file)
-                .unwrap();
-            let mut count = 0;
-            let avg_times = data.get_mut(&baseline).unwrap();
-
-            for result in reader.deserialize() {
-                let record: Record = result.unwrap();
-
-                avg_times[scale_up_factor - 1] += record.time_ms;
-                count += 1;
-            }
-
-            avg_times[scale_up_factor - 1] = avg_times[scale_up_factor - 1] / count;
+            // Read the records, skipping warm-ups and failed runs
+            let records = results::read_results(csv_file);
+            failures.insert(
+                (baseline.clone(), scale_up_factor),
+                FailureRate::from_records(&records),
+            );
+            let samples: Vec<f64> = records
+                .iter()
+                .filter(|record| record.is_measurement())
+                .map(|record| record.time_ms as f64)
+                .collect();
+            data.get_mut(&baseline).unwrap()[scale_up_factor - 1] =
+                SampleStats::from_samples(&samples).map(|stats| stats.scale(1.0 / 1000.0));
 
             /*
             let y_val : f64 = avg_times[scale_up_factor - 1] as f64 / 1000.0;
@@ -1225,6 +1999,34 @@ impl Eval {
             */
         }
 
+        let mut summary_rows = Vec::<(String, SampleStats)>::new();
+        for (baseline, values) in &data {
+            for (x, stats) in values.iter().enumerate() {
+                if let Some(stats) = stats {
+                    summary_rows.push((format!("{baseline}/{}", x + 1), stats.clone()));
+                }
+            }
+        }
+        stats::print_summary_table(
+            &format!("{}", EvalExperiment::ScaleUpLatency),
+            "s",
+            &summary_rows,
+        );
+
+        let failure_rows: Vec<(String, FailureRate)> = failures
+            .iter()
+            .map(|((baseline, scale_up_factor), failure_rate)| {
+                (
+                    format!("{baseline}/{scale_up_factor}"),
+                    failure_rate.clone(),
+                )
+            })
+            .collect();
+        stats::print_failure_table(
+            &format!("{}", EvalExperiment::ScaleUpLatency),
+            &failure_rows,
+        );
+
         let mut y_max: f64 = 200.0;
         let mut plot_path = Env::proj_root();
         plot_path.push("eval");
@@ -1286,21 +2088,36 @@ impl Eval {
         .unwrap();
 
         for (baseline, values) in data {
+            // Skip scale-up factors we have no measurements for
+            let points: Vec<(u32, SampleStats)> = values
+                .iter()
+                .enumerate()
+                .filter_map(|(x, stats)| stats.clone().map(|stats| ((x + 1) as u32, stats)))
+                .collect();
+
             chart
                 .draw_series(LineSeries::new(
-                    (0..values.len())
-                        .zip(values.iter())
-                        .map(|(x, y)| ((x + 1) as u32, *y as f64 / 1000.0)),
+                    points.iter().map(|(x, stats)| (*x, stats.mean)),
                     baseline.get_color().stroke_width(3),
                 ))
                 .unwrap();
 
             chart
-                .draw_series((0..values.len()).zip(values.iter()).map(|(x, y)| {
-                    Circle::new(
-                        ((x + 1) as u32, *y as f64 / 1000.0),
-                        5,
-                        baseline.get_color().filled(),
+                .draw_series(points.iter().map(|(x, stats)| {
+                    Circle::new((*x, stats.mean), 5, baseline.get_color().filled())
+                }))
+                .unwrap();
+
+            // Whiskers with the 95% confidence interval of the mean
+            chart
+                .draw_series(points.iter().map(|(x, stats)| {
+                    ErrorBar::new_vertical(
+                        *x,
+                        stats.ci_low,
+                        stats.mean,
+                        stats.ci_high,
+                        baseline.get_color().stroke_width(2),
+                        10,
                     )
                 }))
                 .unwrap();
@@ -1362,6 +2179,144 @@ impl Eval {
             EvalExperiment::ScaleUpLatency => {
                 Self::plot_scale_up_latency(&data_files);
             }
+            EvalExperiment::Throughput => {
+                Self::plot_throughput(&data_files);
+            }
+            EvalExperiment::ConcurrentRuns => {
+                Self::plot_concurrent_runs(&data_files);
+            }
+            EvalExperiment::ColdStartBreakdown => {
+                Self::plot_cold_start_breakdown();
+            }
+            EvalExperiment::DatasetSize => {
+                Self::plot_dataset_size(&data_files);
+            }
         }
+
+        // Only if we sampled utilisation during the runs
+        Self::plot_utilisation(exp);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::cluster::FakeCluster;
+
+    // Knative Service standing in for the functions of a workflow
+    const FAKE_WORKFLOW_MANIFEST: &str = "
+apiVersion: serving.knative.dev/v1
+kind: Service
+metadata:
+  name: word-count-splitter
+  namespace: tless
+spec:
+  template:
+    metadata:
+      labels:
+        tless.workflows/name: word-count
+    spec:
+      containers:
+        - image: splitter
+";
+
+    /// Run word-count once, cold, against a fake cluster. A stand-in for
+    /// the workflow functions waits for the event that starts the run, and
+    /// writes the state it leaves behind
+    async fn run_cold_once(
+        cluster: &FakeCluster,
+        storage: &dyn Storage,
+        timeout: time::Duration,
+    ) -> ExecutionResult {
+        let workflow = AvailableWorkflow::WordCount;
+        let port_forward = cluster
+            .port_forward("knative-eventing", "imc-dispatcher", 80)
+            .await;
+        let trigger = WorkflowTrigger::new(Some(port_forward.url.clone()));
+        let wait_opts = WaitForKeyOpts {
+            timeout: Some(timeout),
+            initial_backoff: time::Duration::from_millis(10),
+            max_backoff: time::Duration::from_millis(100),
+            use_notifications: false,
+        };
+        let run_id = Workflows::new_run_id();
+
+        let run_functions = async {
+            while !cluster
+                .get_events()
+                .iter()
+                .any(|event| event.body.contains(&run_id))
+            {
+                tokio::time::sleep(time::Duration::from_millis(10)).await;
+            }
+
+            let exec_tokens_prefix = Workflows::get_exec_tokens_prefix(&workflow, &run_id);
+            storage
+                .put_bytes(&format!("{exec_tokens_prefix}splitter"), b"")
+                .await;
+            storage
+                .put_bytes(&Workflows::get_output_key(&workflow, &run_id), b"done")
+                .await;
+        };
+        let (result, _) = tokio::join!(
+            Eval::run_workflow_once(
+                &workflow,
+                &EvalExperiment::E2eLatencyCold,
+                0,
+                storage,
+                cluster,
+                &trigger,
+                &wait_opts,
+                &run_id,
+                false,
+            ),
+            run_functions
+        );
+
+        assert_eq!(result.run_id, run_id);
+        result
+    }
+
+    fn get_test_storage(name: &str) -> (PathBuf, Box<dyn Storage>) {
+        let storage_root =
+            env::temp_dir().join(format!("tless-eval-test-{}-{name}", std::process::id()));
+        let storage = storage::from_url(&format!("file://{}", storage_root.display()));
+        (storage_root, storage)
+    }
+
+    #[tokio::test]
+    async fn cold_run_against_fake_cluster() {
+        let cluster = FakeCluster::new(time::Duration::from_millis(200));
+        cluster.apply(FAKE_WORKFLOW_MANIFEST).await;
+        let (storage_root, storage) = get_test_storage("cold");
+
+        let result = run_cold_once(&cluster, storage.as_ref(), time::Duration::from_secs(30)).await;
+
+        assert_eq!(result.status, ExecutionStatus::Ok);
+        let events = cluster.get_events();
+        assert_eq!(events.len(), 1);
+        assert!(events[0].host.starts_with("ingress-to-splitter-kn-channel"));
+
+        // Cold runs only return once the functions have scaled to zero, and
+        // after removing all the state of the run
+        assert!(cluster.list_pods("tless", "").await.is_empty());
+        assert!(storage.list("word-count/").await.is_empty());
+
+        fs::remove_dir_all(&storage_root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn cold_run_times_out_without_scale_to_zero() {
+        // The functions stay up for much longer than a run may take
+        let cluster = FakeCluster::new(time::Duration::from_secs(3600));
+        cluster.apply(FAKE_WORKFLOW_MANIFEST).await;
+        let (storage_root, storage) = get_test_storage("no-scale-down");
+
+        let result = run_cold_once(&cluster, storage.as_ref(), time::Duration::from_secs(1)).await;
+
+        assert_eq!(result.status, ExecutionStatus::TimedOut);
+        assert!(!cluster.list_pods("tless", "").await.is_empty());
+
+        fs::remove_dir_all(&storage_root).unwrap();
     }
 }
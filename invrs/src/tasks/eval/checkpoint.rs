@@ -0,0 +1,173 @@
+use crate::tasks::eval::EvalBaseline;
+use crate::tasks::workflows::AvailableWorkflow;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Runs completed so far for a (baseline, workflow, scale-up factor) combination
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CombinationProgress {
+    pub num_warmups: u32,
+    pub num_repeats: u32,
+    /// Failed attempts of the repeat in progress that we have already
+    /// recorded, and re-try
+    #[serde(default)]
+    pub num_attempts: u32,
+}
+
+/// Progress of an experiment, so that we can resume it if it dies half-way.
+/// We update it after every run, and write it atomically
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Checkpoint {
+    pub run_id: String,
+    progress: BTreeMap<String, CombinationProgress>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Checkpoint {
+    /// Key for a combination, same as the stem of its results file
+    pub fn get_key(
+        workflow: &AvailableWorkflow,
+        baseline: &EvalBaseline,
+        scale_up_factor: u32,
+    ) -> String {
+        match scale_up_factor {
+            0 => format!("{baseline}_{workflow}"),
+            _ => format!("{baseline}_{workflow}-{scale_up_factor}"),
+        }
+    }
+
+    pub fn new(path: &Path, run_id: &str) -> Self {
+        let checkpoint = Self {
+            run_id: run_id.to_string(),
+            progress: BTreeMap::new(),
+            path: path.to_path_buf(),
+        };
+        checkpoint.save();
+        checkpoint
+    }
+
+    pub fn load(path: &Path) -> Option<Self> {
+        let contents = fs::read(path).ok()?;
+        let mut checkpoint: Self = serde_json::from_slice(&contents).unwrap_or_else(|e| {
+            panic!(
+                "tlessctl(eval): malformed checkpoint file {}: {e}",
+                path.display()
+            )
+        });
+        checkpoint.path = path.to_path_buf();
+
+        Some(checkpoint)
+    }
+
+    fn save(&self) {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+
+        // Write to a temporary file and rename it, so that dying mid-write
+        // does not corrupt the checkpoint
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(self).unwrap())
+            .expect("tlessctl(eval): failed to write checkpoint");
+        fs::rename(&tmp_path, &self.path).expect("tlessctl(eval): failed to write checkpoint");
+    }
+
+    pub fn get(&self, key: &str) -> CombinationProgress {
+        self.progress.get(key).cloned().unwrap_or_default()
+    }
+
+    /// Whether we have already recorded any run (or failed attempt) for a
+    /// combination, and so we must append to its results file
+    pub fn is_started(&self, key: &str) -> bool {
+        let progress = self.get(key);
+        progress.num_warmups > 0 || progress.num_repeats > 0 || progress.num_attempts > 0
+    }
+
+    pub fn is_done(&self, key: &str, num_repeats: u32) -> bool {
+        self.get(key).num_repeats >= num_repeats
+    }
+
+    pub fn record_warmup(&mut self, key: &str) {
+        self.progress
+            .entry(key.to_string())
+            .or_default()
+            .num_warmups += 1;
+        self.save();
+    }
+
+    /// Record a failed attempt of the repeat in progress, before we re-try
+    /// it, so that resuming does not record the attempt twice
+    pub fn record_attempt(&mut self, key: &str) {
+        self.progress
+            .entry(key.to_string())
+            .or_default()
+            .num_attempts += 1;
+        self.save();
+    }
+
+    pub fn record_repeat(&mut self, key: &str) {
+        let progress = self.progress.entry(key.to_string()).or_default();
+        progress.num_repeats += 1;
+        progress.num_attempts = 0;
+        self.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn get_test_path(name: &str) -> PathBuf {
+        env::temp_dir()
+            .join(format!("tless-checkpoint-test-{}", std::process::id()))
+            .join(format!("{name}.json"))
+    }
+
+    #[test]
+    fn resume_skips_recorded_attempts() {
+        let path = get_test_path("attempts");
+        let key = "knative_word-count";
+
+        // A failed first attempt already has a row in the results file
+        let mut checkpoint = Checkpoint::new(&path, "sweep");
+        checkpoint.record_attempt(key);
+        assert!(Checkpoint::load(&path).unwrap().is_started(key));
+
+        checkpoint.record_repeat(key);
+        checkpoint.record_attempt(key);
+        checkpoint.record_attempt(key);
+
+        let mut checkpoint = Checkpoint::load(&path).unwrap();
+        assert_eq!(checkpoint.get(key).num_repeats, 1);
+        assert_eq!(checkpoint.get(key).num_attempts, 2);
+
+        // Attempts only count for the repeat in progress
+        checkpoint.record_repeat(key);
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        assert_eq!(checkpoint.get(key).num_repeats, 2);
+        assert_eq!(checkpoint.get(key).num_attempts, 0);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn load_checkpoint_without_attempts() {
+        let path = get_test_path("no-attempts");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            &path,
+            r#"{"run_id":"sweep","progress":{"knative_finra":{"num_warmups":1,"num_repeats":3}}}"#,
+        )
+        .unwrap();
+
+        let checkpoint = Checkpoint::load(&path).unwrap();
+        assert_eq!(checkpoint.get("knative_finra").num_repeats, 3);
+        assert_eq!(checkpoint.get("knative_finra").num_attempts, 0);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
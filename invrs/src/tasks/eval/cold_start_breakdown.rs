@@ -0,0 +1,466 @@
+use crate::env::Env;
+use crate::tasks::cluster::{Cluster, PodTimeline};
+use crate::tasks::eval::results::ExecutionResult;
+use crate::tasks::eval::stats::{self, SampleStats};
+use crate::tasks::eval::{Eval, EvalBaseline, EvalExperiment};
+use crate::tasks::workflows::AvailableWorkflow;
+use chrono::{DateTime, Utc};
+use csv::{ReaderBuilder, WriterBuilder};
+use futures_util::future::join;
+use log::{debug, warn};
+use plotters::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::io::Write;
+use std::path::PathBuf;
+use std::{fs, time};
+use tokio::sync::Notify;
+
+// Label with the name of the workflow function that a pod runs
+const FUNCTION_LABEL: &str = "tless.workflows/name";
+// Line that the function handlers log when they start handling an event
+const HANDLER_START_MARKER: &str = "tless(handler): handling event";
+// Pods that run to completion (e.g. JobSink pods) may only be ready for a
+// short while, so we look at them often
+const POLL_INTERVAL: time::Duration = time::Duration::from_millis(500);
+const COLD_START_HEADER: &str = "Run,Function,Pod,TriggerTime,CreatedTime,ScheduledTime,SandboxReadyTime,InitializedTime,ContainersReadyTime,ReadyTime,HandlerStartTime";
+
+/// Phases of a cold start, from triggering the workflow to the handler in a
+/// new pod starting. Each phase ends at the next timestamp in a record
+const PHASES: [&str; 6] = [
+    "Autoscaling",
+    "Scheduling",
+    "Sandbox",
+    "Containers",
+    "Readiness",
+    "First request",
+];
+
+/// When each phase of the cold start of one pod finished, as a row in the
+/// cold-start CSV. We leave out the timestamps we did not see
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ColdStartRecord {
+    pub run: u32,
+    pub function: String,
+    pub pod: String,
+    pub trigger_time: DateTime<Utc>,
+    pub created_time: Option<DateTime<Utc>>,
+    pub scheduled_time: Option<DateTime<Utc>>,
+    /// When the pod sandbox (i.e. the VM, for confidential containers) was
+    /// ready to start containers
+    pub sandbox_ready_time: Option<DateTime<Utc>>,
+    pub initialized_time: Option<DateTime<Utc>>,
+    pub containers_ready_time: Option<DateTime<Utc>>,
+    pub ready_time: Option<DateTime<Utc>>,
+    pub handler_start_time: Option<DateTime<Utc>>,
+}
+
+impl ColdStartRecord {
+    /// Duration of each phase in seconds, if we saw both its ends
+    fn get_phases(&self) -> [Option<f64>; PHASES.len()] {
+        // Clusters before Kubernetes 1.29 do not report when the sandbox is
+        // ready, which is close to when the pod is initialised
+        let sandbox_ready_time = self.sandbox_ready_time.or(self.initialized_time);
+        let times = [
+            Some(self.trigger_time),
+            self.created_time,
+            self.scheduled_time,
+            sandbox_ready_time,
+            self.containers_ready_time,
+            self.ready_time,
+            self.handler_start_time,
+        ];
+
+        let mut phases = [None; PHASES.len()];
+        for (idx, phase) in phases.iter_mut().enumerate() {
+            if let (Some(start), Some(end)) = (times[idx], times[idx + 1]) {
+                *phase = Some((end - start).num_milliseconds().max(0) as f64 / 1000.0);
+            }
+        }
+
+        phases
+    }
+}
+
+/// What we saw of one pod while a run was in flight
+pub struct PodObservation {
+    timeline: PodTimeline,
+    handler_start_time: Option<DateTime<Utc>>,
+}
+
+/// Periodically record the condition transitions, and the handler start, of
+/// the workflow pods while a workflow runs
+pub struct ColdStartRecorder<'a> {
+    cluster: &'a dyn Cluster,
+}
+
+impl<'a> ColdStartRecorder<'a> {
+    pub fn new(cluster: &'a dyn Cluster) -> Self {
+        Self { cluster }
+    }
+
+    async fn observe(&self, pods: &mut BTreeMap<String, PodObservation>) {
+        // Only one workflow is deployed at a time, so all pods other than
+        // MinIO belong to it
+        for timeline in self
+            .cluster
+            .get_pod_timelines("tless", "tless.workflows/name!=minio")
+            .await
+        {
+            let pod = pods
+                .entry(timeline.name.clone())
+                .or_insert_with(|| PodObservation {
+                    timeline: PodTimeline {
+                        name: timeline.name.clone(),
+                        labels: timeline.labels.clone(),
+                        created: timeline.created,
+                        conditions: BTreeMap::new(),
+                    },
+                    handler_start_time: None,
+                });
+
+            // Conditions become false again when a pod finishes, so we keep
+            // the first time we saw each of them become true
+            for (condition, time) in timeline.conditions {
+                pod.timeline.conditions.entry(condition).or_insert(time);
+            }
+        }
+
+        for (name, pod) in pods
+            .iter_mut()
+            .filter(|(_, pod)| pod.handler_start_time.is_none())
+        {
+            if let Some(lines) = self.cluster.get_pod_logs("tless", name).await {
+                pod.handler_start_time = lines
+                    .into_iter()
+                    .find(|(_, line)| line.contains(HANDLER_START_MARKER))
+                    .map(|(time, _)| time);
+            }
+        }
+    }
+
+    /// Drive a run to completion, observing the workflow pods until it
+    /// finishes
+    pub async fn record_during<Fut>(&self, fut: Fut) -> (Fut::Output, Vec<PodObservation>)
+    where
+        Fut: Future,
+    {
+        let done = Notify::new();
+        let run_fut = async {
+            let output = fut.await;
+            done.notify_one();
+            output
+        };
+
+        let record_fut = async {
+            let mut pods = BTreeMap::new();
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {},
+                    _ = done.notified() => break,
+                }
+
+                self.observe(&mut pods).await;
+            }
+
+            debug!(
+                "{}(eval): observed {} pods during run",
+                Env::SYS_NAME,
+                pods.len()
+            );
+            pods.into_values().collect()
+        };
+
+        join(run_fut, record_fut).await
+    }
+}
+
+impl Eval {
+    /// Cold-start records live next to the results they belong to, with the
+    /// same file name
+    fn get_cold_start_file_name(workflow: &AvailableWorkflow, baseline: &EvalBaseline) -> PathBuf {
+        let exp = EvalExperiment::ColdStartBreakdown;
+        let data_file = PathBuf::from(Self::get_data_file_name(workflow, &exp, baseline, 0));
+
+        let mut path = Self::get_cold_start_dir();
+        path.push(data_file.file_name().unwrap());
+        path
+    }
+
+    fn get_cold_start_dir() -> PathBuf {
+        let mut path = Self::get_root();
+        path.push(format!("{}", EvalExperiment::ColdStartBreakdown));
+        path.push("data");
+        path.push("pods");
+        path
+    }
+
+    pub(super) fn init_cold_start_file(workflow: &AvailableWorkflow, baseline: &EvalBaseline) {
+        fs::create_dir_all(Self::get_cold_start_dir()).unwrap();
+        let mut file = fs::File::create(Self::get_cold_start_file_name(workflow, baseline))
+            .expect("tlessctl(eval): failed to write to file");
+
+        writeln!(file, "{COLD_START_HEADER}").expect("tlessctl(eval): failed to write to file");
+    }
+
+    /// Append the cold starts we observed during a run. Pods that existed
+    /// before we triggered the workflow were warm, and pods created after
+    /// it finished (e.g. while we clean-up) did not take part in it, so we
+    /// drop both
+    pub(super) fn write_cold_starts(
+        workflow: &AvailableWorkflow,
+        baseline: &EvalBaseline,
+        result: &ExecutionResult,
+        pods: &[PodObservation],
+    ) {
+        let path = Self::get_cold_start_file_name(workflow, baseline);
+        if !path.exists() {
+            Self::init_cold_start_file(workflow, baseline);
+        }
+
+        let file = fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .expect("tlessctl(eval): failed to write to file");
+
+        let mut writer = WriterBuilder::new().has_headers(false).from_writer(file);
+        for pod in pods {
+            let timeline = &pod.timeline;
+            match timeline.created {
+                Some(created) if created >= result.start_time && created <= result.end_time => {}
+                _ => continue,
+            }
+
+            let condition = |name: &str| timeline.conditions.get(name).cloned();
+            let record = ColdStartRecord {
+                run: result.iter,
+                function: timeline
+                    .labels
+                    .get(FUNCTION_LABEL)
+                    .cloned()
+                    .unwrap_or_default(),
+                pod: timeline.name.clone(),
+                trigger_time: result.start_time,
+                created_time: timeline.created,
+                scheduled_time: condition("PodScheduled"),
+                sandbox_ready_time: condition("PodReadyToStartContainers"),
+                initialized_time: condition("Initialized"),
+                containers_ready_time: condition("ContainersReady"),
+                ready_time: condition("Ready"),
+                handler_start_time: pod.handler_start_time,
+            };
+            writer
+                .serialize(record)
+                .expect("tlessctl(eval): failed to write to file");
+        }
+        writer
+            .flush()
+            .expect("tlessctl(eval): failed to write to file");
+    }
+
+    fn get_phase_color(idx: usize) -> RGBColor {
+        match idx {
+            0 => RGBColor(171, 222, 230),
+            1 => RGBColor(203, 170, 203),
+            2 => RGBColor(243, 176, 195),
+            3 => RGBColor(255, 204, 182),
+            4 => RGBColor(255, 255, 181),
+            _ => RGBColor(151, 193, 169),
+        }
+    }
+
+    pub(super) fn plot_cold_start_breakdown() {
+        let exp = EvalExperiment::ColdStartBreakdown;
+        let entries = match fs::read_dir(Self::get_cold_start_dir()) {
+            Ok(entries) => entries,
+            Err(_) => {
+                warn!("{}(eval): no cold starts to plot", Env::SYS_NAME);
+                return;
+            }
+        };
+
+        // Collect the mean duration of each phase over all the cold starts of
+        // a workflow, and the stats over the cold starts of each function
+        let mut data = BTreeMap::<AvailableWorkflow, BTreeMap<EvalBaseline, [f64; 6]>>::new();
+        let mut function_rows: [Vec<(String, SampleStats)>; 6] = Default::default();
+        for entry in entries {
+            let csv_file = entry.unwrap().path();
+            if csv_file.extension().and_then(|e| e.to_str()) != Some("csv") {
+                continue;
+            }
+
+            let file_name_no_ext = csv_file
+                .file_stem()
+                .and_then(|f| f.to_str())
+                .unwrap_or_default();
+            let parts: Vec<&str> = file_name_no_ext.split("_").collect();
+            let baseline: EvalBaseline = parts[0].parse().unwrap();
+            let workflow: AvailableWorkflow = parts[1].parse().unwrap();
+
+            let records: Vec<ColdStartRecord> = ReaderBuilder::new()
+                .has_headers(true)
+                .from_path(&csv_file)
+                .unwrap()
+                .deserialize()
+                .map(|record| {
+                    record.unwrap_or_else(|e| {
+                        panic!(
+                            "tlessctl(eval): malformed cold start in {}: {e}",
+                            csv_file.display()
+                        )
+                    })
+                })
+                .collect();
+            if records.is_empty() {
+                warn!(
+                    "{}(eval): no cold starts in {}",
+                    Env::SYS_NAME,
+                    csv_file.display()
+                );
+                continue;
+            }
+
+            let mut phases: [Vec<f64>; 6] = Default::default();
+            let mut function_phases = BTreeMap::<&str, [Vec<f64>; 6]>::new();
+            for record in &records {
+                for (idx, phase) in record.get_phases().into_iter().enumerate() {
+                    if let Some(phase) = phase {
+                        phases[idx].push(phase);
+                        function_phases.entry(record.function.as_str()).or_default()[idx]
+                            .push(phase);
+                    }
+                }
+            }
+
+            for (function, phases) in function_phases {
+                for (idx, samples) in phases.iter().enumerate() {
+                    if let Some(stats) = SampleStats::from_samples(samples) {
+                        function_rows[idx]
+                            .push((format!("{workflow}/{baseline}/{function}"), stats));
+                    }
+                }
+            }
+
+            // Phases we never saw finish count as zero in the stack
+            let means = phases.map(|samples| {
+                SampleStats::from_samples(&samples)
+                    .map(|stats| stats.mean)
+                    .unwrap_or(0.0)
+            });
+            data.entry(workflow).or_default().insert(baseline, means);
+        }
+
+        for (phase, rows) in PHASES.iter().zip(function_rows.iter()) {
+            stats::print_summary_table(&format!("{exp}: {phase}"), "s", rows);
+        }
+
+        if data.is_empty() {
+            return;
+        }
+
+        let mut plot_path = Env::proj_root();
+        plot_path.push("eval");
+        plot_path.push(format!("{exp}"));
+        plot_path.push("plots");
+        fs::create_dir_all(plot_path.clone()).unwrap();
+        plot_path.push(format!("{exp}.svg"));
+
+        // One panel per workflow, with one stacked bar per baseline. Each
+        // segment is the mean duration of a phase over all the cold starts
+        // of the workflow functions
+        let baselines: Vec<&EvalBaseline> = EvalBaseline::iter_variants()
+            .filter(|baseline| !Self::is_faasm_baseline(baseline))
+            .collect();
+        // The legend needs to fit, even with a single workflow
+        let width = (300 * data.len() as u32).max(850);
+        let root = SVGBackend::new(&plot_path, (width, 350)).into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let (legend_area, plot_area) = root.split_vertically(40);
+        let panels = plot_area.split_evenly((1, data.len()));
+
+        for (panel, (workflow, workflow_data)) in panels.iter().zip(data.iter()) {
+            let y_max = workflow_data
+                .values()
+                .map(|means| means.iter().sum::<f64>())
+                .fold(0.0, f64::max)
+                * 1.2;
+
+            let mut chart = ChartBuilder::on(panel)
+                .caption(format!("{workflow}"), ("sans-serif", 20))
+                .x_label_area_size(40)
+                .y_label_area_size(50)
+                .margin(10)
+                .build_cartesian_2d(0f64..baselines.len() as f64, 0f64..y_max.max(1.0))
+                .unwrap();
+
+            chart
+                .configure_mesh()
+                .disable_x_mesh()
+                .x_label_formatter(&|_| String::new())
+                .y_label_style(("sans-serif", 16).into_font())
+                .y_desc("Cold start [s]")
+                .y_label_formatter(&|y| format!("{:.0}", y))
+                .draw()
+                .unwrap();
+
+            for (b_idx, baseline) in baselines.iter().enumerate() {
+                let x = b_idx as f64;
+
+                // Add label for the baseline, below its bar
+                let (x_label, y_label) = chart.plotting_area().map_coordinate(&(x + 0.2, 0.0));
+                let mut label = format!("{baseline}");
+                if *baseline == &EvalBaseline::CcKnative {
+                    label = "sev-knative".to_string();
+                }
+                root.draw(&Text::new(
+                    label,
+                    (x_label, y_label + 10),
+                    ("sans-serif", 14).into_font(),
+                ))
+                .unwrap();
+
+                let means = match workflow_data.get(baseline) {
+                    Some(means) => means,
+                    None => continue,
+                };
+
+                let mut y_start = 0.0;
+                for (p_idx, mean) in means.iter().enumerate() {
+                    let mut bar = Rectangle::new(
+                        [(x, y_start), (x + 1.0, y_start + mean)],
+                        Self::get_phase_color(p_idx).filled(),
+                    );
+                    bar.set_margin(0, 0, 10, 10);
+                    chart.draw_series(std::iter::once(bar)).unwrap();
+                    y_start += mean;
+                }
+            }
+        }
+
+        // Manually draw the legend (one entry per phase) above all panels
+        for (idx, phase) in PHASES.iter().enumerate() {
+            let x_pos = 50 + idx as i32 * 130;
+            let y_pos = 10;
+
+            legend_area
+                .draw(&Rectangle::new(
+                    [(x_pos, y_pos), (x_pos + 20, y_pos + 20)],
+                    Self::get_phase_color(idx).filled(),
+                ))
+                .unwrap();
+
+            legend_area
+                .draw(&Text::new(
+                    phase.to_string(),
+                    (x_pos + 30, y_pos + 5),
+                    ("sans-serif", 20).into_font(),
+                ))
+                .unwrap();
+        }
+
+        root.present().unwrap();
+    }
+}
@@ -0,0 +1,293 @@
+use crate::env::Env;
+use crate::tasks::eval::checkpoint::Checkpoint;
+use crate::tasks::eval::results::{self, ExecutionResult, ExecutionStatus, ResultRecord};
+use crate::tasks::eval::stats::{self, FailureRate, SampleStats};
+use crate::tasks::eval::{Eval, EvalBaseline, EvalExperiment, EvalRunArgs};
+use crate::tasks::workflows::AvailableWorkflow;
+use futures_util::future::join_all;
+use indicatif::ProgressBar;
+use log::{info, warn};
+use plotters::prelude::*;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::{fs, time};
+
+/// Latency (in seconds) of each run when we start a number of runs of the
+/// same workflow at the same time, and its slowdown with respect to the
+/// smallest number of runs we measured
+struct InterferencePoint {
+    num_runs: u32,
+    latency: SampleStats,
+    slowdown: SampleStats,
+}
+
+impl Eval {
+    /// Run, for a workflow, the steps we have not run yet. At each step we
+    /// start as many runs as the concurrency level at the same time, and
+    /// wait for all of them to finish. We record every run, and checkpoint
+    /// after each step
+    pub(super) async fn run_concurrent_runs<F, Fut>(
+        workflow: &AvailableWorkflow,
+        baseline: &EvalBaseline,
+        args: &EvalRunArgs,
+        checkpoint: &mut Checkpoint,
+        pb: &ProgressBar,
+        invoke: F,
+    ) where
+        F: Fn() -> Fut,
+        Fut: Future<Output = ExecutionResult>,
+    {
+        let exp = EvalExperiment::ConcurrentRuns;
+        let key = Checkpoint::get_key(workflow, baseline, 0);
+        let progress = checkpoint.get(&key);
+
+        for step in progress.num_repeats..args.get_num_steps(&exp, workflow) {
+            let num_runs = args.concurrency[(step / args.num_repeats) as usize];
+            let iter = step % args.num_repeats;
+
+            // Each run has its own run id, so runs only share the workflow
+            // deployment, not their state
+            let results = join_all((0..num_runs).map(|_| invoke())).await;
+
+            let num_failed = results
+                .iter()
+                .filter(|result| result.status != ExecutionStatus::Ok)
+                .count();
+            info!(
+                "{}(eval): {baseline}/{workflow}: {num_runs} concurrent runs ({num_failed} failed)",
+                Env::SYS_NAME
+            );
+
+            for mut result in results {
+                result.iter = iter;
+                result.concurrency = num_runs;
+                Self::write_result_to_file(
+                    workflow,
+                    &exp,
+                    baseline,
+                    &result,
+                    0,
+                    &checkpoint.run_id,
+                );
+            }
+            checkpoint.record_repeat(&key);
+
+            pb.inc(1);
+
+            // Cautionary sleep between steps
+            tokio::time::sleep(time::Duration::from_secs(5)).await;
+        }
+    }
+
+    /// Latency at each number of concurrent runs in a results file, and its
+    /// slowdown with respect to the median latency with the fewest runs
+    fn get_interference_points(records: &[ResultRecord]) -> Vec<InterferencePoint> {
+        let mut levels = BTreeMap::<u32, Vec<f64>>::new();
+        for record in records.iter().filter(|record| record.is_measurement()) {
+            levels
+                .entry(record.concurrency.unwrap_or(1))
+                .or_default()
+                .push(record.time_ms as f64 / 1000.0);
+        }
+
+        let mut points = Vec::new();
+        let mut fewest_runs_median = None;
+        for (num_runs, samples) in levels {
+            let latency = match SampleStats::from_samples(&samples) {
+                Some(latency) => latency,
+                None => continue,
+            };
+            let reference = *fewest_runs_median.get_or_insert(latency.median);
+            if reference <= 0.0 {
+                continue;
+            }
+
+            points.push(InterferencePoint {
+                num_runs,
+                slowdown: latency.scale(1.0 / reference),
+                latency,
+            });
+        }
+
+        points
+    }
+
+    pub(super) fn plot_concurrent_runs(data_files: &Vec<PathBuf>) {
+        let exp = EvalExperiment::ConcurrentRuns;
+
+        // Collect data
+        let mut data =
+            BTreeMap::<AvailableWorkflow, BTreeMap<EvalBaseline, Vec<InterferencePoint>>>::new();
+        let mut failures = BTreeMap::<(AvailableWorkflow, EvalBaseline), FailureRate>::new();
+        for csv_file in data_files {
+            let file_name_no_ext = csv_file
+                .file_stem()
+                .and_then(|f| f.to_str())
+                .unwrap_or_default();
+            let parts: Vec<&str> = file_name_no_ext.split("_").collect();
+            let baseline: EvalBaseline = parts[0].parse().unwrap();
+            let workflow: AvailableWorkflow = parts[1].parse().unwrap();
+
+            let records = results::read_results(csv_file);
+            failures.insert(
+                (workflow.clone(), baseline.clone()),
+                FailureRate::from_records(&records),
+            );
+
+            let points = Self::get_interference_points(&records);
+            if points.is_empty() {
+                warn!(
+                    "{}(eval): no succesful runs in {}",
+                    Env::SYS_NAME,
+                    csv_file.display()
+                );
+                continue;
+            }
+            data.entry(workflow).or_default().insert(baseline, points);
+        }
+
+        let mut latency_rows = Vec::<(String, SampleStats)>::new();
+        let mut slowdown_rows = Vec::<(String, SampleStats)>::new();
+        for (workflow, baselines) in &data {
+            for (baseline, points) in baselines {
+                for point in points {
+                    let label = format!("{workflow}/{baseline}/{}", point.num_runs);
+                    latency_rows.push((label.clone(), point.latency.clone()));
+                    slowdown_rows.push((label, point.slowdown.clone()));
+                }
+            }
+        }
+        stats::print_summary_table(&format!("{exp}"), "s", &latency_rows);
+        stats::print_summary_table(
+            &format!("{exp} slowdown"),
+            "x the median with the fewest runs",
+            &slowdown_rows,
+        );
+
+        let failure_rows: Vec<(String, FailureRate)> = failures
+            .iter()
+            .map(|((workflow, baseline), failure_rate)| {
+                (format!("{workflow}/{baseline}"), failure_rate.clone())
+            })
+            .collect();
+        stats::print_failure_table(&format!("{exp}"), &failure_rows);
+
+        if data.is_empty() {
+            return;
+        }
+
+        let mut plot_path = Env::proj_root();
+        plot_path.push("eval");
+        plot_path.push(format!("{exp}"));
+        plot_path.push("plots");
+        fs::create_dir_all(plot_path.clone()).unwrap();
+        plot_path.push(format!("{exp}.svg"));
+
+        // One slowdown curve per baseline, and one panel per workflow
+        let root = SVGBackend::new(&plot_path, (400 * data.len() as u32, 350)).into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let (legend_area, plot_area) = root.split_vertically(40);
+        let panels = plot_area.split_evenly((1, data.len()));
+
+        for (panel, (workflow, baselines)) in panels.iter().zip(data.iter()) {
+            let points = baselines.values().flatten();
+            let x_max = points
+                .clone()
+                .map(|point| point.num_runs)
+                .max()
+                .unwrap_or(1)
+                + 1;
+            let y_max = points.map(|point| point.slowdown.p95).fold(1.0, f64::max) * 1.2;
+
+            let mut chart = ChartBuilder::on(panel)
+                .caption(format!("{workflow}"), ("sans-serif", 20))
+                .x_label_area_size(40)
+                .y_label_area_size(50)
+                .margin(10)
+                .build_cartesian_2d(0..x_max, 0f64..y_max)
+                .unwrap();
+
+            chart
+                .configure_mesh()
+                .x_label_style(("sans-serif", 16).into_font())
+                .y_label_style(("sans-serif", 16).into_font())
+                .x_desc("Concurrent runs")
+                .y_desc("Slowdown")
+                .y_label_formatter(&|y| format!("{:.1}", y))
+                .draw()
+                .unwrap();
+
+            // Runs that do not interfere with each other stay at 1
+            chart
+                .draw_series(LineSeries::new(
+                    vec![(0, 1.0), (x_max, 1.0)],
+                    BLACK.stroke_width(1),
+                ))
+                .unwrap();
+
+            for (baseline, points) in baselines {
+                chart
+                    .draw_series(LineSeries::new(
+                        points
+                            .iter()
+                            .map(|point| (point.num_runs, point.slowdown.median)),
+                        baseline.get_color().stroke_width(3),
+                    ))
+                    .unwrap();
+
+                chart
+                    .draw_series(points.iter().map(|point| {
+                        Circle::new(
+                            (point.num_runs, point.slowdown.median),
+                            5,
+                            baseline.get_color().filled(),
+                        )
+                    }))
+                    .unwrap();
+
+                // Whiskers from the 5th to the 95th percentile
+                chart
+                    .draw_series(points.iter().map(|point| {
+                        ErrorBar::new_vertical(
+                            point.num_runs,
+                            point.slowdown.p5,
+                            point.slowdown.median,
+                            point.slowdown.p95,
+                            baseline.get_color().stroke_width(2),
+                            10,
+                        )
+                    }))
+                    .unwrap();
+            }
+        }
+
+        // Manually draw the legend above all panels
+        for (idx, baseline) in EvalBaseline::iter_variants().enumerate() {
+            let x_pos = 50 + idx as i32 * 130;
+            let y_pos = 10;
+
+            legend_area
+                .draw(&Rectangle::new(
+                    [(x_pos, y_pos), (x_pos + 20, y_pos + 20)],
+                    baseline.get_color().filled(),
+                ))
+                .unwrap();
+
+            let mut label = format!("{baseline}");
+            if baseline == &EvalBaseline::CcKnative {
+                label = "sev-knative".to_string();
+            }
+
+            legend_area
+                .draw(&Text::new(
+                    label,
+                    (x_pos + 30, y_pos + 5),
+                    ("sans-serif", 20).into_font(),
+                ))
+                .unwrap();
+        }
+
+        root.present().unwrap();
+    }
+}
@@ -0,0 +1,275 @@
+use crate::env::Env;
+use crate::tasks::eval::checkpoint::Checkpoint;
+use crate::tasks::eval::results::{self, ExecutionResult, ResultRecord};
+use crate::tasks::eval::stats::{self, FailureRate, SampleStats};
+use crate::tasks::eval::utilisation::UtilisationSampler;
+use crate::tasks::eval::{Eval, EvalBaseline, EvalExperiment, EvalRunArgs};
+use crate::tasks::workflows::{AvailableWorkflow, Workflows};
+use indicatif::ProgressBar;
+use log::warn;
+use plotters::prelude::*;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::{fs, time};
+
+/// Latency (in seconds) of the runs of a workflow with a given input size
+struct InputSizePoint {
+    input_size: u32,
+    latency: SampleStats,
+}
+
+impl Eval {
+    /// Run, for a workflow, the steps we have not run yet. Each step is one
+    /// run with the input size for the step, and we re-try failed runs like
+    /// in the other latency experiments
+    pub(super) async fn run_dataset_size<F, Fut>(
+        workflow: &AvailableWorkflow,
+        baseline: &EvalBaseline,
+        args: &EvalRunArgs,
+        checkpoint: &mut Checkpoint,
+        pb: &ProgressBar,
+        sampler: Option<&UtilisationSampler<'_>>,
+        invoke: F,
+    ) where
+        F: Fn(u32) -> Fut,
+        Fut: Future<Output = ExecutionResult>,
+    {
+        let exp = EvalExperiment::DatasetSize;
+        let key = Checkpoint::get_key(workflow, baseline, 0);
+        let progress = checkpoint.get(&key);
+        let input_sizes = args.get_input_sizes(workflow);
+
+        for step in progress.num_repeats..args.get_num_steps(&exp, workflow) {
+            let input_size = input_sizes[(step / args.num_repeats) as usize];
+            let iter = step % args.num_repeats;
+
+            // Retry failed runs, but record every attempt
+            let first_attempt = checkpoint.get(&key).num_attempts;
+            for attempt in first_attempt..=args.num_retries {
+                let run = invoke(input_size);
+                let (mut result, samples) = match sampler {
+                    Some(sampler) => sampler.sample_during(iter, run).await,
+                    None => (run.await, Vec::new()),
+                };
+                result.iter = iter;
+                result.input_size = input_size;
+                Self::write_result_to_file(
+                    workflow,
+                    &exp,
+                    baseline,
+                    &result,
+                    0,
+                    &checkpoint.run_id,
+                );
+                if args.sample_utilisation {
+                    Self::write_utilisation_samples(workflow, &exp, baseline, 0, &result, &samples);
+                }
+
+                if !Self::should_retry(workflow, iter, &result, attempt, args.num_retries) {
+                    break;
+                }
+                checkpoint.record_attempt(&key);
+            }
+            checkpoint.record_repeat(&key);
+
+            pb.inc(1);
+
+            // Cautionary sleep between steps
+            tokio::time::sleep(time::Duration::from_secs(5)).await;
+        }
+    }
+
+    /// Latency at each input size in a results file
+    fn get_input_size_points(records: &[ResultRecord]) -> Vec<InputSizePoint> {
+        let mut sizes = BTreeMap::<u32, Vec<f64>>::new();
+        for record in records.iter().filter(|record| record.is_measurement()) {
+            // Runs with the default input do not belong to the sweep
+            if let Some(input_size) = record.input_size.filter(|size| *size > 0) {
+                sizes
+                    .entry(input_size)
+                    .or_default()
+                    .push(record.time_ms as f64 / 1000.0);
+            }
+        }
+
+        sizes
+            .into_iter()
+            .filter_map(|(input_size, samples)| {
+                SampleStats::from_samples(&samples).map(|latency| InputSizePoint {
+                    input_size,
+                    latency,
+                })
+            })
+            .collect()
+    }
+
+    pub(super) fn plot_dataset_size(data_files: &Vec<PathBuf>) {
+        let exp = EvalExperiment::DatasetSize;
+
+        // Collect data
+        let mut data =
+            BTreeMap::<AvailableWorkflow, BTreeMap<EvalBaseline, Vec<InputSizePoint>>>::new();
+        let mut failures = BTreeMap::<(AvailableWorkflow, EvalBaseline), FailureRate>::new();
+        for csv_file in data_files {
+            let file_name_no_ext = csv_file
+                .file_stem()
+                .and_then(|f| f.to_str())
+                .unwrap_or_default();
+            let parts: Vec<&str> = file_name_no_ext.split("_").collect();
+            let baseline: EvalBaseline = parts[0].parse().unwrap();
+            let workflow: AvailableWorkflow = parts[1].parse().unwrap();
+
+            let records = results::read_results(csv_file);
+            failures.insert(
+                (workflow.clone(), baseline.clone()),
+                FailureRate::from_records(&records),
+            );
+
+            let points = Self::get_input_size_points(&records);
+            if points.is_empty() {
+                warn!(
+                    "{}(eval): no succesful runs in {}",
+                    Env::SYS_NAME,
+                    csv_file.display()
+                );
+                continue;
+            }
+            data.entry(workflow).or_default().insert(baseline, points);
+        }
+
+        let mut latency_rows = Vec::<(String, SampleStats)>::new();
+        for (workflow, baselines) in &data {
+            for (baseline, points) in baselines {
+                for point in points {
+                    latency_rows.push((
+                        format!("{workflow}/{baseline}/{}", point.input_size),
+                        point.latency.clone(),
+                    ));
+                }
+            }
+        }
+        stats::print_summary_table(&format!("{exp}"), "s", &latency_rows);
+
+        let failure_rows: Vec<(String, FailureRate)> = failures
+            .iter()
+            .map(|((workflow, baseline), failure_rate)| {
+                (format!("{workflow}/{baseline}"), failure_rate.clone())
+            })
+            .collect();
+        stats::print_failure_table(&format!("{exp}"), &failure_rows);
+
+        if data.is_empty() {
+            return;
+        }
+
+        let mut plot_path = Env::proj_root();
+        plot_path.push("eval");
+        plot_path.push(format!("{exp}"));
+        plot_path.push("plots");
+        fs::create_dir_all(plot_path.clone()).unwrap();
+        plot_path.push(format!("{exp}.svg"));
+
+        // One latency curve per baseline, and one panel per workflow
+        let root = SVGBackend::new(&plot_path, (400 * data.len() as u32, 350)).into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let (legend_area, plot_area) = root.split_vertically(40);
+        let panels = plot_area.split_evenly((1, data.len()));
+
+        for (panel, (workflow, baselines)) in panels.iter().zip(data.iter()) {
+            let points = baselines.values().flatten();
+            let x_max = points
+                .clone()
+                .map(|point| point.input_size)
+                .max()
+                .unwrap_or(1) as f64
+                * 1.1;
+            let y_max = points.map(|point| point.latency.p95).fold(0.0, f64::max) * 1.2;
+
+            let mut chart = ChartBuilder::on(panel)
+                .caption(format!("{workflow}"), ("sans-serif", 20))
+                .x_label_area_size(40)
+                .y_label_area_size(50)
+                .margin(10)
+                .build_cartesian_2d(0f64..x_max, 0f64..y_max)
+                .unwrap();
+
+            chart
+                .configure_mesh()
+                .x_label_style(("sans-serif", 16).into_font())
+                .y_label_style(("sans-serif", 16).into_font())
+                .x_desc(format!(
+                    "Input size [{}]",
+                    Workflows::get_input_unit(workflow)
+                ))
+                .y_desc("Latency [s]")
+                .x_label_formatter(&|x| format!("{:.0}", x))
+                .y_label_formatter(&|y| format!("{:.0}", y))
+                .draw()
+                .unwrap();
+
+            for (baseline, points) in baselines {
+                chart
+                    .draw_series(LineSeries::new(
+                        points
+                            .iter()
+                            .map(|point| (point.input_size as f64, point.latency.median)),
+                        baseline.get_color().stroke_width(3),
+                    ))
+                    .unwrap();
+
+                chart
+                    .draw_series(points.iter().map(|point| {
+                        Circle::new(
+                            (point.input_size as f64, point.latency.median),
+                            5,
+                            baseline.get_color().filled(),
+                        )
+                    }))
+                    .unwrap();
+
+                // Whiskers from the 5th to the 95th percentile
+                chart
+                    .draw_series(points.iter().map(|point| {
+                        ErrorBar::new_vertical(
+                            point.input_size as f64,
+                            point.latency.p5,
+                            point.latency.median,
+                            point.latency.p95,
+                            baseline.get_color().stroke_width(2),
+                            10,
+                        )
+                    }))
+                    .unwrap();
+            }
+        }
+
+        // Manually draw the legend above all panels
+        for (idx, baseline) in EvalBaseline::iter_variants().enumerate() {
+            let x_pos = 50 + idx as i32 * 130;
+            let y_pos = 10;
+
+            legend_area
+                .draw(&Rectangle::new(
+                    [(x_pos, y_pos), (x_pos + 20, y_pos + 20)],
+                    baseline.get_color().filled(),
+                ))
+                .unwrap();
+
+            let mut label = format!("{baseline}");
+            if baseline == &EvalBaseline::CcKnative {
+                label = "sev-knative".to_string();
+            }
+
+            legend_area
+                .draw(&Text::new(
+                    label,
+                    (x_pos + 30, y_pos + 5),
+                    ("sans-serif", 20).into_font(),
+                ))
+                .unwrap();
+        }
+
+        root.present().unwrap();
+    }
+}
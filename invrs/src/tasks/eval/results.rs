@@ -0,0 +1,174 @@
+use crate::tasks::s3::S3WaitError;
+use chrono::{DateTime, Utc};
+use csv::{ReaderBuilder, WriterBuilder};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::{fmt, fs};
+
+/// Version of the results CSV schema. We write it in a comment line before
+/// the header, and files without it are version 1 (only Run,TimeMs)
+pub const RESULTS_SCHEMA_VERSION: u32 = 2;
+const RESULTS_SCHEMA_PREFIX: &str = "# tless-eval-schema:";
+
+/// Column names for version 2. The first two columns are the same as in
+/// version 1, so that old scripts can still read new files
+pub const RESULTS_HEADER: &str =
+    "Run,TimeMs,StartTime,EndTime,Baseline,Workflow,ScaleUpFactor,Warmup,Status,RunId,Concurrency,InputSize,SweepId";
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExecutionStatus {
+    #[default]
+    Ok,
+    Failed,
+    TimedOut,
+}
+
+impl fmt::Display for ExecutionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutionStatus::Ok => write!(f, "ok"),
+            ExecutionStatus::Failed => write!(f, "failed"),
+            ExecutionStatus::TimedOut => write!(f, "timed-out"),
+        }
+    }
+}
+
+impl From<&S3WaitError> for ExecutionStatus {
+    fn from(error: &S3WaitError) -> Self {
+        match error {
+            S3WaitError::Timeout { .. } => ExecutionStatus::TimedOut,
+            S3WaitError::BucketNotFound(_) => ExecutionStatus::Failed,
+        }
+    }
+}
+
+pub struct ExecutionResult {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub iter: u32,
+    pub warmup: bool,
+    pub status: ExecutionStatus,
+    /// Number of runs of the same workflow in flight with this one
+    pub concurrency: u32,
+    /// Number of entries (e.g. images) in the input, or 0 for the default
+    pub input_size: u32,
+    /// Id of this execution, under which it keeps its state in storage
+    pub run_id: String,
+}
+
+impl Default for ExecutionResult {
+    fn default() -> Self {
+        Self {
+            start_time: Utc::now(),
+            end_time: Utc::now(),
+            iter: 0,
+            warmup: false,
+            status: ExecutionStatus::Ok,
+            concurrency: 1,
+            input_size: 0,
+            run_id: String::new(),
+        }
+    }
+}
+
+/// A row in a results CSV. All columns after TimeMs are optional, so that
+/// we can read version 1 files too
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ResultRecord {
+    pub run: u32,
+    pub time_ms: u64,
+    #[serde(default)]
+    pub start_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub end_time: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub baseline: Option<String>,
+    #[serde(default)]
+    pub workflow: Option<String>,
+    #[serde(default)]
+    pub scale_up_factor: Option<u32>,
+    #[serde(default)]
+    pub warmup: bool,
+    #[serde(default)]
+    pub status: ExecutionStatus,
+    #[serde(default)]
+    pub run_id: Option<String>,
+    #[serde(default)]
+    pub concurrency: Option<u32>,
+    #[serde(default)]
+    pub input_size: Option<u32>,
+    #[serde(default)]
+    pub sweep_id: Option<String>,
+}
+
+impl ResultRecord {
+    /// Whether we should account for this record when plotting, i.e. it
+    /// is a succesful, non warm-up, run
+    pub fn is_measurement(&self) -> bool {
+        !self.warmup && self.status == ExecutionStatus::Ok
+    }
+}
+
+/// Read the schema version from the first line of a results file
+pub fn read_schema_version(path: &Path) -> u32 {
+    let mut first_line = String::new();
+    BufReader::new(fs::File::open(path).unwrap())
+        .read_line(&mut first_line)
+        .unwrap();
+
+    match first_line.trim().strip_prefix(RESULTS_SCHEMA_PREFIX) {
+        Some(version) => version.trim().parse().unwrap_or_else(|_| {
+            panic!(
+                "tlessctl(eval): malformed schema version in {}: {first_line}",
+                path.display()
+            )
+        }),
+        None => 1,
+    }
+}
+
+pub fn write_header(file: &mut impl Write) {
+    writeln!(file, "{RESULTS_SCHEMA_PREFIX} {RESULTS_SCHEMA_VERSION}")
+        .expect("tlessctl(eval): failed to write to file");
+    writeln!(file, "{RESULTS_HEADER}").expect("tlessctl(eval): failed to write to file");
+}
+
+pub fn write_record(file: &mut impl Write, record: &ResultRecord) {
+    let mut writer = WriterBuilder::new().has_headers(false).from_writer(file);
+    writer
+        .serialize(record)
+        .expect("tlessctl(eval): failed to write to file");
+    writer
+        .flush()
+        .expect("tlessctl(eval): failed to write to file");
+}
+
+/// Read all the records in a results file, of any schema version we know of
+pub fn read_results(path: &Path) -> Vec<ResultRecord> {
+    let version = read_schema_version(path);
+    if version > RESULTS_SCHEMA_VERSION {
+        panic!(
+            "tlessctl(eval): unsupported results schema version {version} in {} (max: {RESULTS_SCHEMA_VERSION})",
+            path.display()
+        );
+    }
+
+    ReaderBuilder::new()
+        .has_headers(true)
+        .comment(Some(b'#'))
+        .from_path(path)
+        .unwrap()
+        .deserialize()
+        .map(|result| {
+            result.unwrap_or_else(|e| {
+                panic!(
+                    "tlessctl(eval): malformed record in {}: {e}",
+                    path.display()
+                )
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,217 @@
+use crate::tasks::cluster::ClusterBackend;
+use crate::tasks::eval::{
+    EvalBaseline, EvalExperiment, EvalRunArgs, DEFAULT_CONCURRENCY, DEFAULT_DEPLOY_TIMEOUT_SECS,
+    DEFAULT_DURATION_SECS, DEFAULT_NUM_REPEATS, DEFAULT_NUM_RETRIES, DEFAULT_NUM_WARMUP_REPEATS,
+    DEFAULT_SAMPLE_INTERVAL_MS, DEFAULT_SCALE_UP_RANGE, DEFAULT_STORAGE, DEFAULT_WAIT_TIMEOUT_SECS,
+};
+use crate::tasks::workflows::AvailableWorkflow;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::str::FromStr;
+
+/// Per-workflow parameters in an experiment specification
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct WorkflowSpec {
+    /// Command line for the Faasm driver function
+    faasm_cmdline: Option<String>,
+    /// Input sizes for the dataset-size experiment
+    input_sizes: Option<Vec<u32>>,
+}
+
+/// Declarative description of an experiment matrix, e.g.:
+///
+/// experiment: e2e-latency
+/// baselines: [knative, cc-knative, tless-knative]
+/// workflows: [finra, word-count]
+/// num-repeats: 5
+/// workflow-params:
+///   finra:
+///     faasm-cmdline: "finra/yfinance.csv 20"
+///   word-count:
+///     input-sizes: [1, 2, 4, 8]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ExperimentSpec {
+    experiment: String,
+    baselines: Vec<String>,
+    /// Defaults to all the workflows the experiment supports
+    #[serde(default)]
+    workflows: Vec<String>,
+    #[serde(default)]
+    workflow_params: BTreeMap<String, WorkflowSpec>,
+    #[serde(default = "ExperimentSpec::default_num_repeats")]
+    num_repeats: u32,
+    #[serde(default = "ExperimentSpec::default_num_warmup_repeats")]
+    num_warmup_repeats: u32,
+    #[serde(default = "ExperimentSpec::default_scale_up_range")]
+    scale_up_range: u32,
+    #[serde(default = "ExperimentSpec::default_concurrency")]
+    concurrency: Vec<u32>,
+    #[serde(default = "ExperimentSpec::default_duration_secs")]
+    duration_secs: u64,
+    /// Input sizes for all workflows, overriding the per-workflow ones
+    #[serde(default)]
+    input_sizes: Vec<u32>,
+    #[serde(default = "ExperimentSpec::default_wait_timeout_secs")]
+    wait_timeout_secs: u64,
+    #[serde(default = "ExperimentSpec::default_deploy_timeout_secs")]
+    deploy_timeout_secs: u64,
+    #[serde(default = "ExperimentSpec::default_num_retries")]
+    num_retries: u32,
+    #[serde(default = "ExperimentSpec::default_s3_notifications")]
+    s3_notifications: bool,
+    #[serde(default = "ExperimentSpec::default_storage")]
+    storage: String,
+    /// Workflow functions can not decrypt envelope-encrypted state yet
+    #[serde(default)]
+    encrypt_state: bool,
+    #[serde(default)]
+    presign_outputs: Option<String>,
+    #[serde(default)]
+    cluster: ClusterBackend,
+    #[serde(default)]
+    trigger_url: Option<String>,
+    #[serde(default)]
+    sample_utilisation: bool,
+    #[serde(default = "ExperimentSpec::default_sample_interval_ms")]
+    sample_interval_ms: u64,
+}
+
+impl ExperimentSpec {
+    // Serde can only take defaults from functions, so we wrap the ones we
+    // share with the command line arguments
+    fn default_num_repeats() -> u32 {
+        DEFAULT_NUM_REPEATS
+    }
+
+    fn default_num_warmup_repeats() -> u32 {
+        DEFAULT_NUM_WARMUP_REPEATS
+    }
+
+    fn default_scale_up_range() -> u32 {
+        DEFAULT_SCALE_UP_RANGE
+    }
+
+    fn default_concurrency() -> Vec<u32> {
+        DEFAULT_CONCURRENCY.to_vec()
+    }
+
+    fn default_duration_secs() -> u64 {
+        DEFAULT_DURATION_SECS
+    }
+
+    fn default_wait_timeout_secs() -> u64 {
+        DEFAULT_WAIT_TIMEOUT_SECS
+    }
+
+    fn default_deploy_timeout_secs() -> u64 {
+        DEFAULT_DEPLOY_TIMEOUT_SECS
+    }
+
+    fn default_num_retries() -> u32 {
+        DEFAULT_NUM_RETRIES
+    }
+
+    // The command line flag is --no-s3-notifications instead
+    fn default_s3_notifications() -> bool {
+        true
+    }
+
+    fn default_storage() -> String {
+        DEFAULT_STORAGE.to_string()
+    }
+
+    fn default_sample_interval_ms() -> u64 {
+        DEFAULT_SAMPLE_INTERVAL_MS
+    }
+
+    /// Read and validate an experiment specification. We report all the
+    /// errors in the file at once, before running anything
+    pub fn load(spec_path: &str, resume: bool) -> (EvalExperiment, EvalRunArgs) {
+        let spec_str = fs::read_to_string(spec_path)
+            .unwrap_or_else(|e| panic!("tlessctl(eval): error reading spec {spec_path}: {e}"));
+        let spec: ExperimentSpec = serde_yaml::from_str(&spec_str)
+            .unwrap_or_else(|e| panic!("tlessctl(eval): malformed spec {spec_path}: {e}"));
+
+        let mut errors = Vec::new();
+
+        let exp = EvalExperiment::from_str(&spec.experiment);
+        if exp.is_err() {
+            errors.push(format!("unknown experiment: {}", spec.experiment));
+        }
+
+        let mut baselines = Vec::new();
+        for baseline in &spec.baselines {
+            match EvalBaseline::from_str(baseline) {
+                Ok(baseline) => baselines.push(baseline),
+                Err(_) => errors.push(format!("unknown baseline: {baseline}")),
+            }
+        }
+
+        let mut workflows = Vec::new();
+        for workflow in &spec.workflows {
+            match AvailableWorkflow::from_str(workflow) {
+                Ok(workflow) => workflows.push(workflow),
+                Err(_) => errors.push(format!("unknown workflow: {workflow}")),
+            }
+        }
+
+        let mut faasm_cmdlines = BTreeMap::new();
+        let mut workflow_input_sizes = BTreeMap::new();
+        for (workflow, params) in &spec.workflow_params {
+            match AvailableWorkflow::from_str(workflow) {
+                Ok(workflow) => {
+                    if let Some(cmdline) = &params.faasm_cmdline {
+                        faasm_cmdlines.insert(workflow.clone(), cmdline.clone());
+                    }
+                    if let Some(input_sizes) = &params.input_sizes {
+                        workflow_input_sizes.insert(workflow, input_sizes.clone());
+                    }
+                }
+                Err(_) => errors.push(format!("unknown workflow in workflow-params: {workflow}")),
+            }
+        }
+
+        let args = EvalRunArgs {
+            baseline: baselines,
+            workflow: workflows,
+            num_repeats: spec.num_repeats,
+            num_warmup_repeats: spec.num_warmup_repeats,
+            scale_up_range: spec.scale_up_range,
+            concurrency: spec.concurrency,
+            duration_secs: spec.duration_secs,
+            input_sizes: spec.input_sizes,
+            wait_timeout_secs: spec.wait_timeout_secs,
+            deploy_timeout_secs: spec.deploy_timeout_secs,
+            num_retries: spec.num_retries,
+            no_s3_notifications: !spec.s3_notifications,
+            storage: spec.storage,
+            encrypt_state: spec.encrypt_state,
+            presign_outputs: spec.presign_outputs,
+            cluster: spec.cluster,
+            trigger_url: spec.trigger_url,
+            sample_utilisation: spec.sample_utilisation,
+            sample_interval_ms: spec.sample_interval_ms,
+            resume,
+            faasm_cmdlines,
+            workflow_input_sizes,
+            spec: Some(spec_str),
+        };
+
+        // The rest of the checks are shared with the command line
+        if let Ok(exp) = &exp {
+            errors.extend(args.validate(exp));
+        }
+
+        if !errors.is_empty() {
+            panic!(
+                "tlessctl(eval): invalid spec {spec_path}:\n - {}",
+                errors.join("\n - ")
+            );
+        }
+
+        (exp.unwrap(), args)
+    }
+}
@@ -0,0 +1,192 @@
+use crate::tasks::eval::results::{ExecutionStatus, ResultRecord};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Number of re-samples for bootstrap confidence intervals
+const NUM_BOOTSTRAP_SAMPLES: usize = 1000;
+// Fixed seed so that re-plotting the same data gives the same intervals
+const BOOTSTRAP_SEED: u64 = 1234;
+
+/// Summary statistics for the measurements of one data point
+#[derive(Clone, Debug)]
+pub struct SampleStats {
+    pub num_samples: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub p5: f64,
+    pub p95: f64,
+    pub stddev: f64,
+    /// 95% bootstrap confidence interval of the mean
+    pub ci_low: f64,
+    pub ci_high: f64,
+}
+
+/// Percentile of a sorted, non-empty, slice with linear interpolation
+/// between the closest ranks
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let low = rank.floor() as usize;
+    let high = rank.ceil() as usize;
+
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
+}
+
+fn mean(samples: &[f64]) -> f64 {
+    samples.iter().sum::<f64>() / samples.len() as f64
+}
+
+impl SampleStats {
+    pub fn from_samples(samples: &[f64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let mean = mean(samples);
+        let stddev = match samples.len() {
+            1 => 0.0,
+            n => {
+                let sum_sq: f64 = samples.iter().map(|x| (x - mean).powi(2)).sum();
+                (sum_sq / (n - 1) as f64).sqrt()
+            }
+        };
+
+        // Bootstrap the distribution of the mean by re-sampling with
+        // replacement
+        let mut rng = StdRng::seed_from_u64(BOOTSTRAP_SEED);
+        let mut resample = vec![0.0; samples.len()];
+        let mut boot_means: Vec<f64> = (0..NUM_BOOTSTRAP_SAMPLES)
+            .map(|_| {
+                for x in resample.iter_mut() {
+                    *x = samples[rng.gen_range(0..samples.len())];
+                }
+                self::mean(&resample)
+            })
+            .collect();
+        boot_means.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        Some(Self {
+            num_samples: samples.len(),
+            mean,
+            median: percentile(&sorted, 50.0),
+            p5: percentile(&sorted, 5.0),
+            p95: percentile(&sorted, 95.0),
+            stddev,
+            ci_low: percentile(&boot_means, 2.5),
+            ci_high: percentile(&boot_means, 97.5),
+        })
+    }
+
+    /// Same statistics, in different units (e.g. ms to s) or normalised
+    pub fn scale(&self, factor: f64) -> Self {
+        Self {
+            num_samples: self.num_samples,
+            mean: self.mean * factor,
+            median: self.median * factor,
+            p5: self.p5 * factor,
+            p95: self.p95 * factor,
+            stddev: self.stddev * factor,
+            ci_low: self.ci_low * factor,
+            ci_high: self.ci_high * factor,
+        }
+    }
+}
+
+/// Print a table with the statistics for each data point in a plot
+pub fn print_summary_table(title: &str, unit: &str, rows: &[(String, SampleStats)]) {
+    println!("{title} (all values in {unit})");
+    println!(
+        "{:<36} {:>4} {:>10} {:>10} {:>10} {:>10} {:>10} {:>23}",
+        "", "N", "MEAN", "MEDIAN", "P5", "P95", "STDDEV", "95% CI (MEAN)"
+    );
+    for (label, stats) in rows {
+        println!(
+            "{:<36} {:>4} {:>10.1} {:>10.1} {:>10.1} {:>10.1} {:>10.1} {:>23}",
+            label,
+            stats.num_samples,
+            stats.mean,
+            stats.median,
+            stats.p5,
+            stats.p95,
+            stats.stddev,
+            format!("[{:.1}, {:.1}]", stats.ci_low, stats.ci_high)
+        );
+    }
+}
+
+/// Runs that did not finish for one data point. We count every attempt,
+/// including re-tries, but not warm-ups
+#[derive(Clone, Debug, Default)]
+pub struct FailureRate {
+    pub num_runs: usize,
+    pub num_failed: usize,
+    pub num_timed_out: usize,
+}
+
+impl FailureRate {
+    pub fn from_records(records: &[ResultRecord]) -> Self {
+        let mut failure_rate = Self::default();
+        for record in records.iter().filter(|record| !record.warmup) {
+            failure_rate.num_runs += 1;
+            match record.status {
+                ExecutionStatus::Ok => {}
+                ExecutionStatus::Failed => failure_rate.num_failed += 1,
+                ExecutionStatus::TimedOut => failure_rate.num_timed_out += 1,
+            }
+        }
+
+        failure_rate
+    }
+
+    pub fn rate(&self) -> f64 {
+        match self.num_runs {
+            0 => 0.0,
+            n => (self.num_failed + self.num_timed_out) as f64 / n as f64,
+        }
+    }
+}
+
+/// Print a table with the failure rate for each data point in a plot. We
+/// skip it altogether if all runs finished
+pub fn print_failure_table(title: &str, rows: &[(String, FailureRate)]) {
+    if rows
+        .iter()
+        .all(|(_, failure_rate)| failure_rate.rate() == 0.0)
+    {
+        return;
+    }
+
+    println!("{title} (failed runs)");
+    println!(
+        "{:<36} {:>6} {:>8} {:>10} {:>8}",
+        "", "RUNS", "FAILED", "TIMED-OUT", "RATE"
+    );
+    for (label, failure_rate) in rows {
+        println!(
+            "{:<36} {:>6} {:>8} {:>10} {:>7.1}%",
+            label,
+            failure_rate.num_runs,
+            failure_rate.num_failed,
+            failure_rate.num_timed_out,
+            failure_rate.rate() * 100.0
+        );
+    }
+}
+
+/// Print a table with the throughput, and the latency under load, for each
+/// concurrency level in the throughput experiment
+pub fn print_throughput_table(title: &str, rows: &[(String, f64, SampleStats)]) {
+    println!("{title} (throughput in workflows/min, latency in s)");
+    println!(
+        "{:<36} {:>4} {:>10} {:>10} {:>10} {:>10}",
+        "", "N", "TPUT", "MEAN", "MEDIAN", "P95"
+    );
+    for (label, throughput, stats) in rows {
+        println!(
+            "{:<36} {:>4} {:>10.2} {:>10.1} {:>10.1} {:>10.1}",
+            label, stats.num_samples, throughput, stats.mean, stats.median, stats.p95
+        );
+    }
+}
@@ -0,0 +1,380 @@
+use crate::env::Env;
+use crate::tasks::eval::checkpoint::Checkpoint;
+use crate::tasks::eval::results::{self, ExecutionResult, ExecutionStatus, ResultRecord};
+use crate::tasks::eval::stats::{self, FailureRate, SampleStats};
+use crate::tasks::eval::{Eval, EvalBaseline, EvalExperiment, EvalRunArgs};
+use crate::tasks::s3::WaitForKeyOpts;
+use crate::tasks::storage::Storage;
+use crate::tasks::workflows::{AvailableWorkflow, WorkflowTrigger, Workflows};
+use chrono::Utc;
+use futures_util::future::join_all;
+use indicatif::ProgressBar;
+use log::{error, info, warn};
+use plotters::prelude::*;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::{fs, time};
+
+/// Throughput and latency (in seconds) at one concurrency level
+struct ThroughputPoint {
+    concurrency: u32,
+    /// Workflows that finished succesfully, per minute
+    throughput: f64,
+    latency: SampleStats,
+}
+
+impl Eval {
+    /// Keep `concurrency` clients busy invoking a workflow for a fixed time.
+    /// Each client invokes the workflow again as soon as its previous
+    /// invocation finishes (i.e. a closed loop), and we return all the
+    /// invocations sorted by start time
+    async fn run_closed_loop<F, Fut>(
+        concurrency: u32,
+        duration: time::Duration,
+        invoke: &F,
+    ) -> Vec<ExecutionResult>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = ExecutionResult>,
+    {
+        let deadline = time::Instant::now() + duration;
+        let clients = (0..concurrency).map(|_| async move {
+            let mut results = Vec::new();
+            while time::Instant::now() < deadline {
+                results.push(invoke().await);
+            }
+            results
+        });
+
+        let mut results: Vec<ExecutionResult> =
+            join_all(clients).await.into_iter().flatten().collect();
+        results.sort_by_key(|result| result.start_time);
+        for (i, result) in results.iter_mut().enumerate() {
+            result.iter = i as u32;
+            result.concurrency = concurrency;
+        }
+
+        results
+    }
+
+    /// Trigger a Knative workflow and wait for its output. Other runs may be
+    /// in flight, so we only clean-up the state under our own run id
+    pub(super) async fn run_knative_workflow_under_load(
+        workflow: &AvailableWorkflow,
+        storage: &dyn Storage,
+        trigger: &WorkflowTrigger,
+        wait_opts: &WaitForKeyOpts,
+        input_size: u32,
+    ) -> ExecutionResult {
+        let run_id = Workflows::new_run_id();
+        let mut exp_result = ExecutionResult {
+            run_id: run_id.clone(),
+            ..Default::default()
+        };
+
+        if let Err(e) = trigger.trigger(workflow, 0, input_size, &run_id).await {
+            error!("{}(eval): failed to trigger {workflow}: {e}", Env::SYS_NAME);
+            exp_result.end_time = Utc::now();
+            exp_result.status = ExecutionStatus::Failed;
+            Self::cleanup_run(workflow, storage, &run_id, false).await;
+            return exp_result;
+        }
+
+        // The trigger counts towards the time budget of the run
+        let mut wait_opts = wait_opts.clone();
+        wait_opts.timeout = wait_opts.timeout.map(|timeout| {
+            let elapsed = (Utc::now() - exp_result.start_time)
+                .to_std()
+                .unwrap_or_default();
+            timeout.saturating_sub(elapsed)
+        });
+
+        let result_key = Workflows::get_output_key(workflow, &run_id);
+        match storage.wait_for_key(&result_key, &wait_opts).await {
+            Ok(time) => exp_result.end_time = time,
+            Err(e) => {
+                error!(
+                    "{}(eval): error waiting for {workflow} to finish: {e}",
+                    Env::SYS_NAME
+                );
+                exp_result.end_time = Utc::now();
+                exp_result.status = ExecutionStatus::from(&e);
+            }
+        }
+        Self::cleanup_run(workflow, storage, &run_id, false).await;
+
+        exp_result
+    }
+
+    /// Invoke a Faasm workflow under its own run id, and clean-up after it
+    /// without affecting other runs in flight
+    pub(super) async fn run_faasm_workflow_under_load(
+        workflow: &AvailableWorkflow,
+        faasm_cmdline: &str,
+        storage: &dyn Storage,
+        timeout: time::Duration,
+    ) -> ExecutionResult {
+        let run_id = Workflows::new_run_id();
+        let faasmctl_cmd = Self::get_faasmctl_invoke_cmd(workflow, faasm_cmdline, &run_id);
+        let mut result = Self::run_faasm_workflow_once(&faasmctl_cmd, timeout).await;
+        Self::cleanup_run(workflow, storage, &run_id, false).await;
+
+        result.run_id = run_id;
+        result
+    }
+
+    /// Run, for a workflow, the concurrency levels we have not run yet. We
+    /// record every invocation, and checkpoint after each level
+    pub(super) async fn run_throughput<F, Fut>(
+        workflow: &AvailableWorkflow,
+        baseline: &EvalBaseline,
+        args: &EvalRunArgs,
+        checkpoint: &mut Checkpoint,
+        pb: &ProgressBar,
+        invoke: F,
+    ) where
+        F: Fn() -> Fut,
+        Fut: Future<Output = ExecutionResult>,
+    {
+        let key = Checkpoint::get_key(workflow, baseline, 0);
+        let progress = checkpoint.get(&key);
+        let duration = time::Duration::from_secs(args.duration_secs);
+
+        for level in progress.num_repeats as usize..args.concurrency.len() {
+            let concurrency = args.concurrency[level];
+            let results = Self::run_closed_loop(concurrency, duration, &invoke).await;
+
+            let num_failed = results
+                .iter()
+                .filter(|result| result.status != ExecutionStatus::Ok)
+                .count();
+            info!(
+                "{}(eval): {baseline}/{workflow}: {} runs with {concurrency} clients ({num_failed} failed)",
+                Env::SYS_NAME,
+                results.len()
+            );
+
+            for result in &results {
+                Self::write_result_to_file(
+                    workflow,
+                    &EvalExperiment::Throughput,
+                    baseline,
+                    result,
+                    0,
+                    &checkpoint.run_id,
+                );
+            }
+
+            checkpoint.record_repeat(&key);
+
+            pb.inc(1);
+        }
+    }
+
+    /// Throughput at each concurrency level in a results file. Throughput is
+    /// the number of succesful runs over the time we kept the clients busy
+    fn get_throughput_points(records: &[ResultRecord]) -> Vec<ThroughputPoint> {
+        let mut levels = BTreeMap::<u32, Vec<&ResultRecord>>::new();
+        for record in records.iter().filter(|record| !record.warmup) {
+            levels
+                .entry(record.concurrency.unwrap_or(1))
+                .or_default()
+                .push(record);
+        }
+
+        let mut points = Vec::new();
+        for (concurrency, records) in levels {
+            let start = records.iter().filter_map(|record| record.start_time).min();
+            let end = records.iter().filter_map(|record| record.end_time).max();
+            let window_secs = match (start, end) {
+                (Some(start), Some(end)) => (end - start).num_milliseconds() as f64 / 1000.0,
+                _ => continue,
+            };
+
+            let samples: Vec<f64> = records
+                .iter()
+                .filter(|record| record.is_measurement())
+                .map(|record| record.time_ms as f64 / 1000.0)
+                .collect();
+            if let Some(latency) = SampleStats::from_samples(&samples) {
+                if window_secs > 0.0 {
+                    points.push(ThroughputPoint {
+                        concurrency,
+                        throughput: samples.len() as f64 * 60.0 / window_secs,
+                        latency,
+                    });
+                }
+            }
+        }
+
+        points
+    }
+
+    pub(super) fn plot_throughput(data_files: &Vec<PathBuf>) {
+        let exp = EvalExperiment::Throughput;
+
+        // Collect data
+        let mut data =
+            BTreeMap::<AvailableWorkflow, BTreeMap<EvalBaseline, Vec<ThroughputPoint>>>::new();
+        let mut failures = BTreeMap::<(AvailableWorkflow, EvalBaseline), FailureRate>::new();
+        for csv_file in data_files {
+            let file_name_no_ext = csv_file
+                .file_stem()
+                .and_then(|f| f.to_str())
+                .unwrap_or_default();
+            let parts: Vec<&str> = file_name_no_ext.split("_").collect();
+            let baseline: EvalBaseline = parts[0].parse().unwrap();
+            let workflow: AvailableWorkflow = parts[1].parse().unwrap();
+
+            let records = results::read_results(csv_file);
+            failures.insert(
+                (workflow.clone(), baseline.clone()),
+                FailureRate::from_records(&records),
+            );
+
+            let points = Self::get_throughput_points(&records);
+            if points.is_empty() {
+                warn!(
+                    "{}(eval): no succesful runs in {}",
+                    Env::SYS_NAME,
+                    csv_file.display()
+                );
+                continue;
+            }
+            data.entry(workflow).or_default().insert(baseline, points);
+        }
+
+        let mut summary_rows = Vec::<(String, f64, SampleStats)>::new();
+        for (workflow, baselines) in &data {
+            for (baseline, points) in baselines {
+                for point in points {
+                    summary_rows.push((
+                        format!("{workflow}/{baseline}/{}", point.concurrency),
+                        point.throughput,
+                        point.latency.clone(),
+                    ));
+                }
+            }
+        }
+        stats::print_throughput_table(&format!("{exp}"), &summary_rows);
+
+        let failure_rows: Vec<(String, FailureRate)> = failures
+            .iter()
+            .map(|((workflow, baseline), failure_rate)| {
+                (format!("{workflow}/{baseline}"), failure_rate.clone())
+            })
+            .collect();
+        stats::print_failure_table(&format!("{exp}"), &failure_rows);
+
+        if data.is_empty() {
+            return;
+        }
+
+        let mut plot_path = Env::proj_root();
+        plot_path.push("eval");
+        plot_path.push(format!("{exp}"));
+        plot_path.push("plots");
+        fs::create_dir_all(plot_path.clone()).unwrap();
+        plot_path.push(format!("{exp}.svg"));
+
+        // One throughput-latency curve per baseline, and one panel per
+        // workflow, as workflows take very different times
+        let root = SVGBackend::new(&plot_path, (400 * data.len() as u32, 350)).into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let (legend_area, plot_area) = root.split_vertically(40);
+        let panels = plot_area.split_evenly((1, data.len()));
+
+        for (panel, (workflow, baselines)) in panels.iter().zip(data.iter()) {
+            let points = baselines.values().flatten();
+            let x_max = points
+                .clone()
+                .map(|point| point.throughput)
+                .fold(0.0, f64::max)
+                * 1.2;
+            let y_max = points.map(|point| point.latency.p95).fold(0.0, f64::max) * 1.2;
+
+            let mut chart = ChartBuilder::on(panel)
+                .caption(format!("{workflow}"), ("sans-serif", 20))
+                .x_label_area_size(40)
+                .y_label_area_size(50)
+                .margin(10)
+                .build_cartesian_2d(0f64..x_max, 0f64..y_max)
+                .unwrap();
+
+            chart
+                .configure_mesh()
+                .x_label_style(("sans-serif", 16).into_font())
+                .y_label_style(("sans-serif", 16).into_font())
+                .x_desc("Throughput [workflows/min]")
+                .y_desc("Latency [s]")
+                .x_label_formatter(&|x| format!("{:.1}", x))
+                .y_label_formatter(&|y| format!("{:.0}", y))
+                .draw()
+                .unwrap();
+
+            for (baseline, points) in baselines {
+                chart
+                    .draw_series(LineSeries::new(
+                        points
+                            .iter()
+                            .map(|point| (point.throughput, point.latency.median)),
+                        baseline.get_color().stroke_width(3),
+                    ))
+                    .unwrap();
+
+                chart
+                    .draw_series(points.iter().map(|point| {
+                        Circle::new(
+                            (point.throughput, point.latency.median),
+                            5,
+                            baseline.get_color().filled(),
+                        )
+                    }))
+                    .unwrap();
+
+                // Whiskers from the 5th to the 95th percentile
+                chart
+                    .draw_series(points.iter().map(|point| {
+                        ErrorBar::new_vertical(
+                            point.throughput,
+                            point.latency.p5,
+                            point.latency.median,
+                            point.latency.p95,
+                            baseline.get_color().stroke_width(2),
+                            10,
+                        )
+                    }))
+                    .unwrap();
+            }
+        }
+
+        // Manually draw the legend above all panels
+        for (idx, baseline) in EvalBaseline::iter_variants().enumerate() {
+            let x_pos = 50 + idx as i32 * 130;
+            let y_pos = 10;
+
+            legend_area
+                .draw(&Rectangle::new(
+                    [(x_pos, y_pos), (x_pos + 20, y_pos + 20)],
+                    baseline.get_color().filled(),
+                ))
+                .unwrap();
+
+            let mut label = format!("{baseline}");
+            if baseline == &EvalBaseline::CcKnative {
+                label = "sev-knative".to_string();
+            }
+
+            legend_area
+                .draw(&Text::new(
+                    label,
+                    (x_pos + 30, y_pos + 5),
+                    ("sans-serif", 20).into_font(),
+                ))
+                .unwrap();
+        }
+
+        root.present().unwrap();
+    }
+}
@@ -0,0 +1,564 @@
+use crate::env::Env;
+use crate::tasks::cluster::{Cluster, ResourceUsage};
+use crate::tasks::eval::results::ExecutionResult;
+use crate::tasks::eval::stats::{self, SampleStats};
+use crate::tasks::eval::{Eval, EvalBaseline, EvalExperiment};
+use crate::tasks::workflows::AvailableWorkflow;
+use chrono::{DateTime, Utc};
+use csv::{ReaderBuilder, WriterBuilder};
+use futures_util::future::join;
+use log::{debug, warn};
+use plotters::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::io::Write;
+use std::path::PathBuf;
+use std::{fs, time};
+use tokio::sync::Notify;
+
+// Processes that make up a Faasm deployment. Workers run the functions, and
+// the planner schedules them
+const FAASM_PROCESSES: [&str; 2] = ["pool_runner", "planner_server"];
+// Linux reports CPU times in clock ticks of USER_HZ, which is 100 on all the
+// architectures we run on
+const USER_HZ: f64 = 100.0;
+const UTILISATION_HEADER: &str =
+    "Run,Time,ElapsedSecs,NumTargets,CpuCores,MemoryMiB,RxMiBPerSec,TxMiBPerSec";
+const MIB: f64 = 1024.0 * 1024.0;
+
+/// Resource usage of a workflow at one point of a run, added over all the
+/// pods (or processes) that run it
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct UtilisationSample {
+    pub run: u32,
+    pub time: DateTime<Utc>,
+    /// Seconds since the run started
+    pub elapsed_secs: f64,
+    pub num_targets: usize,
+    pub cpu_cores: f64,
+    #[serde(rename = "MemoryMiB")]
+    pub memory_mib: f64,
+    #[serde(rename = "RxMiBPerSec")]
+    pub rx_mib_per_sec: f64,
+    #[serde(rename = "TxMiBPerSec")]
+    pub tx_mib_per_sec: f64,
+}
+
+impl UtilisationSample {
+    /// Work-out usage rates from the counters at two points in time. Pods
+    /// (or processes) we did not see before started in between, so we
+    /// count all their usage
+    fn new(
+        run: u32,
+        start: DateTime<Utc>,
+        prev: &BTreeMap<String, ResourceUsage>,
+        curr: &[ResourceUsage],
+        interval: time::Duration,
+    ) -> Self {
+        let interval_secs = interval.as_secs_f64().max(f64::EPSILON);
+        let zero = ResourceUsage::default();
+        let mut sample = Self {
+            run,
+            time: Utc::now(),
+            elapsed_secs: 0.0,
+            num_targets: curr.len(),
+            cpu_cores: 0.0,
+            memory_mib: 0.0,
+            rx_mib_per_sec: 0.0,
+            tx_mib_per_sec: 0.0,
+        };
+        sample.elapsed_secs = (sample.time - start).num_milliseconds() as f64 / 1000.0;
+
+        for usage in curr {
+            let prev = prev.get(&usage.name).unwrap_or(&zero);
+            sample.cpu_cores += (usage.cpu_secs - prev.cpu_secs).max(0.0) / interval_secs;
+            sample.memory_mib += usage.memory_bytes as f64 / MIB;
+            sample.rx_mib_per_sec +=
+                usage.rx_bytes.saturating_sub(prev.rx_bytes) as f64 / MIB / interval_secs;
+            sample.tx_mib_per_sec +=
+                usage.tx_bytes.saturating_sub(prev.tx_bytes) as f64 / MIB / interval_secs;
+        }
+
+        sample
+    }
+}
+
+/// Where we read resource usage from
+enum UsageSource<'a> {
+    /// Workflow pods, as reported by the kubelets
+    Pods(&'a dyn Cluster),
+    /// Local Faasm processes, from /proc
+    FaasmProcesses,
+}
+
+/// Periodically record the resource usage of a workflow while it runs
+pub struct UtilisationSampler<'a> {
+    source: UsageSource<'a>,
+    interval: time::Duration,
+}
+
+impl<'a> UtilisationSampler<'a> {
+    pub fn pods(cluster: &'a dyn Cluster, interval: time::Duration) -> Self {
+        Self {
+            source: UsageSource::Pods(cluster),
+            interval,
+        }
+    }
+
+    pub fn faasm_processes(interval: time::Duration) -> Self {
+        Self {
+            source: UsageSource::FaasmProcesses,
+            interval,
+        }
+    }
+
+    async fn get_usage(&self) -> Vec<ResourceUsage> {
+        match self.source {
+            // Only one workflow is deployed at a time, so all pods other
+            // than MinIO belong to it
+            UsageSource::Pods(cluster) => {
+                cluster
+                    .get_pod_usage("tless", "tless.workflows/name!=minio")
+                    .await
+            }
+            UsageSource::FaasmProcesses => read_faasm_processes_usage(),
+        }
+    }
+
+    /// Drive a run to completion, sampling resource usage until it finishes
+    pub async fn sample_during<Fut>(
+        &self,
+        run: u32,
+        fut: Fut,
+    ) -> (Fut::Output, Vec<UtilisationSample>)
+    where
+        Fut: Future,
+    {
+        let done = Notify::new();
+        let run_fut = async {
+            let output = fut.await;
+            done.notify_one();
+            output
+        };
+
+        let sample_fut = async {
+            let start = Utc::now();
+            let mut samples = Vec::new();
+            let mut prev: BTreeMap<String, ResourceUsage> = BTreeMap::new();
+            let mut prev_time = time::Instant::now();
+            for usage in self.get_usage().await {
+                prev.insert(usage.name.clone(), usage);
+            }
+
+            let mut interval = tokio::time::interval(self.interval);
+            // The first tick completes straight away
+            interval.tick().await;
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {},
+                    _ = done.notified() => break,
+                }
+
+                let usage = self.get_usage().await;
+                let now = time::Instant::now();
+                samples.push(UtilisationSample::new(
+                    run,
+                    start,
+                    &prev,
+                    &usage,
+                    now - prev_time,
+                ));
+
+                prev = usage
+                    .into_iter()
+                    .map(|usage| (usage.name.clone(), usage))
+                    .collect();
+                prev_time = now;
+            }
+
+            debug!(
+                "{}(eval): took {} utilisation samples during run {run}",
+                Env::SYS_NAME,
+                samples.len()
+            );
+            samples
+        };
+
+        join(run_fut, sample_fut).await
+    }
+}
+
+/// Usage of one process, from its entries in /proc
+fn read_process_usage(pid: &str) -> Option<ResourceUsage> {
+    // The command name may contain spaces, so we split after its closing
+    // parenthesis. After it, utime and stime are the 12th and 13th fields
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    let (_, fields) = stat.rsplit_once(')')?;
+    let fields: Vec<&str> = fields.split_whitespace().collect();
+    let cpu_ticks: u64 =
+        fields.get(11)?.parse::<u64>().ok()? + fields.get(12)?.parse::<u64>().ok()?;
+
+    let status = fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let rss_kb: u64 = status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.split_whitespace().next())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_default();
+
+    Some(ResourceUsage {
+        name: pid.to_string(),
+        cpu_secs: cpu_ticks as f64 / USER_HZ,
+        memory_bytes: rss_kb * 1024,
+        ..Default::default()
+    })
+}
+
+/// Bytes received and sent over all the interfaces, but loopback, in the
+/// network namespace of a process
+fn read_process_net_usage(pid: &str) -> Option<(u64, u64)> {
+    let net_dev = fs::read_to_string(format!("/proc/{pid}/net/dev")).ok()?;
+
+    // The first two lines are headers. Each interface then has eight
+    // counters for received traffic, followed by eight for sent traffic
+    let mut rx_bytes = 0;
+    let mut tx_bytes = 0;
+    for line in net_dev.lines().skip(2) {
+        let (interface, counters) = line.split_once(':')?;
+        if interface.trim() == "lo" {
+            continue;
+        }
+
+        let counters: Vec<u64> = counters
+            .split_whitespace()
+            .filter_map(|counter| counter.parse().ok())
+            .collect();
+        rx_bytes += counters.first().copied().unwrap_or_default();
+        tx_bytes += counters.get(8).copied().unwrap_or_default();
+    }
+
+    Some((rx_bytes, tx_bytes))
+}
+
+/// Usage of all the local Faasm processes. Processes in the same container
+/// share their network counters, so we only attribute them to the process
+/// with the lowest pid in each network namespace
+fn read_faasm_processes_usage() -> Vec<ResourceUsage> {
+    let mut pids: Vec<u64> = match fs::read_dir("/proc") {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+            .collect(),
+        Err(e) => {
+            warn!("{}(eval): failed to read /proc: {e}", Env::SYS_NAME);
+            return Vec::new();
+        }
+    };
+    pids.sort();
+
+    let mut seen_net_namespaces = Vec::new();
+    let mut usage = Vec::new();
+    for pid in pids.iter().map(|pid| pid.to_string()) {
+        let comm = fs::read_to_string(format!("/proc/{pid}/comm")).unwrap_or_default();
+        if !FAASM_PROCESSES.contains(&comm.trim()) {
+            continue;
+        }
+
+        // Processes may exit while we read them
+        let mut process_usage = match read_process_usage(&pid) {
+            Some(process_usage) => process_usage,
+            None => continue,
+        };
+
+        if let Ok(net_namespace) = fs::read_link(format!("/proc/{pid}/ns/net")) {
+            if !seen_net_namespaces.contains(&net_namespace) {
+                if let Some((rx_bytes, tx_bytes)) = read_process_net_usage(&pid) {
+                    process_usage.rx_bytes = rx_bytes;
+                    process_usage.tx_bytes = tx_bytes;
+                }
+                seen_net_namespaces.push(net_namespace);
+            }
+        }
+
+        usage.push(process_usage);
+    }
+
+    usage
+}
+
+impl Eval {
+    /// Utilisation time series live next to the results they belong to,
+    /// with the same file name
+    fn get_utilisation_file_name(
+        workflow: &AvailableWorkflow,
+        exp: &EvalExperiment,
+        baseline: &EvalBaseline,
+        scale_up_factor: u32,
+    ) -> PathBuf {
+        let data_file = PathBuf::from(Self::get_data_file_name(
+            workflow,
+            exp,
+            baseline,
+            scale_up_factor,
+        ));
+
+        let mut path = Self::get_utilisation_dir(exp);
+        path.push(data_file.file_name().unwrap());
+        path
+    }
+
+    fn get_utilisation_dir(exp: &EvalExperiment) -> PathBuf {
+        let mut path = Self::get_root();
+        path.push(format!("{exp}"));
+        path.push("data");
+        path.push("utilisation");
+        path
+    }
+
+    pub(super) fn init_utilisation_file(
+        workflow: &AvailableWorkflow,
+        exp: &EvalExperiment,
+        baseline: &EvalBaseline,
+        scale_up_factor: u32,
+    ) {
+        fs::create_dir_all(Self::get_utilisation_dir(exp)).unwrap();
+        let mut file = fs::File::create(Self::get_utilisation_file_name(
+            workflow,
+            exp,
+            baseline,
+            scale_up_factor,
+        ))
+        .expect("tlessctl(eval): failed to write to file");
+
+        writeln!(file, "{UTILISATION_HEADER}").expect("tlessctl(eval): failed to write to file");
+    }
+
+    /// Append the samples we took during a run. We drop the ones after the
+    /// run finished, e.g. while we clean-up. When resuming a run that did not
+    /// sample utilisation, we start the file here
+    pub(super) fn write_utilisation_samples(
+        workflow: &AvailableWorkflow,
+        exp: &EvalExperiment,
+        baseline: &EvalBaseline,
+        scale_up_factor: u32,
+        result: &ExecutionResult,
+        samples: &[UtilisationSample],
+    ) {
+        let path = Self::get_utilisation_file_name(workflow, exp, baseline, scale_up_factor);
+        if !path.exists() {
+            Self::init_utilisation_file(workflow, exp, baseline, scale_up_factor);
+        }
+
+        let file = fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .expect("tlessctl(eval): failed to write to file");
+
+        let mut writer = WriterBuilder::new().has_headers(false).from_writer(file);
+        for sample in samples
+            .iter()
+            .filter(|sample| sample.time <= result.end_time)
+        {
+            writer
+                .serialize(sample)
+                .expect("tlessctl(eval): failed to write to file");
+        }
+        writer
+            .flush()
+            .expect("tlessctl(eval): failed to write to file");
+    }
+
+    /// Plot the average utilisation of each workflow, for each baseline we
+    /// sampled, if any
+    pub(super) fn plot_utilisation(exp: &EvalExperiment) {
+        let utilisation_dir = Self::get_utilisation_dir(exp);
+        let entries = match fs::read_dir(&utilisation_dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        // Collect data, by workflow (which may include a scale-up factor)
+        // and baseline
+        let metrics = [("CPU", "cores"), ("Memory", "MiB"), ("Network", "MiB/s")];
+        let mut data = BTreeMap::<String, BTreeMap<EvalBaseline, [SampleStats; 3]>>::new();
+        for entry in entries {
+            let csv_file = entry.unwrap().path();
+            if csv_file.extension().and_then(|e| e.to_str()) != Some("csv") {
+                continue;
+            }
+
+            let file_name_no_ext = csv_file
+                .file_stem()
+                .and_then(|f| f.to_str())
+                .unwrap_or_default();
+            let (baseline, workflow) = file_name_no_ext.split_once("_").unwrap();
+            let baseline: EvalBaseline = baseline.parse().unwrap();
+
+            let samples: Vec<UtilisationSample> = ReaderBuilder::new()
+                .has_headers(true)
+                .from_path(&csv_file)
+                .unwrap()
+                .deserialize()
+                .map(|sample| {
+                    sample.unwrap_or_else(|e| {
+                        panic!(
+                            "tlessctl(eval): malformed sample in {}: {e}",
+                            csv_file.display()
+                        )
+                    })
+                })
+                .collect();
+
+            let cpu: Vec<f64> = samples.iter().map(|sample| sample.cpu_cores).collect();
+            let memory: Vec<f64> = samples.iter().map(|sample| sample.memory_mib).collect();
+            let network: Vec<f64> = samples
+                .iter()
+                .map(|sample| sample.rx_mib_per_sec + sample.tx_mib_per_sec)
+                .collect();
+            match (
+                SampleStats::from_samples(&cpu),
+                SampleStats::from_samples(&memory),
+                SampleStats::from_samples(&network),
+            ) {
+                (Some(cpu), Some(memory), Some(network)) => {
+                    data.entry(workflow.to_string())
+                        .or_default()
+                        .insert(baseline, [cpu, memory, network]);
+                }
+                _ => warn!(
+                    "{}(eval): no utilisation samples in {}",
+                    Env::SYS_NAME,
+                    csv_file.display()
+                ),
+            }
+        }
+
+        if data.is_empty() {
+            return;
+        }
+
+        for (idx, (metric, unit)) in metrics.iter().enumerate() {
+            let rows: Vec<(String, SampleStats)> = data
+                .iter()
+                .flat_map(|(workflow, baselines)| {
+                    baselines.iter().map(move |(baseline, stats)| {
+                        (format!("{workflow}/{baseline}"), stats[idx].clone())
+                    })
+                })
+                .collect();
+            stats::print_summary_table(&format!("{exp} utilisation: {metric}"), unit, &rows);
+        }
+
+        let mut plot_path = Env::proj_root();
+        plot_path.push("eval");
+        plot_path.push(format!("{exp}"));
+        plot_path.push("plots");
+        fs::create_dir_all(plot_path.clone()).unwrap();
+        plot_path.push("utilisation.svg");
+
+        // One panel per metric, with a group of bars (one per baseline) for
+        // each workflow. Bars are the mean, and whiskers go from the 5th to
+        // the 95th percentile
+        let num_baselines = EvalBaseline::iter_variants().len();
+        let x_max = (data.len() * (num_baselines + 1)) as f64;
+        let root =
+            SVGBackend::new(&plot_path, (400 * metrics.len() as u32, 350)).into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let (legend_area, plot_area) = root.split_vertically(40);
+        let panels = plot_area.split_evenly((1, metrics.len()));
+
+        for (idx, (panel, (metric, unit))) in panels.iter().zip(metrics.iter()).enumerate() {
+            let y_max = data
+                .values()
+                .flat_map(|baselines| baselines.values())
+                .map(|stats| stats[idx].p95)
+                .fold(0.0, f64::max)
+                * 1.2;
+
+            let mut chart = ChartBuilder::on(panel)
+                .x_label_area_size(40)
+                .y_label_area_size(50)
+                .margin(10)
+                .build_cartesian_2d(0f64..x_max, 0f64..y_max.max(1.0))
+                .unwrap();
+
+            chart
+                .configure_mesh()
+                .disable_x_mesh()
+                .x_label_formatter(&|_| String::new())
+                .y_label_style(("sans-serif", 16).into_font())
+                .y_desc(format!("{metric} [{unit}]"))
+                .y_label_formatter(&|y| format!("{:.1}", y))
+                .draw()
+                .unwrap();
+
+            for (w_idx, (workflow, baselines)) in data.iter().enumerate() {
+                let x_orig = (w_idx * (num_baselines + 1)) as f64;
+
+                // Add label for the workflow, below its group of bars
+                let (x_label, y_label) = chart.plotting_area().map_coordinate(&(x_orig, 0.0));
+                root.draw(&Text::new(
+                    workflow.to_string(),
+                    (x_label, y_label + 10),
+                    ("sans-serif", 14).into_font(),
+                ))
+                .unwrap();
+
+                for (b_idx, baseline) in EvalBaseline::iter_variants().enumerate() {
+                    let stats = match baselines.get(baseline) {
+                        Some(stats) => &stats[idx],
+                        None => continue,
+                    };
+                    let x = x_orig + b_idx as f64;
+
+                    let mut bar = Rectangle::new(
+                        [(x, 0.0), (x + 1.0, stats.mean)],
+                        baseline.get_color().filled(),
+                    );
+                    bar.set_margin(0, 0, 2, 2);
+                    chart.draw_series(std::iter::once(bar)).unwrap();
+
+                    chart
+                        .draw_series(std::iter::once(ErrorBar::new_vertical(
+                            x + 0.5,
+                            stats.p5,
+                            stats.mean,
+                            stats.p95,
+                            BLACK.stroke_width(1),
+                            6,
+                        )))
+                        .unwrap();
+                }
+            }
+        }
+
+        // Manually draw the legend above all panels
+        for (idx, baseline) in EvalBaseline::iter_variants().enumerate() {
+            let x_pos = 50 + idx as i32 * 130;
+            let y_pos = 10;
+
+            legend_area
+                .draw(&Rectangle::new(
+                    [(x_pos, y_pos), (x_pos + 20, y_pos + 20)],
+                    baseline.get_color().filled(),
+                ))
+                .unwrap();
+
+            let mut label = format!("{baseline}");
+            if baseline == &EvalBaseline::CcKnative {
+                label = "sev-knative".to_string();
+            }
+
+            legend_area
+                .draw(&Text::new(
+                    label,
+                    (x_pos + 30, y_pos + 5),
+                    ("sans-serif", 20).into_font(),
+                ))
+                .unwrap();
+        }
+
+        root.present().unwrap();
+    }
+}
@@ -1,6 +1,10 @@
 use crate::env::Env;
+use crate::tasks::crypto::{Crypto, EnvelopeError, EnvelopeMetadata, ENVELOPE_CHUNK_SIZE};
+use crate::tasks::storage::StorageObject;
+use base64::prelude::*;
 use bytes::Bytes;
 use chrono::{DateTime, Utc};
+use clap::ValueEnum;
 use futures_util::StreamExt;
 use log::{debug, error, info, warn};
 use minio::s3::args::*;
@@ -10,12 +14,145 @@ use minio::s3::creds::StaticProvider;
 use minio::s3::error::Error;
 use minio::s3::http::BaseUrl;
 use minio::s3::types::{S3Api, ToStream};
+use minio::s3::utils::Multimap;
+use serde::Serialize;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
-use std::{
-    env, fs,
-    io::{Read, Write},
-    thread, time,
-};
+use std::{collections::BTreeMap, env, fmt, fs, time};
+use tokio_util::io::ReaderStream;
+
+mod lifecycle;
+mod manifest;
+mod presign;
+mod server;
+mod snapshot;
+mod watch;
+
+pub use manifest::{DatasetManifest, VerifyReport};
+pub use presign::S3PresignMethod;
+pub use server::S3ServerMode;
+
+// Credentials for the MinIO deployments we manage (see k8s_common.yaml)
+const S3_USER: &str = "minio";
+const S3_PASSWORD: &str = "minio123";
+
+/// Options controlling how we wait for a key to appear in a bucket
+#[derive(Clone, Debug)]
+pub struct WaitForKeyOpts {
+    /// Give up after this long. None means wait forever
+    pub timeout: Option<time::Duration>,
+    /// First polling interval, doubled after every miss
+    pub initial_backoff: time::Duration,
+    /// Upper bound for the polling interval
+    pub max_backoff: time::Duration,
+    /// Use MinIO's listen API to react to new keys immediately, falling
+    /// back to polling if it is not available
+    pub use_notifications: bool,
+}
+
+impl Default for WaitForKeyOpts {
+    fn default() -> Self {
+        Self {
+            timeout: Some(time::Duration::from_secs(30 * 60)),
+            initial_backoff: time::Duration::from_millis(250),
+            max_backoff: time::Duration::from_secs(5),
+            use_notifications: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum S3WaitError {
+    BucketNotFound(String),
+    Timeout { key: String, waited: time::Duration },
+}
+
+impl fmt::Display for S3WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            S3WaitError::BucketNotFound(bucket) => write!(f, "bucket does not exist: {bucket}"),
+            S3WaitError::Timeout { key, waited } => {
+                write!(f, "timed-out after {waited:?} waiting for key: {key}")
+            }
+        }
+    }
+}
+
+/// How to render the contents of an object on stdout
+#[derive(Clone, Debug, ValueEnum)]
+pub enum S3OutputFormat {
+    Raw,
+    Hex,
+    Base64,
+}
+
+impl S3OutputFormat {
+    /// Wrap a sink so that we render whatever we write to it in this format
+    pub fn writer<W: Write>(&self, inner: W) -> S3OutputWriter<W> {
+        S3OutputWriter {
+            format: self.clone(),
+            pending: Vec::new(),
+            inner,
+        }
+    }
+}
+
+/// Render an object in an output format chunk-by-chunk. Base64 encodes
+/// groups of three bytes, so we hold back the bytes that do not fill a
+/// group until the next write
+pub struct S3OutputWriter<W: Write> {
+    format: S3OutputFormat,
+    pending: Vec<u8>,
+    inner: W,
+}
+
+impl<W: Write> S3OutputWriter<W> {
+    /// Write the bytes we held back, and the trailing new line of the text
+    /// formats
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.format {
+            S3OutputFormat::Raw => {}
+            S3OutputFormat::Hex => self.inner.write_all(b"\n")?,
+            S3OutputFormat::Base64 => {
+                let encoded = BASE64_STANDARD.encode(&self.pending);
+                self.inner.write_all(format!("{encoded}\n").as_bytes())?;
+            }
+        }
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for S3OutputWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.format {
+            S3OutputFormat::Raw => self.inner.write_all(buf)?,
+            S3OutputFormat::Hex => self.inner.write_all(hex::encode(buf).as_bytes())?,
+            S3OutputFormat::Base64 => {
+                self.pending.extend_from_slice(buf);
+                let num_bytes = self.pending.len() / 3 * 3;
+                let encoded = BASE64_STANDARD.encode(&self.pending[..num_bytes]);
+                self.pending.drain(..num_bytes);
+                self.inner.write_all(encoded.as_bytes())?;
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Aggregated usage for all keys sharing a prefix
+#[derive(Debug, Serialize)]
+pub struct S3PrefixUsage {
+    pub prefix: String,
+    pub num_objects: u64,
+    pub num_bytes: u64,
+}
 
 #[derive(Debug)]
 pub struct S3 {}
@@ -37,7 +174,7 @@ impl S3 {
             .parse::<BaseUrl>()
             .unwrap();
 
-        let static_provider = StaticProvider::new("minio", "minio123", None);
+        let static_provider = StaticProvider::new(S3_USER, S3_PASSWORD, None);
 
         ClientBuilder::new(base_url.clone())
             .provider(Some(Box::new(static_provider)))
@@ -45,10 +182,9 @@ impl S3 {
             .unwrap()
     }
 
-    pub fn get_datasets_root() -> PathBuf {
-        let mut path = env::current_dir().expect("invrs: failed to get current directory");
-        path.push("datasets");
-        path
+    /// Directory with the datasets we upload, in a checkout of the project
+    pub fn get_datasets_root(proj_root: &Path) -> PathBuf {
+        proj_root.join("datasets")
     }
 
     pub async fn clear_bucket(bucket_name: String) {
@@ -68,9 +204,13 @@ impl S3 {
             return;
         }
 
+        // Buckets with versioning (see lifecycle_apply_defaults) keep the
+        // old versions, and delete markers, of the objects we remove, and
+        // we can only remove a bucket once all of them are gone
         let mut objects = client
             .list_objects(&bucket_name)
             .recursive(true)
+            .include_versions(true)
             .to_stream()
             .await;
 
@@ -79,7 +219,10 @@ impl S3 {
                 Ok(resp) => {
                     for item in resp.contents {
                         client
-                            .remove_object(&bucket_name, item.name.as_str())
+                            .remove_object(
+                                &bucket_name,
+                                (item.name.as_str(), item.version_id.as_deref()),
+                            )
                             .send()
                             .await
                             .unwrap();
@@ -144,7 +287,174 @@ impl S3 {
             .unwrap();
     }
 
-    pub async fn get_dir(bucket_name: &str, s3_path: &str, host_path: &str) {
+    /// Stream an object chunk-by-chunk, and pass each chunk to a callback as
+    /// it arrives, so that memory usage does not depend on the object size
+    async fn stream_object<F: FnMut(&[u8])>(
+        client: &Client,
+        bucket_name: &str,
+        key_name: &str,
+        mut on_chunk: F,
+    ) {
+        let (mut object, _) = client
+            .get_object(bucket_name, key_name)
+            .send()
+            .await
+            .unwrap()
+            .content
+            .to_stream()
+            .await
+            .unwrap();
+
+        while let Some(chunk) = object.next().await {
+            let chunk = chunk.expect("tlessctl(s3): failed to read chunk");
+            on_chunk(&chunk);
+        }
+    }
+
+    /// Read a whole object into memory
+    async fn read_object(client: &Client, bucket_name: &str, key_name: &str) -> Vec<u8> {
+        let mut content = Vec::new();
+        Self::stream_object(client, bucket_name, key_name, |chunk| {
+            content.extend_from_slice(chunk)
+        })
+        .await;
+
+        content
+    }
+
+    /// Envelope metadata of an object, or None if the object is not
+    /// envelope-encrypted (e.g. a dataset manifest)
+    async fn get_envelope_metadata(
+        client: &Client,
+        bucket_name: &str,
+        key_name: &str,
+    ) -> Option<EnvelopeMetadata> {
+        let stat = client
+            .stat_object(&StatObjectArgs::new(bucket_name, key_name).unwrap())
+            .await
+            .unwrap();
+        EnvelopeMetadata::from_user_metadata(&stat.user_metadata)
+    }
+
+    /// Stream an envelope-encrypted object, decrypting it chunk-by-chunk, and
+    /// pass the plain-text to a callback as we decrypt it. Prefixes may mix
+    /// encrypted and plain-text objects, so we pass the latter on as they are
+    async fn stream_encrypted_object<F: FnMut(&[u8])>(
+        client: &Client,
+        bucket_name: &str,
+        key_name: &str,
+        mut on_plain_text: F,
+    ) -> Result<(), EnvelopeError> {
+        let metadata = match Self::get_envelope_metadata(client, bucket_name, key_name).await {
+            Some(metadata) => metadata,
+            None => {
+                warn!("tlessctl(s3): object is not envelope-encrypted, downloading it as-is: {bucket_name}/{key_name}");
+                Self::stream_object(client, bucket_name, key_name, on_plain_text).await;
+                return Ok(());
+            }
+        };
+
+        // Once a chunk fails to decrypt, we skip the rest of the object
+        let mut decryptor = Crypto::envelope_decrypt(&metadata);
+        let mut result = Ok(());
+        Self::stream_object(client, bucket_name, key_name, |chunk| {
+            if result.is_ok() {
+                result = decryptor
+                    .update(chunk)
+                    .map(|plain_text| on_plain_text(&plain_text));
+            }
+        })
+        .await;
+        result?;
+        on_plain_text(&decryptor.finish()?);
+
+        Ok(())
+    }
+
+    /// Read and decrypt an envelope-encrypted object into memory
+    async fn read_encrypted_object(client: &Client, bucket_name: &str, key_name: &str) -> Vec<u8> {
+        let mut content = Vec::new();
+        Self::stream_encrypted_object(client, bucket_name, key_name, |plain_text| {
+            content.extend_from_slice(plain_text)
+        })
+        .await
+        .unwrap_or_else(|e| panic!("tlessctl(s3): error reading {bucket_name}/{key_name}: {e}"));
+
+        content
+    }
+
+    /// Stream an object, decrypting it if asked to, and write it to a sink
+    /// chunk-by-chunk. Returns the number of (plain-text) bytes we wrote
+    async fn download_object<W: Write>(
+        client: &Client,
+        bucket_name: &str,
+        key_name: &str,
+        sink: &mut W,
+        decrypt: bool,
+    ) -> u64 {
+        let mut num_bytes: u64 = 0;
+        let on_chunk = |chunk: &[u8]| {
+            sink.write_all(chunk)
+                .expect("tlessctl(s3): failed to write object");
+            num_bytes += chunk.len() as u64;
+        };
+        match decrypt {
+            true => Self::stream_encrypted_object(client, bucket_name, key_name, on_chunk)
+                .await
+                .unwrap_or_else(|e| {
+                    panic!("tlessctl(s3): error reading {bucket_name}/{key_name}: {e}")
+                }),
+            false => Self::stream_object(client, bucket_name, key_name, on_chunk).await,
+        }
+
+        num_bytes
+    }
+
+    /// Encrypt a file with a fresh data key, and upload it together with the
+    /// wrapped data key as user metadata. We encrypt the file chunk-by-chunk
+    /// as we upload it, so that memory usage does not depend on its size
+    async fn put_encrypted_object(
+        client: &Client,
+        bucket_name: &str,
+        key_name: &str,
+        host_file_path: &Path,
+    ) {
+        let file = tokio::fs::File::open(host_file_path).await.unwrap();
+        let size = Crypto::get_envelope_size(file.metadata().await.unwrap().len());
+        let (encryptor, metadata) = Crypto::envelope_encrypt();
+
+        let mut user_metadata = Multimap::new();
+        for (key, value) in metadata.to_user_metadata() {
+            user_metadata.insert(key, value);
+        }
+
+        // Read the file asynchronously (tokio::fs reads off the runtime
+        // threads), and encrypt each chunk as the client asks for it
+        let reader = ReaderStream::with_capacity(file, ENVELOPE_CHUNK_SIZE);
+        let cipher_text = futures_util::stream::unfold(
+            (reader, Some(encryptor)),
+            |(mut reader, encryptor)| async move {
+                let mut encryptor = encryptor?;
+                match reader.next().await {
+                    Some(Ok(plain_text)) => {
+                        let cipher_text = Bytes::from(encryptor.update(&plain_text));
+                        Some((Ok(cipher_text), (reader, Some(encryptor))))
+                    }
+                    Some(Err(e)) => Some((Err(e), (reader, None))),
+                    None => Some((Ok(Bytes::from(encryptor.finish())), (reader, None))),
+                }
+            },
+        );
+        let content = ObjectContent::new_from_stream(cipher_text, Some(size));
+        client
+            .put_object_content(bucket_name, key_name, content)
+            .user_metadata(Some(user_metadata))
+            .send()
+            .await
+            .unwrap();
+    }
+
+    pub async fn get_dir(bucket_name: &str, s3_path: &str, host_path: &str, decrypt: bool) {
         let client = Self::init_s3_client();
 
         let exists: bool = client
@@ -175,27 +485,12 @@ impl S3 {
                     for item in resp.contents {
                         let host_file_name = item.name.rsplit('/').next().unwrap_or(&item.name);
 
-                        let (mut object, _) = client
-                            .get_object(bucket_name, &item.name)
-                            .send()
-                            .await
-                            .unwrap()
-                            .content
-                            .to_stream()
-                            .await
-                            .unwrap();
-
-                        let mut content = Vec::new();
-                        while let Some(chunk) = object.next().await {
-                            let chunk = chunk.expect("Failed to read chunk");
-                            content.extend_from_slice(&chunk);
-                        }
-
                         let host_file_path = format!("{host_path}/{host_file_name}");
                         println!("tlessctl(s3): serializing {s3_path} to {host_path}");
 
-                        let mut file = fs::File::create(Path::new(&host_file_path)).unwrap();
-                        file.write_all(&content).unwrap();
+                        let mut file = fs::File::create(&host_file_path).unwrap();
+                        Self::download_object(&client, bucket_name, &item.name, &mut file, decrypt)
+                            .await;
                     }
                 }
                 Err(e) => error!("invrs(s3): error: {:?}", e),
@@ -203,7 +498,7 @@ impl S3 {
         }
     }
 
-    pub async fn get_key(bucket_name: &str, key_name: &str) -> String {
+    pub async fn get_key(bucket_name: &str, key_name: &str, decrypt: bool) -> Vec<u8> {
         let client = Self::init_s3_client();
 
         // Return fast if the bucket does not exist
@@ -214,31 +509,193 @@ impl S3 {
 
         if !exists {
             warn!("invrs(s3): warning: bucket does not exist: {bucket_name}");
-            return "".to_string();
+            return Vec::new();
         }
 
-        // Loop until the object appears, and return its last modified date
-        let (mut object, _) = client
-            .get_object(bucket_name, key_name)
-            .send()
+        match decrypt {
+            true => Self::read_encrypted_object(&client, bucket_name, key_name).await,
+            false => Self::read_object(&client, bucket_name, key_name).await,
+        }
+    }
+
+    /// Print the size, ETag, last-modified date and user metadata of a key
+    pub async fn stat_key(bucket_name: &str, key_name: &str) {
+        let stat = Self::init_s3_client()
+            .stat_object(&StatObjectArgs::new(bucket_name, key_name).unwrap())
             .await
-            .unwrap()
-            .content
+            .unwrap();
+
+        println!("key: {bucket_name}/{key_name}");
+        println!("size: {} bytes", stat.size);
+        println!("etag: {}", stat.etag);
+        match stat.last_modified {
+            Some(last_modified) => println!("last-modified: {}", last_modified.to_rfc3339()),
+            None => println!("last-modified: -"),
+        }
+        if let Some(version_id) = &stat.version_id {
+            println!("version-id: {version_id}");
+        }
+
+        let mut user_metadata: Vec<(&String, &String)> = stat.user_metadata.iter().collect();
+        user_metadata.sort();
+        println!("user-metadata:");
+        for (key, value) in user_metadata {
+            println!("  {key}: {value}");
+        }
+
+        match EnvelopeMetadata::from_user_metadata(&stat.user_metadata) {
+            Some(metadata) => println!("encrypted: yes (key id: {})", metadata.key_id),
+            None => println!("encrypted: no"),
+        }
+    }
+
+    /// Download a single key to a file on the host, without buffering the
+    /// whole object in memory
+    pub async fn get_key_to_file(
+        bucket_name: &str,
+        key_name: &str,
+        host_path: &str,
+        decrypt: bool,
+    ) {
+        let client = Self::init_s3_client();
+
+        let host_path_rs = Path::new(host_path);
+        if let Some(parent) = host_path_rs.parent() {
+            if !parent.as_os_str().is_empty() && !parent.exists() {
+                fs::create_dir_all(parent).unwrap();
+            }
+        }
+
+        let mut file = fs::File::create(host_path_rs).unwrap();
+        let num_bytes =
+            Self::download_object(&client, bucket_name, key_name, &mut file, decrypt).await;
+        debug!("tlessctl(s3): downloaded {num_bytes} bytes from {bucket_name}/{key_name} to {host_path}");
+    }
+
+    /// Print a single key to stdout in an output format, without buffering
+    /// the whole object in memory
+    pub async fn get_key_to_stdout(
+        bucket_name: &str,
+        key_name: &str,
+        format: &S3OutputFormat,
+        decrypt: bool,
+    ) {
+        let client = Self::init_s3_client();
+
+        let mut stdout = format.writer(io::stdout());
+        Self::download_object(&client, bucket_name, key_name, &mut stdout, decrypt).await;
+        stdout
+            .finish()
+            .expect("tlessctl(s3): failed to write object to stdout");
+    }
+
+    /// Return the last-modified date of a key, if the key exists
+    async fn find_key(client: &Client, bucket_name: &str, key_name: &str) -> Option<DateTime<Utc>> {
+        let mut objects = client
+            .list_objects(bucket_name)
+            .recursive(true)
+            .prefix(Some(key_name.to_string()))
             .to_stream()
+            .await;
+
+        while let Some(result) = objects.next().await {
+            match result {
+                Ok(resp) => {
+                    for item in resp.contents {
+                        if item.name != key_name {
+                            continue;
+                        }
+
+                        debug!("item: {} (last: {:?})", item.name, item.last_modified);
+                        return Some(item.last_modified.unwrap_or_else(Utc::now));
+                    }
+                }
+                Err(e) => match e {
+                    Error::S3Error(s3_error) => match s3_error.code.as_str() {
+                        _ => panic!("invrs(s3): error: {}", s3_error.message),
+                    },
+                    _ => panic!("invrs(s3): error: {}", e),
+                },
+            }
+        }
+
+        None
+    }
+
+    /// Wait for a key by listening to MinIO bucket notifications. Returns
+    /// None if we can not subscribe to notifications (e.g. the server does
+    /// not support the listen API), in which case the caller should poll
+    async fn wait_for_key_notification(
+        client: &Client,
+        bucket_name: &str,
+        key_name: &str,
+    ) -> Option<DateTime<Utc>> {
+        // Subscribe before checking for the key, so that we do not miss an
+        // object created in between the check and the subscription
+        let (_, events) = match client
+            .listen_bucket_notification(bucket_name)
+            .prefix(Some(key_name.to_string()))
+            .events(Some(vec!["s3:ObjectCreated:*".to_string()]))
+            .send()
             .await
-            .unwrap();
+        {
+            Ok(resp) => resp,
+            Err(e) => {
+                warn!("tlessctl(s3): can not listen to bucket notifications: {e}");
+                return None;
+            }
+        };
+        let mut events = Box::pin(events);
 
-        let mut content = Vec::new();
-        while let Some(chunk) = object.next().await {
-            let chunk = chunk.expect("Failed to read chunk");
-            content.extend_from_slice(&chunk);
+        if let Some(last_modified) = Self::find_key(client, bucket_name, key_name).await {
+            return Some(last_modified);
         }
 
-        String::from_utf8(content).expect("tlessctl(s3): error converting object to string")
+        while let Some(event) = events.next().await {
+            match event {
+                // Keys in notification records are URL-encoded, so we
+                // re-check with a listing, which also gives us the
+                // last-modified date
+                Ok(_) => {
+                    if let Some(last_modified) = Self::find_key(client, bucket_name, key_name).await
+                    {
+                        return Some(last_modified);
+                    }
+                }
+                Err(e) => {
+                    warn!("tlessctl(s3): bucket notification stream failed: {e}");
+                    return None;
+                }
+            }
+        }
+
+        None
+    }
+
+    async fn poll_for_key(
+        client: &Client,
+        bucket_name: &str,
+        key_name: &str,
+        opts: &WaitForKeyOpts,
+    ) -> DateTime<Utc> {
+        let mut backoff = opts.initial_backoff;
+        loop {
+            if let Some(last_modified) = Self::find_key(client, bucket_name, key_name).await {
+                return last_modified;
+            }
+
+            debug!("invrs(s3): waiting for key ({key_name}) for {backoff:?}...");
+            tokio::time::sleep(backoff).await;
+            backoff = std::cmp::min(backoff * 2, opts.max_backoff);
+        }
     }
 
     /// Wait for a key to be ready, and return when it was last modified
-    pub async fn wait_for_key(bucket_name: &str, key_name: &str) -> Option<DateTime<Utc>> {
+    pub async fn wait_for_key(
+        bucket_name: &str,
+        key_name: &str,
+        opts: &WaitForKeyOpts,
+    ) -> Result<DateTime<Utc>, S3WaitError> {
         let client = Self::init_s3_client();
 
         // Return fast if the bucket does not exist
@@ -249,41 +706,31 @@ impl S3 {
 
         if !exists {
             debug!("invrs(s3): warning: bucket does not exist: {bucket_name}");
-            return None;
+            return Err(S3WaitError::BucketNotFound(bucket_name.to_string()));
         }
 
-        // Loop until the object appears, and return its last modified date
-        loop {
-            let mut objects = client
-                .list_objects(&bucket_name)
-                .recursive(true)
-                .prefix(Some(key_name.to_string()))
-                .to_stream()
-                .await;
-
-            while let Some(result) = objects.next().await {
-                match result {
-                    Ok(resp) => {
-                        for item in resp.contents {
-                            debug!(
-                                "item: {} (last: {})",
-                                item.name,
-                                item.last_modified.unwrap()
-                            );
-                            return item.last_modified;
-                        }
-                    }
-                    Err(e) => match e {
-                        Error::S3Error(s3_error) => match s3_error.code.as_str() {
-                            _ => panic!("invrs(s3): error: {}", s3_error.message),
-                        },
-                        _ => panic!("invrs(s3): error: {}", e),
-                    },
+        let wait = async {
+            if opts.use_notifications {
+                if let Some(last_modified) =
+                    Self::wait_for_key_notification(&client, bucket_name, key_name).await
+                {
+                    return last_modified;
                 }
             }
 
-            debug!("invrs(s3): waiting for key ({key_name})...");
-            thread::sleep(time::Duration::from_secs(2));
+            Self::poll_for_key(&client, bucket_name, key_name, opts).await
+        };
+
+        match opts.timeout {
+            Some(timeout) => {
+                tokio::time::timeout(timeout, wait)
+                    .await
+                    .map_err(|_| S3WaitError::Timeout {
+                        key: key_name.to_string(),
+                        waited: timeout,
+                    })
+            }
+            None => Ok(wait.await),
         }
     }
 
@@ -324,6 +771,116 @@ impl S3 {
         }
     }
 
+    /// List all objects under a prefix, together with their size and
+    /// last-modified date
+    pub async fn list_objects(bucket_name: &str, prefix: &str) -> Vec<StorageObject> {
+        let mut objects = Self::init_s3_client()
+            .list_objects(bucket_name)
+            .recursive(true)
+            .prefix(match prefix.is_empty() {
+                true => None,
+                false => Some(prefix.to_string()),
+            })
+            .to_stream()
+            .await;
+
+        let mut result = Vec::new();
+        while let Some(resp) = objects.next().await {
+            match resp {
+                Ok(resp) => {
+                    for item in resp.contents {
+                        result.push(StorageObject {
+                            key: item.name,
+                            size: item.size.unwrap_or(0) as u64,
+                            last_modified: item.last_modified,
+                        });
+                    }
+                }
+                Err(e) => error!("tlessctl(s3): error: {:?}", e),
+            }
+        }
+
+        result
+    }
+
+    fn format_bytes(num_bytes: u64) -> String {
+        const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+        let mut value = num_bytes as f64;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < UNITS.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        match unit {
+            0 => format!("{num_bytes} B"),
+            _ => format!("{value:.1} {}", UNITS[unit]),
+        }
+    }
+
+    /// Aggregate object counts and sizes per prefix, grouping keys by their
+    /// first `depth` path components, and print them sorted by size
+    pub async fn du(bucket_name: &str, prefix: &Option<String>, depth: usize, json: bool) {
+        let prefix = prefix.clone().unwrap_or_default();
+        let depth = depth.max(1);
+
+        let mut usage = BTreeMap::<String, S3PrefixUsage>::new();
+        for object in Self::list_objects(bucket_name, &prefix).await {
+            let parts: Vec<&str> = object.key.split('/').collect();
+            let group = match parts.len() > depth {
+                true => format!("{}/", parts[..depth].join("/")),
+                false => object.key.clone(),
+            };
+
+            let entry = usage.entry(group.clone()).or_insert(S3PrefixUsage {
+                prefix: group,
+                num_objects: 0,
+                num_bytes: 0,
+            });
+            entry.num_objects += 1;
+            entry.num_bytes += object.size;
+        }
+
+        let mut usage: Vec<S3PrefixUsage> = usage.into_values().collect();
+        usage.sort_by(|a, b| {
+            b.num_bytes
+                .cmp(&a.num_bytes)
+                .then_with(|| a.prefix.cmp(&b.prefix))
+        });
+
+        let total_objects: u64 = usage.iter().map(|u| u.num_objects).sum();
+        let total_bytes: u64 = usage.iter().map(|u| u.num_bytes).sum();
+
+        if json {
+            let output = serde_json::json!({
+                "bucket": bucket_name,
+                "prefix": prefix,
+                "depth": depth,
+                "total_objects": total_objects,
+                "total_bytes": total_bytes,
+                "prefixes": usage,
+            });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+            return;
+        }
+
+        println!("{:>12} {:>10}  PREFIX", "SIZE", "OBJECTS");
+        for entry in &usage {
+            println!(
+                "{:>12} {:>10}  {}",
+                Self::format_bytes(entry.num_bytes),
+                entry.num_objects,
+                entry.prefix
+            );
+        }
+        println!(
+            "{:>12} {:>10}  (total in {bucket_name}/{prefix})",
+            Self::format_bytes(total_bytes),
+            total_objects
+        );
+    }
+
     pub async fn upload_bytes(bucket_name: &str, s3_path: &str, bytes: &[u8]) {
         debug!(
             "tlessctl(s3): uploading {} bytes to {bucket_name}/{s3_path}",
@@ -352,7 +909,12 @@ impl S3 {
             .unwrap();
     }
 
-    pub async fn upload_dir(bucket_name: String, host_path: String, s3_path: String) {
+    pub async fn upload_dir(
+        bucket_name: String,
+        host_path: String,
+        s3_path: String,
+        encrypt: bool,
+    ) {
         debug!("invrs(s3): uploading {host_path} to {bucket_name}/{s3_path}");
 
         let client = Self::init_s3_client();
@@ -373,13 +935,19 @@ impl S3 {
         let path = Path::new(&host_path);
         for entry in fs::read_dir(path).unwrap() {
             let host_file_path: &Path = &entry.unwrap().path();
-            let content = ObjectContent::from(host_file_path);
             let s3_file_path = format!(
                 "{}/{}",
                 s3_path,
                 host_file_path.file_name().expect("").to_string_lossy()
             );
 
+            if encrypt {
+                Self::put_encrypted_object(&client, &bucket_name, &s3_file_path, host_file_path)
+                    .await;
+                continue;
+            }
+
+            let content = ObjectContent::from(host_file_path);
             client
                 .put_object_content(&bucket_name, &s3_file_path, content)
                 .send()
@@ -388,7 +956,7 @@ impl S3 {
         }
     }
 
-    pub async fn upload_file(bucket_name: &str, host_path: &str, s3_path: &str) {
+    pub async fn upload_file(bucket_name: &str, host_path: &str, s3_path: &str, encrypt: bool) {
         debug!("invrs(s3): uploading {host_path} to {s3_path}");
 
         let client = Self::init_s3_client();
@@ -405,13 +973,15 @@ impl S3 {
                 .unwrap();
         }
 
-        // Load file to byte array
-        let mut file = fs::File::open(host_path).unwrap();
-        let mut file_contents = Vec::new();
-        file.read_to_end(&mut file_contents).unwrap();
+        if encrypt {
+            Self::put_encrypted_object(&client, bucket_name, s3_path, Path::new(host_path)).await;
+            return;
+        }
 
-        // Upload it to S3
-        let content = ObjectContent::from(file_contents);
+        // Stream the file from disk. For objects larger than a single part,
+        // put_object_content performs a multipart upload, reading only one
+        // part into memory at a time
+        let content = ObjectContent::from(Path::new(host_path));
         client
             .put_object_content(&bucket_name, &s3_path, content)
             .send()
@@ -419,3 +989,147 @@ impl S3 {
             .unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+    use std::io::Read;
+
+    const TEST_BUCKET: &str = "tless-test-round-trip";
+
+    // Large enough that buffering the whole object would show in the peak
+    // memory usage of the process
+    const OBJECT_SIZE: usize = 512 << 20;
+    const CHUNK_SIZE: usize = 1 << 20;
+
+    // Headroom for the part buffers of the client and the runtime
+    const MAX_RSS_GROWTH: u64 = 128 << 20;
+
+    static ROUND_TRIP_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    /// Peak resident set size of this process, in bytes
+    fn get_peak_rss() -> u64 {
+        let status = fs::read_to_string("/proc/self/status").unwrap();
+        let kb = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmHWM:"))
+            .and_then(|value| {
+                value
+                    .trim()
+                    .trim_end_matches("kB")
+                    .trim()
+                    .parse::<u64>()
+                    .ok()
+            })
+            .expect("tlessctl(s3): no VmHWM in /proc/self/status");
+        kb * 1024
+    }
+
+    /// Write a file chunk-by-chunk, and return the hash of its contents
+    fn write_test_file(path: &Path) -> String {
+        let mut file = fs::File::create(path).unwrap();
+        let mut hasher = Sha256::new();
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        for i in 0..OBJECT_SIZE / CHUNK_SIZE {
+            // Vary the contents, so that a misplaced part changes the hash
+            for (j, byte) in chunk.iter_mut().enumerate() {
+                *byte = (i + j) as u8;
+            }
+            hasher.update(&chunk);
+            file.write_all(&chunk).unwrap();
+        }
+
+        hex::encode(hasher.finalize())
+    }
+
+    fn hash_file(path: &Path) -> String {
+        let mut file = fs::File::open(path).unwrap();
+        let mut hasher = Sha256::new();
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        loop {
+            let num_bytes = file.read(&mut chunk).unwrap();
+            if num_bytes == 0 {
+                break;
+            }
+            hasher.update(&chunk[..num_bytes]);
+        }
+
+        hex::encode(hasher.finalize())
+    }
+
+    /// Upload a large file and download it again. Peak memory is process
+    /// wide, so we only run one round trip at a time
+    async fn round_trip_large_object(encrypt: bool) {
+        let _guard = ROUND_TRIP_LOCK.lock().await;
+
+        let test_dir =
+            env::temp_dir().join(format!("tless-s3-test-{}-{encrypt}", std::process::id()));
+        fs::create_dir_all(&test_dir).unwrap();
+        let upload_path = test_dir.join("upload.bin");
+        let download_path = test_dir.join("download.bin");
+        let key = "large/object.bin";
+
+        let expected_hash = write_test_file(&upload_path);
+        let rss_before = get_peak_rss();
+
+        S3::upload_file(TEST_BUCKET, upload_path.to_str().unwrap(), key, encrypt).await;
+        S3::get_key_to_file(TEST_BUCKET, key, download_path.to_str().unwrap(), encrypt).await;
+
+        let rss_growth = get_peak_rss().saturating_sub(rss_before);
+        S3::clear_bucket(TEST_BUCKET.to_string()).await;
+
+        assert_eq!(
+            fs::metadata(&download_path).unwrap().len(),
+            OBJECT_SIZE as u64
+        );
+        assert_eq!(hash_file(&download_path), expected_hash);
+        assert!(
+            rss_growth < MAX_RSS_GROWTH,
+            "peak memory grew by {rss_growth} bytes for a {OBJECT_SIZE} byte object"
+        );
+
+        fs::remove_dir_all(&test_dir).unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MinIO server (e.g. 'invrs s3 server start')"]
+    async fn round_trip_large_object_in_constant_memory() {
+        round_trip_large_object(false).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a MinIO server (e.g. 'invrs s3 server start')"]
+    async fn round_trip_large_encrypted_object_in_constant_memory() {
+        round_trip_large_object(true).await;
+    }
+
+    #[test]
+    fn output_writer_renders_chunks_like_whole_object() {
+        let bytes: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        for (format, expected) in [
+            (S3OutputFormat::Raw, bytes.clone()),
+            (
+                S3OutputFormat::Hex,
+                format!("{}\n", hex::encode(&bytes)).into_bytes(),
+            ),
+            (
+                S3OutputFormat::Base64,
+                format!("{}\n", BASE64_STANDARD.encode(&bytes)).into_bytes(),
+            ),
+        ] {
+            // Chunk sizes that do not line up with base64 groups
+            for chunk_size in [1, 2, 7, 1000] {
+                let mut writer = format.writer(Vec::new());
+                for chunk in bytes.chunks(chunk_size) {
+                    writer.write_all(chunk).unwrap();
+                }
+                assert_eq!(
+                    writer.finish().unwrap(),
+                    expected,
+                    "{format:?}/{chunk_size}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::tasks::s3::S3;
+use crate::tasks::workflows::AvailableWorkflow;
+use log::{debug, info};
+use minio::s3::args::*;
+use minio::s3::error::Error;
+use minio::s3::types::{Filter, LifecycleConfig, LifecycleRule};
+
+// Prefix for the IDs of the rules we manage, so that we can tell them apart
+// from rules installed by other tools
+static RULE_ID_PREFIX: &str = "tless";
+
+impl S3 {
+    fn get_rule_id(prefix: &str) -> String {
+        format!(
+            "{RULE_ID_PREFIX}-{}",
+            prefix.trim_end_matches('/').replace('/', "-")
+        )
+    }
+
+    fn new_expiry_rule(prefix: &str, days: usize, noncurrent_days: Option<usize>) -> LifecycleRule {
+        LifecycleRule {
+            abort_incomplete_multipart_upload_days_after_initiation: None,
+            expiration_date: None,
+            expiration_days: Some(days),
+            expiration_expired_object_delete_marker: None,
+            filter: Filter {
+                and_operator: None,
+                prefix: Some(prefix.to_string()),
+                tag: None,
+            },
+            id: Self::get_rule_id(prefix),
+            noncurrent_version_expiration_noncurrent_days: noncurrent_days,
+            noncurrent_version_transition_noncurrent_days: None,
+            noncurrent_version_transition_storage_class: None,
+            status: true,
+            transition_date: None,
+            transition_days: None,
+            transition_storage_class: None,
+        }
+    }
+
+    fn new_noncurrent_rule(prefix: &str, noncurrent_days: usize) -> LifecycleRule {
+        LifecycleRule {
+            expiration_days: None,
+            ..Self::new_expiry_rule(prefix, 0, Some(noncurrent_days))
+        }
+    }
+
+    async fn get_lifecycle_rules(bucket_name: &str) -> Vec<LifecycleRule> {
+        match Self::init_s3_client()
+            .get_bucket_lifecycle(&GetBucketLifecycleArgs::new(bucket_name).unwrap())
+            .await
+        {
+            Ok(resp) => resp.config.rules,
+            Err(Error::S3Error(e)) if e.code == "NoSuchLifecycleConfiguration" => Vec::new(),
+            Err(e) => panic!("tlessctl(s3): error getting lifecycle rules: {e}"),
+        }
+    }
+
+    async fn set_lifecycle_rules(bucket_name: &str, rules: Vec<LifecycleRule>) {
+        let client = Self::init_s3_client();
+
+        if rules.is_empty() {
+            client
+                .delete_bucket_lifecycle(&DeleteBucketLifecycleArgs::new(bucket_name).unwrap())
+                .await
+                .unwrap();
+            return;
+        }
+
+        let config = LifecycleConfig { rules };
+        client
+            .set_bucket_lifecycle(&SetBucketLifecycleArgs {
+                extra_headers: None,
+                extra_query_params: None,
+                region: None,
+                bucket: bucket_name,
+                config: &config,
+            })
+            .await
+            .unwrap();
+    }
+
+    /// Add (or replace) the rules we manage, keeping any other rules
+    async fn merge_lifecycle_rules(bucket_name: &str, new_rules: Vec<LifecycleRule>) {
+        let mut rules = Self::get_lifecycle_rules(bucket_name).await;
+        rules.retain(|rule| !new_rules.iter().any(|new_rule| new_rule.id == rule.id));
+        rules.extend(new_rules);
+
+        Self::set_lifecycle_rules(bucket_name, rules).await;
+    }
+
+    async fn set_versioning(bucket_name: &str, enabled: bool) {
+        Self::init_s3_client()
+            .set_bucket_versioning(&SetBucketVersioningArgs::new(bucket_name, enabled).unwrap())
+            .await
+            .unwrap();
+    }
+
+    /// Expire objects under a prefix after a number of days. S3 lifecycle
+    /// rules have a granularity of days, so this is the shortest retention
+    /// we can ask MinIO for
+    pub async fn lifecycle_set_expiry(
+        bucket_name: &str,
+        prefix: &str,
+        days: usize,
+        noncurrent_days: Option<usize>,
+    ) {
+        if days == 0 {
+            panic!("tlessctl(s3): expiry must be at least one day");
+        }
+
+        debug!("tlessctl(s3): expiring {bucket_name}/{prefix} after {days} day(s)");
+        Self::merge_lifecycle_rules(
+            bucket_name,
+            vec![Self::new_expiry_rule(prefix, days, noncurrent_days)],
+        )
+        .await;
+    }
+
+    pub async fn lifecycle_remove_rule(bucket_name: &str, prefix: &str) {
+        let rule_id = Self::get_rule_id(prefix);
+        let mut rules = Self::get_lifecycle_rules(bucket_name).await;
+        let num_rules = rules.len();
+        rules.retain(|rule| rule.id != rule_id);
+
+        if rules.len() == num_rules {
+            info!("tlessctl(s3): no lifecycle rule for prefix {prefix} (id: {rule_id})");
+            return;
+        }
+
+        Self::set_lifecycle_rules(bucket_name, rules).await;
+    }
+
+    /// Install the default retention policy for run artefacts. Exec tokens
+    /// and outputs of every workflow expire, so crashed runs do not leave
+    /// them behind forever. We also enable versioning, so that overwritten
+    /// or removed objects, like DAGs and crypto contexts, can be recovered.
+    /// Versioning is bucket-wide in S3, so a bucket-wide rule drops old
+    /// versions (and the delete markers left behind) after noncurrent_days,
+    /// and run artefacts get a rule to drop theirs quickly
+    pub async fn lifecycle_apply_defaults(
+        bucket_name: &str,
+        exec_tokens_days: usize,
+        outputs_days: usize,
+        noncurrent_days: usize,
+    ) {
+        Self::set_versioning(bucket_name, true).await;
+
+        // When rules overlap, S3 applies the shortest expiry, so the run
+        // artefacts below still drop their old versions after one day
+        let mut rules = vec![LifecycleRule {
+            expiration_expired_object_delete_marker: Some(true),
+            id: format!("{RULE_ID_PREFIX}-noncurrent"),
+            ..Self::new_noncurrent_rule("", noncurrent_days)
+        }];
+        for workflow in AvailableWorkflow::iter_variants() {
+            rules.push(Self::new_expiry_rule(
+                &format!("{workflow}/exec-tokens/"),
+                exec_tokens_days,
+                Some(1),
+            ));
+            rules.push(Self::new_expiry_rule(
+                &format!("{workflow}/outputs/"),
+                outputs_days,
+                Some(1),
+            ));
+        }
+
+        Self::merge_lifecycle_rules(bucket_name, rules).await;
+        Self::lifecycle_show(bucket_name).await;
+    }
+
+    pub async fn lifecycle_clear(bucket_name: &str) {
+        let mut rules = Self::get_lifecycle_rules(bucket_name).await;
+        rules.retain(|rule| !rule.id.starts_with(&format!("{RULE_ID_PREFIX}-")));
+
+        Self::set_lifecycle_rules(bucket_name, rules).await;
+    }
+
+    pub async fn lifecycle_show(bucket_name: &str) {
+        let versioning = Self::init_s3_client()
+            .get_bucket_versioning(&GetBucketVersioningArgs::new(bucket_name).unwrap())
+            .await
+            .unwrap();
+        println!(
+            "versioning: {}",
+            match versioning.status {
+                Some(true) => "enabled",
+                Some(false) => "suspended",
+                None => "off",
+            }
+        );
+
+        let rules = Self::get_lifecycle_rules(bucket_name).await;
+        if rules.is_empty() {
+            println!("lifecycle rules: none");
+            return;
+        }
+
+        println!(
+            "{:<40} {:<30} {:>8} {:>12} {:>8}",
+            "ID", "PREFIX", "EXPIRY", "NONCURRENT", "STATUS"
+        );
+        for rule in &rules {
+            let days_or_dash = |days: Option<usize>| match days {
+                Some(days) => format!("{days}d"),
+                None => "-".to_string(),
+            };
+
+            println!(
+                "{:<40} {:<30} {:>8} {:>12} {:>8}",
+                rule.id,
+                rule.filter.prefix.clone().unwrap_or("-".to_string()),
+                days_or_dash(rule.expiration_days),
+                days_or_dash(rule.noncurrent_version_expiration_noncurrent_days),
+                match rule.status {
+                    true => "enabled",
+                    false => "disabled",
+                },
+            );
+        }
+    }
+}
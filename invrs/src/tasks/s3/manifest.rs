@@ -0,0 +1,213 @@
+use crate::tasks::s3::S3;
+use crate::tasks::storage::Storage;
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+/// A file in a dataset, relative to the dataset prefix
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Record of the exact content of a dataset we upload. Sizes and hashes are
+/// of the plain-text, so that encrypted datasets can be verified too
+#[derive(Debug, Deserialize, Serialize)]
+pub struct DatasetManifest {
+    pub version: u32,
+    /// Key prefix for a directory, or key for a single file
+    pub prefix: String,
+    pub encrypted: bool,
+    pub created_at: DateTime<Utc>,
+    pub files: Vec<ManifestEntry>,
+}
+
+impl DatasetManifest {
+    pub const VERSION: u32 = 1;
+
+    /// Hash a file as we read it, so that memory usage does not depend on
+    /// its size
+    fn new_entry(host_path: &Path, path: String) -> ManifestEntry {
+        let mut hasher = Sha256::new();
+        let size = fs::File::open(host_path)
+            .and_then(|file| io::copy(&mut BufReader::new(file), &mut hasher))
+            .unwrap_or_else(|e| {
+                panic!(
+                    "tlessctl(s3): error reading {} for manifest: {e}",
+                    host_path.display()
+                )
+            });
+
+        ManifestEntry {
+            path,
+            size,
+            sha256: hex::encode(hasher.finalize()),
+        }
+    }
+
+    /// Build the manifest for a host directory (uploaded file-by-file under
+    /// prefix) or for a single host file (uploaded to key prefix)
+    pub fn from_host_path(host_path: &Path, prefix: &str, encrypted: bool) -> Self {
+        let mut files = Vec::new();
+        if host_path.is_dir() {
+            for entry in fs::read_dir(host_path).unwrap() {
+                let host_file_path = entry.unwrap().path();
+                let path = host_file_path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string();
+                files.push(Self::new_entry(&host_file_path, path));
+            }
+        } else {
+            files.push(Self::new_entry(host_path, String::new()));
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Self {
+            version: Self::VERSION,
+            prefix: prefix.trim_end_matches('/').to_string(),
+            encrypted,
+            created_at: Utc::now(),
+            files,
+        }
+    }
+
+    /// Manifests live next to the dataset, in a '.manifests' directory of
+    /// the parent prefix. We do not store them under the dataset prefix, as
+    /// workflows list all the keys therein as inputs
+    pub fn get_key(prefix: &str) -> String {
+        match prefix.trim_end_matches('/').rsplit_once('/') {
+            Some((parent, name)) => format!("{parent}/.manifests/{name}.json"),
+            None => format!(".manifests/{}.json", prefix.trim_end_matches('/')),
+        }
+    }
+
+    fn get_object_key(&self, entry: &ManifestEntry) -> String {
+        match entry.path.is_empty() {
+            true => self.prefix.clone(),
+            false => format!("{}/{}", self.prefix, entry.path),
+        }
+    }
+
+    pub async fn upload(&self, storage: &dyn Storage) {
+        let key = Self::get_key(&self.prefix);
+        debug!(
+            "tlessctl(s3): writing manifest for {} ({} files) to {key}",
+            self.prefix,
+            self.files.len()
+        );
+
+        storage
+            .put_bytes(&key, &serde_json::to_vec_pretty(self).unwrap())
+            .await;
+    }
+}
+
+/// Result of checking the objects under a prefix against its manifest
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub num_ok: usize,
+    pub missing: Vec<String>,
+    pub extra: Vec<String>,
+    pub corrupted: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.corrupted.is_empty()
+    }
+}
+
+impl S3 {
+    /// Re-hash all the objects under a prefix, and compare them against the
+    /// manifest we wrote when uploading the dataset. We hash each object as
+    /// we download it, and report objects that fail to decrypt as corrupted
+    pub async fn verify(bucket_name: &str, prefix: &str) -> VerifyReport {
+        let client = Self::init_s3_client();
+        let prefix = prefix.trim_end_matches('/');
+        let manifest_key = DatasetManifest::get_key(prefix);
+        let manifest: DatasetManifest =
+            serde_json::from_slice(&Self::get_key(bucket_name, &manifest_key, false).await)
+                .unwrap_or_else(|e| {
+                    panic!(
+                        "tlessctl(s3): error reading manifest ({bucket_name}/{manifest_key}): {e}"
+                    )
+                });
+
+        let mut expected: BTreeMap<String, &ManifestEntry> = manifest
+            .files
+            .iter()
+            .map(|entry| (manifest.get_object_key(entry), entry))
+            .collect();
+
+        let mut report = VerifyReport::default();
+        for object in Self::list_objects(bucket_name, prefix).await {
+            // Ignore keys that only share a name prefix (e.g. foo-bar/ for foo)
+            let is_in_dataset =
+                object.key == prefix || object.key.starts_with(&format!("{prefix}/"));
+            if !is_in_dataset {
+                continue;
+            }
+
+            let entry = match expected.remove(&object.key) {
+                Some(entry) => entry,
+                None => {
+                    report.extra.push(object.key);
+                    continue;
+                }
+            };
+
+            let mut hasher = Sha256::new();
+            let mut size: u64 = 0;
+            let on_chunk = |chunk: &[u8]| {
+                hasher.update(chunk);
+                size += chunk.len() as u64;
+            };
+            let is_readable = match manifest.encrypted {
+                true => Self::stream_encrypted_object(&client, bucket_name, &object.key, on_chunk)
+                    .await
+                    .is_ok(),
+                false => {
+                    Self::stream_object(&client, bucket_name, &object.key, on_chunk).await;
+                    true
+                }
+            };
+            if !is_readable || size != entry.size || hex::encode(hasher.finalize()) != entry.sha256
+            {
+                report.corrupted.push(object.key);
+                continue;
+            }
+
+            report.num_ok += 1;
+        }
+        report.missing = expected.into_keys().collect();
+
+        for key in &report.missing {
+            println!("missing: {key}");
+        }
+        for key in &report.extra {
+            println!("extra: {key}");
+        }
+        for key in &report.corrupted {
+            println!("corrupted: {key}");
+        }
+        info!(
+            "tlessctl(s3): verified {bucket_name}/{prefix} against manifest from {}: {} ok, {} missing, {} extra, {} corrupted",
+            manifest.created_at,
+            report.num_ok,
+            report.missing.len(),
+            report.extra.len(),
+            report.corrupted.len()
+        );
+
+        report
+    }
+}
@@ -0,0 +1,79 @@
+use crate::tasks::s3::S3;
+use clap::ValueEnum;
+use http::Method;
+use log::debug;
+use minio::s3::args::GetPresignedObjectUrlArgs;
+use std::{fmt, time};
+
+// S3 does not accept presigned URLs that are valid for more than a week
+const MAX_PRESIGN_EXPIRY_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// HTTP method a presigned URL is valid for
+#[derive(Clone, Debug, ValueEnum)]
+pub enum S3PresignMethod {
+    Get,
+    Put,
+}
+
+impl fmt::Display for S3PresignMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            S3PresignMethod::Get => write!(f, "get"),
+            S3PresignMethod::Put => write!(f, "put"),
+        }
+    }
+}
+
+impl S3 {
+    /// Parse an expiry duration like 3600, 30m, 12h, or 7d. Bare numbers are
+    /// seconds
+    pub fn parse_expiry(expiry: &str) -> time::Duration {
+        Self::try_parse_expiry(expiry).unwrap_or_else(|e| panic!("tlessctl(s3): {e}"))
+    }
+
+    pub fn try_parse_expiry(expiry: &str) -> Result<time::Duration, String> {
+        let expiry = expiry.trim();
+        let (value, unit_secs) = match expiry.char_indices().last() {
+            Some((idx, 's')) => (&expiry[..idx], 1),
+            Some((idx, 'm')) => (&expiry[..idx], 60),
+            Some((idx, 'h')) => (&expiry[..idx], 60 * 60),
+            Some((idx, 'd')) => (&expiry[..idx], 24 * 60 * 60),
+            _ => (expiry, 1),
+        };
+
+        let secs = value.parse::<u64>().map_err(|_| {
+            format!("invalid expiry (expected e.g. 3600, 30m, 12h, or 7d): {expiry}")
+        })? * unit_secs;
+
+        if secs == 0 || secs > MAX_PRESIGN_EXPIRY_SECS {
+            return Err(format!("expiry must be between 1s and 7d (got: {expiry})"));
+        }
+
+        Ok(time::Duration::from_secs(secs))
+    }
+
+    /// Generate a URL that grants access to a key without MinIO credentials.
+    /// The URL embeds the MinIO endpoint we sign against, so MINIO_URL and
+    /// MINIO_PORT must point to an address the recipient can reach
+    pub async fn presign(
+        bucket_name: &str,
+        key_name: &str,
+        expiry: time::Duration,
+        method: &S3PresignMethod,
+    ) -> String {
+        let http_method = match method {
+            S3PresignMethod::Get => Method::GET,
+            S3PresignMethod::Put => Method::PUT,
+        };
+
+        let mut args = GetPresignedObjectUrlArgs::new(bucket_name, key_name, http_method).unwrap();
+        args.expiry_seconds = Some(expiry.as_secs().try_into().unwrap());
+
+        debug!("tlessctl(s3): presigning {method} {bucket_name}/{key_name} for {expiry:?}");
+        Self::init_s3_client()
+            .get_presigned_object_url(&args)
+            .await
+            .unwrap()
+            .url
+    }
+}
@@ -0,0 +1,315 @@
+use crate::env::Env;
+use crate::tasks::s3::{S3, S3_PASSWORD, S3_USER};
+use clap::ValueEnum;
+use log::{debug, info, warn};
+use minio::s3::args::{BucketExistsArgs, MakeBucketArgs};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::{fmt, fs, time};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+// Same MinIO release we deploy in the cluster
+static MINIO_IMAGE: &str = "minio/minio:RELEASE.2024-09-13T20-26-02Z";
+static MINIO_CTR_NAME: &str = "tless-minio";
+static DEFAULT_BUCKET_NAME: &str = "tless";
+
+/// How we run the local MinIO server
+#[derive(Clone, Debug, Deserialize, Serialize, ValueEnum, PartialEq)]
+pub enum S3ServerMode {
+    /// Docker container with the same MinIO release we deploy in the cluster
+    Container,
+    /// 'minio' binary in the PATH
+    Process,
+}
+
+impl fmt::Display for S3ServerMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            S3ServerMode::Container => write!(f, "container"),
+            S3ServerMode::Process => write!(f, "process"),
+        }
+    }
+}
+
+/// What we need to remember about a running server to stop it, or to tell
+/// if it is up
+#[derive(Debug, Deserialize, Serialize)]
+struct S3ServerState {
+    mode: S3ServerMode,
+    data_dir: PathBuf,
+    port: u16,
+    console_port: u16,
+    /// PID for processes, container name for containers
+    id: String,
+}
+
+impl S3 {
+    fn get_server_root() -> PathBuf {
+        let mut path = Env::proj_root();
+        path.push("dev");
+        path.push("minio");
+        path
+    }
+
+    pub fn get_server_default_data_dir() -> PathBuf {
+        let mut path = Self::get_server_root();
+        path.push("data");
+        path
+    }
+
+    fn get_server_state_file() -> PathBuf {
+        let mut path = Self::get_server_root();
+        path.push("server.json");
+        path
+    }
+
+    fn read_server_state() -> Option<S3ServerState> {
+        let state = fs::read(Self::get_server_state_file()).ok()?;
+        Some(serde_json::from_slice(&state).expect("tlessctl(s3): malformed server state file"))
+    }
+
+    fn is_server_running(state: &S3ServerState) -> bool {
+        match state.mode {
+            S3ServerMode::Container => Command::new("docker")
+                .args(["inspect", "-f", "{{.State.Running}}", &state.id])
+                .output()
+                .map(|output| String::from_utf8_lossy(&output.stdout).trim() == "true")
+                .unwrap_or(false),
+            S3ServerMode::Process => PathBuf::from(format!("/proc/{}", state.id)).exists(),
+        }
+    }
+
+    /// Query MinIO's readiness probe. We only need a status line, so a plain
+    /// HTTP/1.0 request saves us pulling in an HTTP client
+    async fn is_server_ready(port: u16) -> bool {
+        let mut stream = match TcpStream::connect(("127.0.0.1", port)).await {
+            Ok(stream) => stream,
+            Err(_) => return false,
+        };
+
+        let request = format!("GET /minio/health/ready HTTP/1.0\r\nHost: localhost:{port}\r\n\r\n");
+        if stream.write_all(request.as_bytes()).await.is_err() {
+            return false;
+        }
+
+        // MinIO closes the connection once it answers, but we do not wait
+        // for it for longer than a second
+        let mut response = String::new();
+        let _ = tokio::time::timeout(
+            time::Duration::from_secs(1),
+            stream.read_to_string(&mut response),
+        )
+        .await;
+        response
+            .lines()
+            .next()
+            .map(|status_line| status_line.split_whitespace().nth(1) == Some("200"))
+            .unwrap_or(false)
+    }
+
+    async fn wait_for_server_ready(port: u16, timeout: time::Duration) {
+        let start = time::Instant::now();
+        while !Self::is_server_ready(port).await {
+            if start.elapsed() > timeout {
+                panic!(
+                    "tlessctl(s3): MinIO not ready after {timeout:?} (see {})",
+                    Self::get_server_root().display()
+                );
+            }
+
+            debug!("tlessctl(s3): waiting for MinIO to be ready on port {port}...");
+            tokio::time::sleep(time::Duration::from_millis(250)).await;
+        }
+    }
+
+    fn start_server_container(data_dir: &Path, port: u16, console_port: u16) -> String {
+        let output = Command::new("docker")
+            .arg("run")
+            .arg("-d")
+            .arg("--rm")
+            .arg("--name")
+            .arg(MINIO_CTR_NAME)
+            .arg("-p")
+            .arg(format!("{port}:9000"))
+            .arg("-p")
+            .arg(format!("{console_port}:9001"))
+            .arg("-v")
+            .arg(format!("{}:/data", data_dir.display()))
+            .arg("--env")
+            .arg(format!("MINIO_ROOT_USER={S3_USER}"))
+            .arg("--env")
+            .arg(format!("MINIO_ROOT_PASSWORD={S3_PASSWORD}"))
+            .arg(MINIO_IMAGE)
+            .args(["server", "/data", "--console-address", ":9001"])
+            .stderr(Stdio::inherit())
+            .output()
+            .expect("tlessctl(s3): failed to execute docker command");
+
+        if !output.status.success() {
+            panic!("tlessctl(s3): failed to start MinIO container");
+        }
+
+        MINIO_CTR_NAME.to_string()
+    }
+
+    fn start_server_process(data_dir: &Path, port: u16, console_port: u16) -> String {
+        let mut log_path = Self::get_server_root();
+        log_path.push("minio.log");
+        let log_file = fs::File::create(&log_path).unwrap();
+
+        let child = Command::new("minio")
+            .arg("server")
+            .arg(data_dir)
+            .arg("--address")
+            .arg(format!(":{port}"))
+            .arg("--console-address")
+            .arg(format!(":{console_port}"))
+            .env("MINIO_ROOT_USER", S3_USER)
+            .env("MINIO_ROOT_PASSWORD", S3_PASSWORD)
+            .stdin(Stdio::null())
+            .stdout(log_file.try_clone().unwrap())
+            .stderr(log_file)
+            .spawn()
+            .expect("tlessctl(s3): failed to start MinIO (is 'minio' in the PATH?)");
+
+        info!("tlessctl(s3): MinIO logs are in {}", log_path.display());
+        child.id().to_string()
+    }
+
+    fn print_server_env(port: u16) {
+        // Print to stdout, so that it can be eval'd from a shell
+        println!("export MINIO_URL=localhost");
+        println!("export MINIO_PORT={port}");
+    }
+
+    /// Start a local MinIO server, wait for it to be ready, and create the
+    /// bucket we use for workflows. We print the environment that subsequent
+    /// commands need to talk to it: eval "$(tlessctl s3 server start)"
+    pub async fn server_start(
+        mode: &S3ServerMode,
+        data_dir: &Option<String>,
+        port: u16,
+        console_port: u16,
+    ) {
+        if let Some(state) = Self::read_server_state() {
+            if Self::is_server_running(&state) {
+                info!(
+                    "tlessctl(s3): MinIO already running ({}, id: {})",
+                    state.mode, state.id
+                );
+                Self::print_server_env(state.port);
+                return;
+            }
+        }
+
+        let data_dir = match data_dir {
+            Some(data_dir) => PathBuf::from(data_dir),
+            None => Self::get_server_default_data_dir(),
+        };
+        fs::create_dir_all(&data_dir).unwrap();
+        fs::create_dir_all(Self::get_server_root()).unwrap();
+        let data_dir = fs::canonicalize(data_dir).unwrap();
+
+        let id = match mode {
+            S3ServerMode::Container => Self::start_server_container(&data_dir, port, console_port),
+            S3ServerMode::Process => Self::start_server_process(&data_dir, port, console_port),
+        };
+
+        let state = S3ServerState {
+            mode: mode.clone(),
+            data_dir,
+            port,
+            console_port,
+            id,
+        };
+        fs::write(
+            Self::get_server_state_file(),
+            serde_json::to_vec_pretty(&state).unwrap(),
+        )
+        .unwrap();
+
+        Self::wait_for_server_ready(port, time::Duration::from_secs(30)).await;
+
+        // init_s3_client reads the endpoint from the environment
+        unsafe {
+            std::env::set_var("MINIO_URL", "localhost");
+            std::env::set_var("MINIO_PORT", port.to_string());
+        }
+        let client = Self::init_s3_client();
+        let exists = client
+            .bucket_exists(&BucketExistsArgs::new(DEFAULT_BUCKET_NAME).unwrap())
+            .await
+            .unwrap();
+        if !exists {
+            client
+                .make_bucket(&MakeBucketArgs::new(DEFAULT_BUCKET_NAME).unwrap())
+                .await
+                .unwrap();
+        }
+
+        info!(
+            "tlessctl(s3): MinIO ready on localhost:{port} (console on :{console_port}, data in {})",
+            state.data_dir.display()
+        );
+        Self::print_server_env(port);
+    }
+
+    pub fn server_stop() {
+        let state = match Self::read_server_state() {
+            Some(state) => state,
+            None => {
+                info!("tlessctl(s3): no local MinIO server to stop");
+                return;
+            }
+        };
+
+        if Self::is_server_running(&state) {
+            let (cmd, args) = match state.mode {
+                S3ServerMode::Container => ("docker", vec!["stop", state.id.as_str()]),
+                S3ServerMode::Process => ("kill", vec![state.id.as_str()]),
+            };
+            let status = Command::new(cmd)
+                .args(args)
+                .stdout(Stdio::null())
+                .status()
+                .expect("tlessctl(s3): failed to stop MinIO");
+            if !status.success() {
+                panic!(
+                    "tlessctl(s3): failed to stop MinIO ({}, id: {})",
+                    state.mode, state.id
+                );
+            }
+        } else {
+            warn!("tlessctl(s3): MinIO was not running (id: {})", state.id);
+        }
+
+        fs::remove_file(Self::get_server_state_file()).unwrap();
+    }
+
+    pub async fn server_status() {
+        let state = match Self::read_server_state() {
+            Some(state) => state,
+            None => {
+                println!("status: stopped");
+                return;
+            }
+        };
+
+        let status = match (
+            Self::is_server_running(&state),
+            Self::is_server_ready(state.port).await,
+        ) {
+            (true, true) => "ready",
+            (true, false) => "starting",
+            (false, _) => "stopped",
+        };
+
+        println!("status: {status}");
+        println!("mode: {} (id: {})", state.mode, state.id);
+        println!("endpoint: localhost:{}", state.port);
+        println!("console: localhost:{}", state.console_port);
+        println!("data dir: {}", state.data_dir.display());
+    }
+}
@@ -0,0 +1,220 @@
+use crate::tasks::s3::S3;
+use chrono::{DateTime, Utc};
+use log::{debug, info};
+use minio::s3::args::{CopyObjectArgs, CopySource};
+use minio::s3::client::Client;
+use serde::{Deserialize, Serialize};
+
+// All snapshots live under this prefix, each in its own sub-directory
+static SNAPSHOTS_PREFIX: &str = "snapshots";
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SnapshotObject {
+    /// Key of the object relative to the snapshotted prefix
+    pub path: String,
+    pub size: u64,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Manifest we store alongside the copied objects of a snapshot
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SnapshotManifest {
+    pub name: String,
+    /// Prefix the snapshot was taken from (and is restored to)
+    pub prefix: String,
+    pub created_at: DateTime<Utc>,
+    pub objects: Vec<SnapshotObject>,
+}
+
+impl S3 {
+    fn get_snapshot_root(name: &str) -> String {
+        if name.is_empty() || name.contains('/') {
+            panic!("tlessctl(s3): invalid snapshot name: '{name}'");
+        }
+
+        format!("{SNAPSHOTS_PREFIX}/{name}")
+    }
+
+    fn get_snapshot_manifest_key(name: &str) -> String {
+        format!("{}/snapshot.json", Self::get_snapshot_root(name))
+    }
+
+    fn get_snapshot_data_key(name: &str, path: &str) -> String {
+        format!("{}/data/{path}", Self::get_snapshot_root(name))
+    }
+
+    /// Snapshots are of whole directories, so we make sure that a prefix like
+    /// 'finra' does not also match 'finra-foo/'
+    fn get_snapshot_prefix(prefix: &str) -> String {
+        format!("{}/", prefix.trim_end_matches('/'))
+    }
+
+    async fn copy_object(client: &Client, bucket_name: &str, src_key: &str, dst_key: &str) {
+        debug!("tlessctl(s3): copying {bucket_name}/{src_key} to {bucket_name}/{dst_key}");
+
+        // Server-side copies also preserve the user metadata, including
+        // the wrapping information of envelope-encrypted objects
+        let args = CopyObjectArgs::new(
+            bucket_name,
+            dst_key,
+            CopySource::new(bucket_name, src_key).unwrap(),
+        )
+        .unwrap();
+        client
+            .copy_object(&args)
+            .await
+            .unwrap_or_else(|e| panic!("tlessctl(s3): error copying {src_key} to {dst_key}: {e}"));
+    }
+
+    async fn get_snapshot_manifest(bucket_name: &str, name: &str) -> Option<SnapshotManifest> {
+        let manifest_key = Self::get_snapshot_manifest_key(name);
+        let exists = Self::list_objects(bucket_name, &manifest_key)
+            .await
+            .iter()
+            .any(|object| object.key == manifest_key);
+        if !exists {
+            return None;
+        }
+
+        let manifest =
+            serde_json::from_slice(&Self::get_key(bucket_name, &manifest_key, false).await)
+                .unwrap_or_else(|e| {
+                    panic!("tlessctl(s3): malformed snapshot manifest ({manifest_key}): {e}")
+                });
+        Some(manifest)
+    }
+
+    /// Copy all objects under a prefix to a new snapshot. Copies happen in
+    /// the server, so snapshotting large datasets is cheap
+    pub async fn snapshot_save(bucket_name: &str, prefix: &str, name: &str) {
+        if Self::get_snapshot_manifest(bucket_name, name)
+            .await
+            .is_some()
+        {
+            panic!("tlessctl(s3): snapshot already exists: {name}");
+        }
+
+        let prefix = Self::get_snapshot_prefix(prefix);
+        let client = Self::init_s3_client();
+        let mut objects = Vec::new();
+        for object in Self::list_objects(bucket_name, &prefix).await {
+            let path = object.key.strip_prefix(&prefix).unwrap().to_string();
+            Self::copy_object(
+                &client,
+                bucket_name,
+                &object.key,
+                &Self::get_snapshot_data_key(name, &path),
+            )
+            .await;
+
+            objects.push(SnapshotObject {
+                path,
+                size: object.size,
+                last_modified: object.last_modified,
+            });
+        }
+
+        if objects.is_empty() {
+            panic!("tlessctl(s3): no objects to snapshot under {bucket_name}/{prefix}");
+        }
+
+        // Write the manifest last, so that a snapshot only shows up once all
+        // its objects have been copied
+        let manifest = SnapshotManifest {
+            name: name.to_string(),
+            prefix: prefix.trim_end_matches('/').to_string(),
+            created_at: Utc::now(),
+            objects,
+        };
+        Self::upload_bytes(
+            bucket_name,
+            &Self::get_snapshot_manifest_key(name),
+            &serde_json::to_vec_pretty(&manifest).unwrap(),
+        )
+        .await;
+
+        info!(
+            "tlessctl(s3): saved {} objects from {bucket_name}/{prefix} to snapshot {name}",
+            manifest.objects.len()
+        );
+    }
+
+    pub async fn snapshot_list(bucket_name: &str) {
+        let manifest_keys: Vec<String> = Self::list_objects(bucket_name, SNAPSHOTS_PREFIX)
+            .await
+            .into_iter()
+            .map(|object| object.key)
+            .filter(|key| key.ends_with("/snapshot.json"))
+            .collect();
+
+        println!(
+            "{:<24} {:<24} {:<26} {:>8} {:>10}",
+            "NAME", "PREFIX", "CREATED", "OBJECTS", "SIZE"
+        );
+        for manifest_key in manifest_keys {
+            let manifest: SnapshotManifest =
+                serde_json::from_slice(&Self::get_key(bucket_name, &manifest_key, false).await)
+                    .unwrap_or_else(|e| {
+                        panic!("tlessctl(s3): malformed snapshot manifest ({manifest_key}): {e}")
+                    });
+
+            println!(
+                "{:<24} {:<24} {:<26} {:>8} {:>10}",
+                manifest.name,
+                manifest.prefix,
+                manifest.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                manifest.objects.len(),
+                Self::format_bytes(manifest.objects.iter().map(|object| object.size).sum())
+            );
+        }
+    }
+
+    /// Copy the objects in a snapshot back to the prefix they were taken
+    /// from. If clean is set, we first remove all objects under the prefix,
+    /// so that the prefix ends up exactly as it was when we took the snapshot
+    pub async fn snapshot_restore(bucket_name: &str, name: &str, clean: bool) {
+        let manifest = match Self::get_snapshot_manifest(bucket_name, name).await {
+            Some(manifest) => manifest,
+            None => panic!("tlessctl(s3): snapshot not found: {name}"),
+        };
+
+        let prefix = Self::get_snapshot_prefix(&manifest.prefix);
+        if clean {
+            Self::clear_dir(bucket_name.to_string(), prefix.clone()).await;
+        }
+
+        let client = Self::init_s3_client();
+        for object in &manifest.objects {
+            Self::copy_object(
+                &client,
+                bucket_name,
+                &Self::get_snapshot_data_key(name, &object.path),
+                &format!("{prefix}{}", object.path),
+            )
+            .await;
+        }
+
+        info!(
+            "tlessctl(s3): restored {} objects from snapshot {name} to {bucket_name}/{prefix}",
+            manifest.objects.len()
+        );
+    }
+
+    pub async fn snapshot_delete(bucket_name: &str, name: &str) {
+        if Self::get_snapshot_manifest(bucket_name, name)
+            .await
+            .is_none()
+        {
+            panic!("tlessctl(s3): snapshot not found: {name}");
+        }
+
+        // Remove the manifest first, so that a half-deleted snapshot is not
+        // listed anymore
+        Self::clear_object(bucket_name, &Self::get_snapshot_manifest_key(name)).await;
+        Self::clear_dir(
+            bucket_name.to_string(),
+            format!("{}/", Self::get_snapshot_root(name)),
+        )
+        .await;
+    }
+}
@@ -0,0 +1,133 @@
+use crate::tasks::s3::S3;
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures_util::StreamExt;
+use log::{info, warn};
+use minio::s3::client::Client;
+use std::collections::BTreeMap;
+use std::time;
+
+impl S3 {
+    fn print_watch_event(time: DateTime<Utc>, event: &str, key: &str, size: Option<u64>) {
+        println!(
+            "{}  {event:<8}  {:>10}  {key}",
+            time.to_rfc3339_opts(SecondsFormat::Millis, true),
+            match size {
+                Some(size) => Self::format_bytes(size),
+                None => "-".to_string(),
+            }
+        );
+    }
+
+    /// Stream events using MinIO's listen API. Returns if we can not
+    /// subscribe to notifications or the stream breaks, so that the caller
+    /// can fall back to polling
+    async fn watch_notifications(client: &Client, bucket_name: &str, prefix: &str) {
+        let (_, events) = match client
+            .listen_bucket_notification(bucket_name)
+            .prefix(Some(prefix.to_string()))
+            .events(Some(vec![
+                "s3:ObjectCreated:*".to_string(),
+                "s3:ObjectRemoved:*".to_string(),
+            ]))
+            .send()
+            .await
+        {
+            Ok(resp) => resp,
+            Err(e) => {
+                warn!("tlessctl(s3): can not listen to bucket notifications: {e}");
+                return;
+            }
+        };
+        let mut events = Box::pin(events);
+
+        info!("tlessctl(s3): watching {bucket_name}/{prefix} (bucket notifications)");
+        while let Some(event) = events.next().await {
+            let records = match event {
+                Ok(records) => records,
+                Err(e) => {
+                    warn!("tlessctl(s3): bucket notification stream failed: {e}");
+                    return;
+                }
+            };
+
+            for record in records.records {
+                // Keys in notification records are URL-encoded
+                let key = urlencoding::decode(&record.s3.object.key)
+                    .map(|key| key.into_owned())
+                    .unwrap_or(record.s3.object.key.clone());
+
+                if record.event_name.starts_with("s3:ObjectRemoved") {
+                    Self::print_watch_event(record.event_time, "deleted", &key, None);
+                } else {
+                    Self::print_watch_event(
+                        record.event_time,
+                        "created",
+                        &key,
+                        record.s3.object.size,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Periodically list the prefix and print the differences between
+    /// consecutive listings. Objects created and deleted in between two
+    /// listings go unnoticed
+    async fn watch_poll(bucket_name: &str, prefix: &str, interval: time::Duration) {
+        info!("tlessctl(s3): watching {bucket_name}/{prefix} (polling every {interval:?})");
+
+        let list = || async {
+            Self::list_objects(bucket_name, prefix)
+                .await
+                .into_iter()
+                .map(|object| (object.key.clone(), object))
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let mut known = list().await;
+        loop {
+            tokio::time::sleep(interval).await;
+
+            let current = list().await;
+            for (key, object) in &current {
+                let event = match known.get(key) {
+                    None => "created",
+                    Some(old) if old.last_modified != object.last_modified => "modified",
+                    Some(old) if old.size != object.size => "modified",
+                    Some(_) => continue,
+                };
+
+                Self::print_watch_event(
+                    object.last_modified.unwrap_or_else(Utc::now),
+                    event,
+                    key,
+                    Some(object.size),
+                );
+            }
+            for key in known.keys() {
+                if !current.contains_key(key) {
+                    Self::print_watch_event(Utc::now(), "deleted", key, None);
+                }
+            }
+
+            known = current;
+        }
+    }
+
+    /// Print creations and deletions of keys under a prefix as they happen,
+    /// until interrupted
+    pub async fn watch(
+        bucket_name: &str,
+        prefix: &str,
+        poll_interval: time::Duration,
+        use_notifications: bool,
+    ) {
+        if use_notifications {
+            let client = Self::init_s3_client();
+            Self::watch_notifications(&client, bucket_name, prefix).await;
+            warn!("tlessctl(s3): falling back to polling");
+        }
+
+        Self::watch_poll(bucket_name, prefix, poll_interval).await;
+    }
+}
@@ -0,0 +1,523 @@
+use crate::tasks::s3::{S3PresignMethod, S3WaitError, WaitForKeyOpts, S3};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use log::debug;
+use std::path::{Path, PathBuf};
+use std::{fs, io, time};
+
+/// An object in a storage backend
+#[derive(Clone, Debug)]
+pub struct StorageObject {
+    pub key: String,
+    pub size: u64,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Operations we need from an object store to upload workflow state and
+/// run experiments. Keys are '/'-separated paths relative to the bucket
+#[async_trait(?Send)]
+pub trait Storage: Send + Sync {
+    /// URL that identifies this backend (e.g. s3://tless)
+    fn url(&self) -> String;
+
+    async fn put_bytes(&self, key: &str, bytes: &[u8]);
+
+    /// Upload a file from the host. If encrypt is set, we envelope-encrypt
+    /// the file on its way up (see Crypto::envelope_encrypt)
+    async fn put_file(&self, host_path: &Path, key: &str, encrypt: bool);
+
+    /// Upload all files in a host directory under a given prefix
+    async fn put_dir(&self, host_path: &Path, prefix: &str, encrypt: bool) {
+        for entry in fs::read_dir(host_path).unwrap() {
+            let host_file_path = entry.unwrap().path();
+            let key = format!(
+                "{prefix}/{}",
+                host_file_path.file_name().unwrap().to_string_lossy()
+            );
+            self.put_file(&host_file_path, &key, encrypt).await;
+        }
+    }
+
+    async fn get_bytes(&self, key: &str) -> Vec<u8>;
+
+    async fn get_to_file(&self, key: &str, host_path: &Path);
+
+    async fn list(&self, prefix: &str) -> Vec<StorageObject>;
+
+    async fn delete(&self, key: &str);
+
+    async fn delete_prefix(&self, prefix: &str);
+
+    /// Wait for a key to be ready, and return when it was last modified
+    async fn wait_for_key(
+        &self,
+        key: &str,
+        opts: &WaitForKeyOpts,
+    ) -> Result<DateTime<Utc>, S3WaitError>;
+
+    /// URL to download a key without credentials, if the backend supports it
+    async fn presign_get(&self, _key: &str, _expiry: time::Duration) -> Option<String> {
+        None
+    }
+}
+
+/// Pick a storage backend from a URL: s3://<bucket> uses MinIO, and
+/// file://<path> uses a directory in the local filesystem
+pub fn from_url(url: &str) -> Box<dyn Storage> {
+    if let Some(bucket_name) = url.strip_prefix("s3://") {
+        let bucket_name = bucket_name.trim_end_matches('/');
+        if bucket_name.is_empty() || bucket_name.contains('/') {
+            panic!("tlessctl(storage): expected s3://<bucket>, got: {url}");
+        }
+
+        return Box::new(MinioStorage {
+            bucket_name: bucket_name.to_string(),
+        });
+    }
+
+    if let Some(root) = url.strip_prefix("file://") {
+        if root.is_empty() {
+            panic!("tlessctl(storage): expected file://<path>, got: {url}");
+        }
+
+        return Box::new(FsStorage {
+            root: PathBuf::from(root),
+        });
+    }
+
+    panic!("tlessctl(storage): unsupported storage URL (expected s3:// or file://): {url}");
+}
+
+/// Storage backed by a bucket in a MinIO server
+#[derive(Debug)]
+pub struct MinioStorage {
+    bucket_name: String,
+}
+
+#[async_trait(?Send)]
+impl Storage for MinioStorage {
+    fn url(&self) -> String {
+        format!("s3://{}", self.bucket_name)
+    }
+
+    async fn put_bytes(&self, key: &str, bytes: &[u8]) {
+        S3::upload_bytes(&self.bucket_name, key, bytes).await;
+    }
+
+    async fn put_file(&self, host_path: &Path, key: &str, encrypt: bool) {
+        S3::upload_file(&self.bucket_name, host_path.to_str().unwrap(), key, encrypt).await;
+    }
+
+    async fn put_dir(&self, host_path: &Path, prefix: &str, encrypt: bool) {
+        S3::upload_dir(
+            self.bucket_name.clone(),
+            host_path.display().to_string(),
+            prefix.to_string(),
+            encrypt,
+        )
+        .await;
+    }
+
+    async fn get_bytes(&self, key: &str) -> Vec<u8> {
+        S3::get_key(&self.bucket_name, key, false).await
+    }
+
+    async fn get_to_file(&self, key: &str, host_path: &Path) {
+        S3::get_key_to_file(&self.bucket_name, key, host_path.to_str().unwrap(), false).await;
+    }
+
+    async fn list(&self, prefix: &str) -> Vec<StorageObject> {
+        S3::list_objects(&self.bucket_name, prefix).await
+    }
+
+    async fn delete(&self, key: &str) {
+        S3::clear_object(&self.bucket_name, key).await;
+    }
+
+    async fn delete_prefix(&self, prefix: &str) {
+        S3::clear_dir(self.bucket_name.clone(), prefix.to_string()).await;
+    }
+
+    async fn wait_for_key(
+        &self,
+        key: &str,
+        opts: &WaitForKeyOpts,
+    ) -> Result<DateTime<Utc>, S3WaitError> {
+        S3::wait_for_key(&self.bucket_name, key, opts).await
+    }
+
+    async fn presign_get(&self, key: &str, expiry: time::Duration) -> Option<String> {
+        Some(S3::presign(&self.bucket_name, key, expiry, &S3PresignMethod::Get).await)
+    }
+}
+
+/// Storage backed by a directory in the local filesystem, where each key is
+/// a file relative to the root directory
+#[derive(Debug)]
+pub struct FsStorage {
+    root: PathBuf,
+}
+
+impl FsStorage {
+    fn get_path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+
+    fn create_parent_dirs(path: &Path) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+    }
+
+    fn walk(&self, dir: &Path, objects: &mut Vec<StorageObject>) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+            Err(e) => panic!("tlessctl(storage): error reading {}: {e}", dir.display()),
+        };
+
+        for entry in entries {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                self.walk(&path, objects);
+                continue;
+            }
+
+            let metadata = fs::metadata(&path).unwrap();
+            let key = path
+                .strip_prefix(&self.root)
+                .unwrap()
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            objects.push(StorageObject {
+                key,
+                size: metadata.len(),
+                last_modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+            });
+        }
+    }
+
+    fn get_last_modified(&self, key: &str) -> Option<DateTime<Utc>> {
+        let modified = fs::metadata(self.get_path(key)).ok()?.modified().ok()?;
+        Some(DateTime::<Utc>::from(modified))
+    }
+}
+
+#[async_trait(?Send)]
+impl Storage for FsStorage {
+    fn url(&self) -> String {
+        format!("file://{}", self.root.display())
+    }
+
+    async fn put_bytes(&self, key: &str, bytes: &[u8]) {
+        debug!(
+            "tlessctl(storage): writing {} bytes to {}/{key}",
+            bytes.len(),
+            self.root.display()
+        );
+
+        let path = self.get_path(key);
+        Self::create_parent_dirs(&path);
+        fs::write(path, bytes).unwrap();
+    }
+
+    async fn put_file(&self, host_path: &Path, key: &str, encrypt: bool) {
+        if encrypt {
+            panic!("tlessctl(storage): encryption is only supported with the s3:// backend");
+        }
+
+        let path = self.get_path(key);
+        Self::create_parent_dirs(&path);
+        fs::copy(host_path, path).unwrap();
+    }
+
+    async fn get_bytes(&self, key: &str) -> Vec<u8> {
+        fs::read(self.get_path(key))
+            .unwrap_or_else(|e| panic!("tlessctl(storage): error reading key {key}: {e}"))
+    }
+
+    async fn get_to_file(&self, key: &str, host_path: &Path) {
+        Self::create_parent_dirs(host_path);
+        fs::copy(self.get_path(key), host_path)
+            .unwrap_or_else(|e| panic!("tlessctl(storage): error reading key {key}: {e}"));
+    }
+
+    async fn list(&self, prefix: &str) -> Vec<StorageObject> {
+        // Only walk the deepest directory the prefix names, e.g. a/b for
+        // a/b/c, as every key under the prefix lives in it
+        let dir = match prefix.rsplit_once('/') {
+            Some((dir, _)) => self.get_path(dir),
+            None => self.root.clone(),
+        };
+
+        let mut objects = Vec::new();
+        self.walk(&dir, &mut objects);
+
+        objects.retain(|object| object.key.starts_with(prefix));
+        objects.sort_by(|a, b| a.key.cmp(&b.key));
+        objects
+    }
+
+    async fn delete(&self, key: &str) {
+        match fs::remove_file(self.get_path(key)) {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => panic!("tlessctl(storage): error deleting key {key}: {e}"),
+        }
+    }
+
+    async fn delete_prefix(&self, prefix: &str) {
+        for object in self.list(prefix).await {
+            self.delete(&object.key).await;
+        }
+    }
+
+    async fn wait_for_key(
+        &self,
+        key: &str,
+        opts: &WaitForKeyOpts,
+    ) -> Result<DateTime<Utc>, S3WaitError> {
+        let wait = async {
+            let mut backoff = opts.initial_backoff;
+            loop {
+                if let Some(last_modified) = self.get_last_modified(key) {
+                    return last_modified;
+                }
+
+                debug!("tlessctl(storage): waiting for key ({key}) for {backoff:?}...");
+                tokio::time::sleep(backoff).await;
+                backoff = std::cmp::min(backoff * 2, opts.max_backoff);
+            }
+        };
+
+        match opts.timeout {
+            Some(timeout) => {
+                tokio::time::timeout(timeout, wait)
+                    .await
+                    .map_err(|_| S3WaitError::Timeout {
+                        key: key.to_string(),
+                        waited: timeout,
+                    })
+            }
+            None => Ok(wait.await),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tasks::crypto::{Crypto, DEMO_SYM_KEY};
+    use crate::tasks::dag::Dag;
+    use crate::tasks::s3::DatasetManifest;
+    use crate::tasks::workflows::{AvailableWorkflow, Workflows};
+    use sha2::{Digest, Sha256};
+    use std::env;
+
+    const DATASET_FILES: [(&str, &str); 2] = [
+        ("file-1.txt", "the quick brown fox\n"),
+        ("file-2.txt", "jumps over the lazy dog\n"),
+    ];
+
+    // DAG of the word-count workflow, as serialized by Dag::upload
+    const WORD_COUNT_DAG: &str = "splitter\n1\nmapper\n\nmapper\nN\nreducer\n\nreducer\n1\n\n\n";
+
+    /// Scratch project root with the word-count DAG and a small dataset, and
+    /// a file:// storage backend inside it
+    struct Fixture {
+        root: PathBuf,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let root =
+                env::temp_dir().join(format!("tless-storage-test-{}-{name}", std::process::id()));
+
+            let workflow_dir = root.join("workflows").join("word-count");
+            fs::create_dir_all(&workflow_dir).unwrap();
+            fs::copy(
+                Path::new(env!("CARGO_MANIFEST_DIR")).join("../workflows/word-count/tless.yaml"),
+                workflow_dir.join("tless.yaml"),
+            )
+            .unwrap();
+
+            let dataset_dir = root.join("datasets").join("word-count").join("fewer-files");
+            fs::create_dir_all(&dataset_dir).unwrap();
+            for (file_name, contents) in DATASET_FILES {
+                fs::write(dataset_dir.join(file_name), contents).unwrap();
+            }
+
+            Self { root }
+        }
+
+        fn get_storage(&self) -> Box<dyn Storage> {
+            from_url(&format!("file://{}", self.root.join("storage").display()))
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            fs::remove_dir_all(&self.root).unwrap();
+        }
+    }
+
+    async fn get_keys(storage: &dyn Storage, prefix: &str) -> Vec<String> {
+        storage
+            .list(prefix)
+            .await
+            .into_iter()
+            .map(|object| object.key)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn dag_upload_writes_dag_and_crypto_state() {
+        let fixture = Fixture::new("dag");
+        let storage = fixture.get_storage();
+
+        let yaml_path = fixture.root.join("workflows/word-count/tless.yaml");
+        Dag::upload(storage.as_ref(), "word-count", yaml_path.to_str().unwrap()).await;
+
+        assert_eq!(
+            get_keys(storage.as_ref(), "").await,
+            vec![
+                "word-count/cert-chains/test",
+                "word-count/crypto/cp-abe-ctx",
+                "word-count/dag",
+            ]
+        );
+        assert_eq!(
+            storage.get_bytes("word-count/dag").await,
+            WORD_COUNT_DAG.as_bytes()
+        );
+
+        // Both the CP-ABE context and the certificate chain are sealed with
+        // the symmetric key we share with the TEEs
+        Crypto::open(
+            &DEMO_SYM_KEY,
+            &storage.get_bytes("word-count/crypto/cp-abe-ctx").await,
+        );
+        let cert_chain = Crypto::open(
+            &DEMO_SYM_KEY,
+            &storage.get_bytes("word-count/cert-chains/test").await,
+        );
+        serde_json::from_slice::<serde_json::Value>(&cert_chain).unwrap();
+    }
+
+    #[tokio::test]
+    async fn upload_workflow_state_writes_dataset_and_manifest() {
+        let fixture = Fixture::new("state");
+        let storage = fixture.get_storage();
+
+        Workflows::upload_workflow_state(
+            &AvailableWorkflow::WordCount,
+            storage.as_ref(),
+            &fixture.root,
+            true,
+            false,
+        )
+        .await;
+
+        assert_eq!(
+            get_keys(storage.as_ref(), "").await,
+            vec![
+                "word-count/.manifests/few-files.json",
+                "word-count/cert-chains/test",
+                "word-count/crypto/cp-abe-ctx",
+                "word-count/dag",
+                "word-count/few-files/file-1.txt",
+                "word-count/few-files/file-2.txt",
+            ]
+        );
+        for (file_name, contents) in DATASET_FILES {
+            assert_eq!(
+                storage
+                    .get_bytes(&format!("word-count/few-files/{file_name}"))
+                    .await,
+                contents.as_bytes()
+            );
+        }
+
+        let manifest: DatasetManifest = serde_json::from_slice(
+            &storage
+                .get_bytes("word-count/.manifests/few-files.json")
+                .await,
+        )
+        .unwrap();
+        assert_eq!(manifest.version, DatasetManifest::VERSION);
+        assert_eq!(manifest.prefix, "word-count/few-files");
+        assert!(!manifest.encrypted);
+        assert_eq!(manifest.files.len(), DATASET_FILES.len());
+        for (entry, (file_name, contents)) in manifest.files.iter().zip(DATASET_FILES) {
+            assert_eq!(entry.path, file_name);
+            assert_eq!(entry.size, contents.len() as u64);
+            assert_eq!(entry.sha256, hex::encode(Sha256::digest(contents)));
+        }
+    }
+
+    #[tokio::test]
+    async fn upload_workflow_input_takes_first_files_of_dataset() {
+        let fixture = Fixture::new("input");
+        let storage = fixture.get_storage();
+
+        let workflow = AvailableWorkflow::WordCount;
+        assert_eq!(
+            Workflows::get_max_input_size(&workflow, &fixture.root),
+            Some(DATASET_FILES.len() as u32)
+        );
+        Workflows::upload_workflow_input(&workflow, storage.as_ref(), &fixture.root, 1, false)
+            .await;
+
+        assert_eq!(
+            get_keys(storage.as_ref(), "").await,
+            vec![
+                "word-count/.manifests/files-1.json",
+                "word-count/files-1/file-1.txt",
+            ]
+        );
+        let (file_name, contents) = DATASET_FILES[0];
+        assert_eq!(
+            storage
+                .get_bytes(&format!("word-count/files-1/{file_name}"))
+                .await,
+            contents.as_bytes()
+        );
+    }
+
+    #[tokio::test]
+    async fn upload_workflow_state_cleans_previous_runs() {
+        let fixture = Fixture::new("clean");
+        let storage = fixture.get_storage();
+
+        for key in [
+            "word-count/outputs/run-1/reducer",
+            "word-count/exec-tokens/run-1",
+            "finra/outputs/run-1/audit",
+        ] {
+            storage.put_bytes(key, b"stale").await;
+        }
+
+        Workflows::upload_workflow_state(
+            &AvailableWorkflow::WordCount,
+            storage.as_ref(),
+            &fixture.root,
+            true,
+            false,
+        )
+        .await;
+
+        assert!(get_keys(storage.as_ref(), "word-count/outputs")
+            .await
+            .is_empty());
+        assert!(get_keys(storage.as_ref(), "word-count/exec-tokens")
+            .await
+            .is_empty());
+        assert_eq!(
+            get_keys(storage.as_ref(), "finra/").await,
+            vec!["finra/outputs/run-1/audit"]
+        );
+    }
+}
@@ -1,9 +1,15 @@
 use crate::tasks::dag::Dag;
-use crate::tasks::s3::S3;
+use crate::tasks::s3::{DatasetManifest, S3};
+use crate::tasks::storage::Storage;
 use clap::ValueEnum;
-use std::path::PathBuf;
+use rand::Rng;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::{env, fmt};
+use std::{env, fmt, fs};
+
+mod trigger;
+
+pub use trigger::WorkflowTrigger;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ValueEnum)]
 pub enum AvailableWorkflow {
@@ -60,100 +66,301 @@ impl Workflows {
         path
     }
 
+    /// Upload the DAG and the datasets of a workflow, from a checkout of the
+    /// project. If encrypt is set, we envelope-encrypt the datasets. Note
+    /// that workflow functions can not decrypt them yet, so runs against
+    /// encrypted state will fail
     pub async fn upload_workflow_state(
         workflow: &AvailableWorkflow,
-        bucket_name: &str,
+        storage: &dyn Storage,
+        proj_root: &Path,
         clean: bool,
+        encrypt: bool,
     ) {
         // Note that cleaning here means cleaning the outputs of previous runs
         if clean {
             for key_dir in vec!["outputs", "exec-tokens", "cert-chains"] {
-                S3::clear_dir(
-                    bucket_name.to_string(),
-                    format!("{workflow}/{key_dir}").to_string(),
-                )
-                .await;
+                storage
+                    .delete_prefix(format!("{workflow}/{key_dir}").as_str())
+                    .await;
             }
         }
 
         // First, upload the DAG
-        let mut yaml_path = Self::get_root();
+        let mut yaml_path = proj_root.join("workflows");
         yaml_path.push(format!("{workflow}"));
         yaml_path.push("tless.yaml");
-        Dag::upload(format!("{workflow}").as_str(), yaml_path.to_str().unwrap()).await;
+        Dag::upload(
+            storage,
+            format!("{workflow}").as_str(),
+            yaml_path.to_str().unwrap(),
+        )
+        .await;
 
         // Then, upload the respective state
         match workflow {
             AvailableWorkflow::Finra => {
-                let mut host_path = S3::get_datasets_root();
+                let mut host_path = S3::get_datasets_root(proj_root);
                 host_path.push(format!("{workflow}"));
                 host_path.push("yfinance.csv");
                 let s3_path = format!("{workflow}/yfinance.csv");
-                S3::upload_file(bucket_name, host_path.to_str().unwrap(), &s3_path).await;
+                storage.put_file(&host_path, &s3_path, encrypt).await;
+                DatasetManifest::from_host_path(&host_path, &s3_path, encrypt)
+                    .upload(storage)
+                    .await;
             }
             AvailableWorkflow::MlTraining => {
                 // We upload both datasets until we decide which one to use
                 for dataset in vec!["mnist-images-2k", "mnist-images-10k"] {
-                    let mut host_path = S3::get_datasets_root();
+                    let mut host_path = S3::get_datasets_root(proj_root);
                     host_path.push(format!("{workflow}"));
                     host_path.push(format!("{dataset}"));
-                    S3::upload_dir(
-                        bucket_name.to_string(),
-                        host_path.display().to_string(),
-                        format!("{workflow}/{dataset}"),
-                    )
-                    .await;
+                    let s3_path = format!("{workflow}/{dataset}");
+                    storage.put_dir(&host_path, &s3_path, encrypt).await;
+                    DatasetManifest::from_host_path(&host_path, &s3_path, encrypt)
+                        .upload(storage)
+                        .await;
                 }
             }
             AvailableWorkflow::MlInference => {
                 for dataset in vec!["images-inference-1k", "model"] {
-                    let mut host_path = S3::get_datasets_root();
+                    let mut host_path = S3::get_datasets_root(proj_root);
                     host_path.push(format!("{workflow}"));
                     host_path.push(format!("{dataset}"));
-                    S3::upload_dir(
-                        bucket_name.to_string(),
-                        host_path.display().to_string(),
-                        format!("{workflow}/{dataset}"),
-                    )
-                    .await;
+                    let s3_path = format!("{workflow}/{dataset}");
+                    storage.put_dir(&host_path, &s3_path, encrypt).await;
+                    DatasetManifest::from_host_path(&host_path, &s3_path, encrypt)
+                        .upload(storage)
+                        .await;
                 }
             }
             AvailableWorkflow::WordCount => {
-                let mut host_path = S3::get_datasets_root();
+                let mut host_path = S3::get_datasets_root(proj_root);
                 host_path.push(format!("{workflow}"));
                 host_path.push("fewer-files");
-                S3::upload_dir(
-                    bucket_name.to_string(),
-                    host_path.display().to_string(),
-                    format!("{workflow}/few-files"),
-                )
-                .await;
+                let s3_path = format!("{workflow}/few-files");
+                storage.put_dir(&host_path, &s3_path, encrypt).await;
+                DatasetManifest::from_host_path(&host_path, &s3_path, encrypt)
+                    .upload(storage)
+                    .await;
             }
         };
     }
 
-    pub async fn upload_state(bucket_name: &str, clean: bool) {
+    pub async fn upload_state(storage: &dyn Storage, proj_root: &Path, clean: bool, encrypt: bool) {
         if clean {
-            S3::clear_bucket(bucket_name.to_string()).await;
+            storage.delete_prefix("").await;
         }
 
         // Upload state for different workflows
         for workflow in AvailableWorkflow::iter_variants() {
-            Self::upload_workflow_state(&workflow, bucket_name, clean).await;
+            Self::upload_workflow_state(&workflow, storage, proj_root, clean, encrypt).await;
         }
     }
 
-    pub fn get_faasm_cmdline(workflow: &AvailableWorkflow) -> &str {
+    /// Command line for the Faasm driver function. An input size of 0 means
+    /// the default input (see `get_input_key`)
+    pub fn get_faasm_cmdline(workflow: &AvailableWorkflow, input_size: u32) -> String {
+        let input_key = Self::get_input_key(workflow, input_size);
         match workflow {
-            AvailableWorkflow::Finra => "finra/yfinance.csv 20",
+            AvailableWorkflow::Finra => format!("{input_key} 20"),
             // ML Training workflow with SGX on mnist-10k takes ~30'
             // AvailableWorkflow::MlTraining => "ml-training/mnist-images-10k 4 8",
-            AvailableWorkflow::MlTraining => "ml-training/mnist-images-2k 2 8",
+            AvailableWorkflow::MlTraining => format!("{input_key} 2 8"),
             // ML Inference relies on the model outputed by ML Training
-            AvailableWorkflow::MlInference => {
-                "ml-inference/model ml-inference/images-inference-1k 16"
+            AvailableWorkflow::MlInference => format!("ml-inference/model {input_key} 16"),
+            AvailableWorkflow::WordCount => input_key,
+        }
+    }
+
+    /// What we count in the input of a workflow, when we change its size
+    pub fn get_input_unit(workflow: &AvailableWorkflow) -> &str {
+        match workflow {
+            AvailableWorkflow::Finra => "trades",
+            AvailableWorkflow::MlTraining | AvailableWorkflow::MlInference => "images",
+            AvailableWorkflow::WordCount => "files",
+        }
+    }
+
+    /// Input sizes we sweep by default, up to the size of the dataset we
+    /// take the inputs from
+    pub fn get_default_input_sizes(workflow: &AvailableWorkflow) -> Vec<u32> {
+        match workflow {
+            AvailableWorkflow::Finra => vec![1000, 2000, 5000, 10000],
+            AvailableWorkflow::MlTraining => vec![1000, 2000, 5000, 10000],
+            AvailableWorkflow::MlInference => vec![100, 250, 500, 1000],
+            AvailableWorkflow::WordCount => vec![1, 2, 4, 8],
+        }
+    }
+
+    /// Key of the input of a workflow (i.e. a CSV file, or a prefix with one
+    /// file per entry). An input size of 0 means the input we use by default,
+    /// and any other size an input with that many trades, images, or files,
+    /// as uploaded by `upload_workflow_input`
+    pub fn get_input_key(workflow: &AvailableWorkflow, input_size: u32) -> String {
+        match (workflow, input_size) {
+            (AvailableWorkflow::Finra, 0) => format!("{workflow}/yfinance.csv"),
+            (AvailableWorkflow::Finra, n) => format!("{workflow}/yfinance-{n}.csv"),
+            (AvailableWorkflow::MlTraining, 0) => format!("{workflow}/mnist-images-2k"),
+            (AvailableWorkflow::MlTraining, n) => format!("{workflow}/mnist-images-{n}"),
+            (AvailableWorkflow::MlInference, 0) => format!("{workflow}/images-inference-1k"),
+            (AvailableWorkflow::MlInference, n) => format!("{workflow}/images-inference-{n}"),
+            (AvailableWorkflow::WordCount, 0) => format!("{workflow}/few-files"),
+            (AvailableWorkflow::WordCount, n) => format!("{workflow}/files-{n}"),
+        }
+    }
+
+    /// Upload an input for a workflow with the first input_size entries of
+    /// the largest dataset we have for it. We build the input in a scratch
+    /// directory, so that we can upload it (and its manifest) like any other
+    /// dataset
+    pub async fn upload_workflow_input(
+        workflow: &AvailableWorkflow,
+        storage: &dyn Storage,
+        proj_root: &Path,
+        input_size: u32,
+        encrypt: bool,
+    ) {
+        let source_path = Self::get_input_source_path(workflow, proj_root);
+        let s3_path = Self::get_input_key(workflow, input_size);
+        let mut host_path = env::temp_dir();
+        host_path.push("tless-inputs");
+        host_path.push(&s3_path);
+        match workflow {
+            AvailableWorkflow::Finra => {
+                Self::write_csv_head(&source_path, &host_path, input_size as usize);
+                storage.put_file(&host_path, &s3_path, encrypt).await;
+            }
+            _ => {
+                Self::copy_dir_head(&source_path, &host_path, input_size as usize);
+                storage.put_dir(&host_path, &s3_path, encrypt).await;
             }
-            AvailableWorkflow::WordCount => "word-count/few-files",
+        }
+        DatasetManifest::from_host_path(&host_path, &s3_path, encrypt)
+            .upload(storage)
+            .await;
+    }
+
+    /// Largest dataset we have for a workflow, which we take the inputs of
+    /// different sizes from
+    fn get_input_source_path(workflow: &AvailableWorkflow, proj_root: &Path) -> PathBuf {
+        let mut source_path = S3::get_datasets_root(proj_root);
+        source_path.push(format!("{workflow}"));
+        source_path.push(match workflow {
+            AvailableWorkflow::Finra => "yfinance.csv",
+            AvailableWorkflow::MlTraining => "mnist-images-10k",
+            AvailableWorkflow::MlInference => "images-inference-1k",
+            AvailableWorkflow::WordCount => "fewer-files",
+        });
+        source_path
+    }
+
+    /// Largest input size we can upload for a workflow, i.e. the number of
+    /// entries in the dataset we take inputs from, or None if we do not
+    /// have the dataset
+    pub fn get_max_input_size(workflow: &AvailableWorkflow, proj_root: &Path) -> Option<u32> {
+        let source_path = Self::get_input_source_path(workflow, proj_root);
+        let num_entries = match workflow {
+            AvailableWorkflow::Finra => fs::read_to_string(&source_path)
+                .ok()?
+                .lines()
+                .count()
+                .saturating_sub(1),
+            _ => fs::read_dir(&source_path)
+                .ok()?
+                .filter(|entry| entry.as_ref().is_ok_and(|entry| entry.path().is_file()))
+                .count(),
+        };
+
+        Some(num_entries as u32)
+    }
+
+    /// Write the header, and the first num_rows rows, of a CSV file
+    fn write_csv_head(source_path: &Path, host_path: &Path, num_rows: usize) {
+        let contents = fs::read_to_string(source_path).unwrap_or_else(|e| {
+            panic!(
+                "tlessctl(workflows): error reading {}: {e}",
+                source_path.display()
+            )
+        });
+        let lines: Vec<&str> = contents.lines().collect();
+        if lines.len() < num_rows + 1 {
+            panic!(
+                "tlessctl(workflows): {} only has {} rows (need: {num_rows})",
+                source_path.display(),
+                lines.len().saturating_sub(1)
+            );
+        }
+
+        fs::create_dir_all(host_path.parent().unwrap()).unwrap();
+        fs::write(host_path, lines[..num_rows + 1].join("\n") + "\n").unwrap();
+    }
+
+    /// Copy the first num_files files, by name, of a directory
+    fn copy_dir_head(source_path: &Path, host_path: &Path, num_files: usize) {
+        let mut files: Vec<PathBuf> = fs::read_dir(source_path)
+            .unwrap_or_else(|e| {
+                panic!(
+                    "tlessctl(workflows): error reading {}: {e}",
+                    source_path.display()
+                )
+            })
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.is_file())
+            .collect();
+        if files.len() < num_files {
+            panic!(
+                "tlessctl(workflows): {} only has {} files (need: {num_files})",
+                source_path.display(),
+                files.len()
+            );
+        }
+        files.sort();
+
+        // Start from scratch, in case we built a different input here before
+        if host_path.exists() {
+            fs::remove_dir_all(host_path).unwrap();
+        }
+        fs::create_dir_all(host_path).unwrap();
+        for file in &files[..num_files] {
+            fs::copy(file, host_path.join(file.file_name().unwrap())).unwrap();
+        }
+    }
+
+    /// Identifier for one execution of a workflow. Functions keep all the
+    /// state of an execution under its run id, so that executions of the
+    /// same workflow can overlap
+    pub fn new_run_id() -> String {
+        format!("{:08x}", rand::thread_rng().gen::<u32>())
+    }
+
+    /// Prefix for the keys of a directory (e.g. outputs) that belong to one
+    /// execution. An empty run id gives the keys shared by all executions
+    fn get_run_prefix(workflow: &AvailableWorkflow, dir: &str, run_id: &str) -> String {
+        match run_id {
+            "" => format!("{workflow}/{dir}/"),
+            _ => format!("{workflow}/{dir}/{run_id}/"),
+        }
+    }
+
+    pub fn get_outputs_prefix(workflow: &AvailableWorkflow, run_id: &str) -> String {
+        Self::get_run_prefix(workflow, "outputs", run_id)
+    }
+
+    pub fn get_exec_tokens_prefix(workflow: &AvailableWorkflow, run_id: &str) -> String {
+        Self::get_run_prefix(workflow, "exec-tokens", run_id)
+    }
+
+    /// Key for the final output of a workflow execution. It is the last key
+    /// a workflow writes, so we also use it to detect that an execution has
+    /// finished
+    pub fn get_output_key(workflow: &AvailableWorkflow, run_id: &str) -> String {
+        let outputs_prefix = Self::get_outputs_prefix(workflow, run_id);
+        match workflow {
+            AvailableWorkflow::Finra => format!("{outputs_prefix}merge/results.txt"),
+            AvailableWorkflow::MlTraining => format!("{outputs_prefix}done.txt"),
+            AvailableWorkflow::MlInference => format!("{outputs_prefix}predict/done.txt"),
+            AvailableWorkflow::WordCount => format!("{outputs_prefix}aggregated-results.txt"),
         }
     }
 }
@@ -0,0 +1,208 @@
+use crate::tasks::workflows::{AvailableWorkflow, Workflows};
+use log::debug;
+use rand::Rng;
+use serde_json::{json, Value};
+use std::time;
+
+// Namespace where we deploy the channels for all workflows
+const CHANNEL_NAMESPACE: &str = "tless";
+// Default fan-out when we are not scaling-up a workflow
+const DEFAULT_NUM_AUDIT_FUNCS: u32 = 8;
+const DEFAULT_NUM_INF_FUNCS: u32 = 8;
+// Channels reply as soon as they have queued the event
+const REQUEST_TIMEOUT: time::Duration = time::Duration::from_secs(30);
+
+/// A CloudEvent that we POST to a Knative channel to start a workflow
+#[derive(Clone, Debug)]
+pub struct CloudEvent {
+    pub id: String,
+    pub source: String,
+    pub ce_type: String,
+    /// Name of the channel we send the event to
+    pub channel: String,
+    pub data: Value,
+}
+
+impl CloudEvent {
+    fn new(source: &str, ce_type_channel: &str, channel: &str, data: Value) -> Self {
+        Self {
+            id: format!("{:016x}", rand::thread_rng().gen::<u64>()),
+            source: source.to_string(),
+            ce_type: format!("http://{}", get_channel_host(ce_type_channel)),
+            channel: channel.to_string(),
+            data,
+        }
+    }
+}
+
+/// Host name that Knative gives to an in-memory channel
+fn get_channel_host(channel: &str) -> String {
+    format!("{channel}-kn-channel.{CHANNEL_NAMESPACE}.svc.cluster.local")
+}
+
+impl Workflows {
+    /// Events that kick-off one execution of a workflow. All of them carry
+    /// the same run magic and run id, so that functions can tell executions
+    /// apart. A scale-up factor of 0 means the default fan-out, and an input
+    /// size of 0 the default input
+    pub fn get_trigger_events(
+        workflow: &AvailableWorkflow,
+        run_magic: i64,
+        run_id: &str,
+        scale_up_factor: u32,
+        input_size: u32,
+    ) -> Vec<CloudEvent> {
+        let fan_out = |default: u32| match scale_up_factor {
+            0 => default,
+            n => n,
+        };
+        let input_key = Self::get_input_key(workflow, input_size);
+
+        match workflow {
+            AvailableWorkflow::Finra => {
+                let data = json!({
+                    "data-file": input_key,
+                    "num-audit": fan_out(DEFAULT_NUM_AUDIT_FUNCS),
+                    "run-magic": run_magic,
+                    "run-id": run_id,
+                });
+                vec![
+                    CloudEvent::new(
+                        "cli-fetch-public",
+                        "fetch-to-audit",
+                        "ingress-to-fetch-public",
+                        data.clone(),
+                    ),
+                    CloudEvent::new(
+                        "cli-fetch-private",
+                        "fetch-to-audit",
+                        "ingress-to-fetch-private",
+                        data,
+                    ),
+                ]
+            }
+            AvailableWorkflow::MlTraining => vec![CloudEvent::new(
+                "cli",
+                "partition-to-pca",
+                "ingress-to-partition",
+                json!({
+                    "data-dir": input_key,
+                    "num-pca-funcs": 2,
+                    "num-train-funcs": 8,
+                    "run-magic": run_magic,
+                    "run-id": run_id,
+                }),
+            )],
+            AvailableWorkflow::MlInference => {
+                let data = json!({
+                    "model-dir": "ml-inference/model",
+                    "data-dir": input_key,
+                    "num-inf-funcs": fan_out(DEFAULT_NUM_INF_FUNCS),
+                    "run-magic": run_magic,
+                    "run-id": run_id,
+                });
+                vec![
+                    CloudEvent::new(
+                        "cli-partition",
+                        "pre-inf-to-predict",
+                        "ingress-to-partition",
+                        data.clone(),
+                    ),
+                    CloudEvent::new("cli-load", "pre-inf-to-predict", "ingress-to-load", data),
+                ]
+            }
+            AvailableWorkflow::WordCount => vec![CloudEvent::new(
+                "cli",
+                "splitter-to-mapper",
+                "ingress-to-splitter",
+                json!({
+                    "data-dir": input_key,
+                    "run-id": run_id,
+                }),
+            )],
+        }
+    }
+}
+
+/// Send the events that start a workflow to its ingress channels. Channels
+/// are only reachable from inside the cluster, so from outside we send all
+/// events to the in-memory channel dispatcher (e.g. through a port-forward),
+/// which routes them by their Host header
+#[derive(Debug)]
+pub struct WorkflowTrigger {
+    client: reqwest::Client,
+    dispatcher_url: Option<String>,
+}
+
+impl WorkflowTrigger {
+    /// Trigger through a dispatcher URL, or straight to the channels if we
+    /// are running inside the cluster
+    pub fn new(dispatcher_url: Option<String>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .expect("tlessctl(trigger): failed to build HTTP client");
+
+        Self {
+            client,
+            dispatcher_url: dispatcher_url.map(|url| url.trim_end_matches('/').to_string()),
+        }
+    }
+
+    async fn send(&self, event: &CloudEvent) -> Result<(), String> {
+        let channel_host = get_channel_host(&event.channel);
+        let url = match &self.dispatcher_url {
+            Some(url) => url.clone(),
+            None => format!("http://{channel_host}"),
+        };
+        debug!(
+            "tlessctl(trigger): sending event {} to {channel_host} (via: {url})",
+            event.id
+        );
+
+        let response = self
+            .client
+            .post(&url)
+            .header(reqwest::header::HOST, &channel_host)
+            .header("ce-specversion", "1.0")
+            .header("ce-id", &event.id)
+            .header("ce-source", &event.source)
+            .header("ce-type", &event.ce_type)
+            .json(&event.data)
+            .send()
+            .await
+            .map_err(|e| format!("error sending event to {channel_host}: {e}"))?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "channel {channel_host} rejected event (status: {})",
+                response.status()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Start one execution of a workflow, under the given run id, and return
+    /// its run magic
+    pub async fn trigger(
+        &self,
+        workflow: &AvailableWorkflow,
+        scale_up_factor: u32,
+        input_size: u32,
+        run_id: &str,
+    ) -> Result<i64, String> {
+        let run_magic = rand::thread_rng().gen_range(0..i32::MAX as i64);
+        let events =
+            Workflows::get_trigger_events(workflow, run_magic, run_id, scale_up_factor, input_size);
+
+        // Send all events concurrently, as some workflows have more than one
+        // entrypoint
+        let results = futures_util::future::join_all(events.iter().map(|e| self.send(e))).await;
+        for result in results {
+            result?;
+        }
+
+        Ok(run_magic)
+    }
+}
@@ -13,6 +13,7 @@ extern "C"
 #endif
 
 #include "tless.h"
+#include "utils.h"
 #include "trade.h"
 
 #include <iostream>
@@ -48,11 +49,7 @@ int main(int argc, char** argv)
 
 #ifdef __faasm
     // Get the object key as an input
-    int inputSize = faasmGetInputSize();
-    char inputChar[inputSize];
-    faasmGetInput((uint8_t*)inputChar, inputSize);
-
-    std::string tmpStr(inputChar, inputChar + inputSize);
+    std::string tmpStr = tless::utils::getInput();
     auto parts = splitByDelimiter(tmpStr, ":");
     if (parts.size() != 3) {
         std::cerr << "finra(audit): error parsing driver input" << std::endl;
@@ -143,7 +140,7 @@ int main(int argc, char** argv)
     std::cout << "finra(" << us << "): done running audit rule!" << std::endl;
 
     // Upload structured data to S3
-    std::string key = "finra/outputs/audit/" + us;
+    std::string key = tless::utils::getOutputsPrefix("finra") + "audit/" + us;
     std::cout << "finra(" << us << "): uploading audit results to "
               << key
               << std::endl;
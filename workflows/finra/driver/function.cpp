@@ -1,6 +1,7 @@
 #ifdef __faasm
 #include <faasm/core.h>
 #endif
+#include "utils.h"
 
 #include <iostream>
 #include <string>
@@ -11,19 +12,21 @@
 int main(int argc, char** argv)
 {
 #ifdef __faasm
-    if (argc != 3) {
-        std::cout << "finra(driver): usage: <s3_public_data_path> <num_audit_funcs>"
+    if (argc != 3 && argc != 4) {
+        std::cout << "finra(driver): usage: <s3_public_data_path> <num_audit_funcs> [<run_id>]"
                   << std::endl;
         return 1;
     }
     std::string s3DataFile = argv[1];
     int numAuditFuncs = std::stoi(argv[2]);
+    tless::utils::setRunId(argc == 4 ? argv[3] : "");
+    std::string outputsPrefix = tless::utils::getOutputsPrefix("finra");
 
     std::cout << "finra(driver): invoking one fetch-public function" << std::endl;
-    int fetchPublicId = faasmChainNamed("fetch-public", (uint8_t*) s3DataFile.c_str(), s3DataFile.size());
+    int fetchPublicId = tless::utils::chainNamed("fetch-public", s3DataFile);
 
     std::cout << "finra(driver): invoking one fetch-private function" << std::endl;
-    int fetchPrivateId = faasmChainNamed("fetch-private", nullptr, 0);
+    int fetchPrivateId = tless::utils::chainNamed("fetch-private", "");
 
     // Wait for both functions to finish
     int result = faasmAwaitCall(fetchPublicId);
@@ -48,9 +51,9 @@ int main(int argc, char** argv)
     int auditId;
     for (int i = 0; i < numAuditFuncs; i++) {
         std::string auditInput = std::to_string(i);
-        auditInput += ":finra/outputs/fetch-public/trades";
-        auditInput += ":finra/outputs/fetch-private/portfolio";
-        int auditId = faasmChainNamed("audit", (uint8_t*) auditInput.c_str(), auditInput.size());
+        auditInput += ":" + outputsPrefix + "fetch-public/trades";
+        auditInput += ":" + outputsPrefix + "fetch-private/portfolio";
+        int auditId = tless::utils::chainNamed("audit", auditInput);
         auditFuncIds.push_back(auditId);
     }
 
@@ -69,7 +72,7 @@ int main(int argc, char** argv)
     }
 
     std::cout << "finra(driver): invoking one merge function" << std::endl;
-    int mergeId = faasmChainNamed("merge", nullptr, 0);
+    int mergeId = tless::utils::chainNamed("merge", "");
     result = faasmAwaitCall(mergeId);
     if (result != 0) {
         std::cout << "finra(driver): merge execution failed with rc "
@@ -13,6 +13,7 @@ extern "C"
 #endif
 
 #include "tless.h"
+#include "utils.h"
 #include "trade.h"
 
 #include <iostream>
@@ -35,6 +36,11 @@ Portfolio portfolio = {
  */
 int main(int argc, char** argv)
 {
+#ifdef __faasm
+    // We take no input, but we still need our run id
+    tless::utils::getInput();
+#endif
+
     if (!tless::checkChain("finra", "fetch-private", 0)) {
         std::cerr << "finra(fetch-private): error checking TLess chain" << std::endl;
         return 1;
@@ -57,7 +63,7 @@ int main(int argc, char** argv)
     std::vector<uint8_t> serializedPortfolio = tless::finra::serializePortfolio(portfolio);
 
     // Upload structured data to S3
-    std::string key = "finra/outputs/fetch-private/portfolio";
+    std::string key = tless::utils::getOutputsPrefix("finra") + "fetch-private/portfolio";
     std::cout << "finra(fetch-private): uploading structured portfolio data to "
               << key
               << std::endl;
@@ -13,6 +13,7 @@ extern "C"
 #endif
 
 #include "tless.h"
+#include "utils.h"
 #include "trade.h"
 
 #include <iostream>
@@ -54,22 +55,13 @@ std::string join(const std::vector<std::string>& stringList, const std::string&
  */
 int main(int argc, char** argv)
 {
-    if (!tless::checkChain("finra", "fetch-public", 0)) {
-        std::cerr << "finra(fetch-public): error checking TLess chain" << std::endl;
-        return 1;
-    }
-
     // TODO: the bucket name is currently hardcoded
     std::string bucketName = "tless";
     std::string s3DataFile;
 
 #ifdef __faasm
     // Get the object key as an input
-    int inputSize = faasmGetInputSize();
-    char inputChar[inputSize];
-    faasmGetInput((uint8_t*)inputChar, inputSize);
-
-    s3DataFile.assign(inputChar, inputChar + inputSize);
+    s3DataFile = tless::utils::getInput();
 #else
     if (argc != 2) {
         std::cerr << "finra(fetch-public): error: cannot parse input from driver"
@@ -82,6 +74,12 @@ int main(int argc, char** argv)
     s3::S3Wrapper s3cli;
 #endif
 
+    // We need our input (and run id) to check the chain
+    if (!tless::checkChain("finra", "fetch-public", 0)) {
+        std::cerr << "finra(fetch-public): error checking TLess chain" << std::endl;
+        return 1;
+    }
+
     std::cout << "finra(fetch-public): fetching public trades data from "
               << s3DataFile
               << std::endl;
@@ -113,7 +111,7 @@ int main(int argc, char** argv)
     std::vector<uint8_t> serializedTradeData = tless::finra::serializeTradeVector(tradeData);
 
     // Upload structured data to S3
-    std::string key = "finra/outputs/fetch-public/trades";
+    std::string key = tless::utils::getOutputsPrefix("finra") + "fetch-public/trades";
     std::cout << "finra(fetch-public): uploading data from "
               << tradeData.size()
               << " trades to "
@@ -10,6 +10,7 @@ use minio::s3::http::BaseUrl;
 use minio::s3::types::ToStream;
 use once_cell::sync::Lazy;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::process::{Command, Stdio};
 use std::sync::{Arc, Mutex};
 use std::{env, fs, thread, time};
@@ -17,7 +18,9 @@ use tokio::task::JoinHandle;
 use warp::Filter;
 
 static BINARY_DIR: &str = "/workflows/build-native/finra";
-static MERGE_INVOCATION_COUNTER: Lazy<Arc<Mutex<i64>>> = Lazy::new(|| Arc::new(Mutex::new(0)));
+// Fan-in counter for each execution (by run id) in flight
+static MERGE_INVOCATION_COUNTER: Lazy<Arc<Mutex<HashMap<String, i64>>>> =
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 static WORKFLOW_NAME: &str = "finra(driver)";
 
 struct S3Data {
@@ -118,6 +121,34 @@ pub fn get_json_from_event(event: &Event) -> Value {
     .unwrap()
 }
 
+// Executions of the same workflow may overlap, so each one keeps its state
+// under the run id that tlessctl gives it. Events without a run id share the
+// same keys
+pub fn get_run_id(event: &Event) -> String {
+    get_json_from_event(event)
+        .get("run-id")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+// Executions may read inputs of different sizes, so tlessctl tells us which
+// one to read. Events without one read the default input
+pub fn get_data_file(event: &Event) -> String {
+    get_json_from_event(event)
+        .get("data-file")
+        .and_then(Value::as_str)
+        .unwrap_or("finra/yfinance.csv")
+        .to_string()
+}
+
+pub fn get_outputs_prefix(run_id: &str) -> String {
+    match run_id {
+        "" => "finra/outputs/".to_string(),
+        _ => format!("finra/outputs/{run_id}/"),
+    }
+}
+
 // This function is a general wrapper that takes a cloud event as an input,
 // decides what function to execute, and outputs another cloud event
 pub fn process_event(mut event: Event) -> Event {
@@ -125,6 +156,13 @@ pub fn process_event(mut event: Event) -> Event {
     // Pre-process and function invocation
     // -----
 
+    // The cold-start breakdown experiment looks for this line in the logs
+    // to tell when the handler starts
+    println!("tless(handler): handling event {}", event.id());
+
+    let run_id = get_run_id(&event);
+    let outputs_prefix = get_outputs_prefix(&run_id);
+
     event.set_source(match event.source().as_str() {
         "cli-fetch-public" => {
             let func_name = "fetch-public";
@@ -139,9 +177,10 @@ pub fn process_event(mut event: Event) -> Event {
                 .env("S3_PORT", S3Data::PORT.data)
                 .env("S3_USER", S3Data::USER.data)
                 .env("TLESS_MODE", get_tless_mode())
+                .env("TLESS_RUN_ID", &run_id)
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit())
-                .arg("finra/yfinance.csv")
+                .arg(get_data_file(&event))
                 .output()
                 .expect("finra(driver): error: spawning executing fetch-public command")
                 .status
@@ -173,6 +212,7 @@ pub fn process_event(mut event: Event) -> Event {
                 .env("S3_PORT", S3Data::PORT.data)
                 .env("S3_USER", S3Data::USER.data)
                 .env("TLESS_MODE", get_tless_mode())
+                .env("TLESS_RUN_ID", &run_id)
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit())
                 .output()
@@ -219,9 +259,10 @@ pub fn process_event(mut event: Event) -> Event {
                 .env("S3_PORT", S3Data::PORT.data)
                 .env("S3_USER", S3Data::USER.data)
                 .env("TLESS_MODE", get_tless_mode())
+                .env("TLESS_RUN_ID", &run_id)
                 .arg(audit_id.to_string())
-                .arg("finra/outputs/fetch-public/trades")
-                .arg("finra/outputs/fetch-private/portfolio")
+                .arg(format!("{outputs_prefix}fetch-public/trades"))
+                .arg(format!("{outputs_prefix}fetch-private/portfolio"))
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit())
                 .output()
@@ -251,7 +292,8 @@ pub fn process_event(mut event: Event) -> Event {
                 .and_then(Value::as_i64)
                 .expect("finra(driver): error: cannot find 'num-audit' in CE");
 
-            let mut count = MERGE_INVOCATION_COUNTER.lock().unwrap();
+            let mut counters = MERGE_INVOCATION_COUNTER.lock().unwrap();
+            let count = counters.entry(run_id.clone()).or_insert(0);
             *count += 1;
             println!("${WORKFLOW_NAME}: counted {}/{}", *count, num_audit);
 
@@ -267,6 +309,7 @@ pub fn process_event(mut event: Event) -> Event {
                     .env("S3_PORT", S3Data::PORT.data)
                     .env("S3_USER", S3Data::USER.data)
                     .env("TLESS_MODE", get_tless_mode())
+                    .env("TLESS_RUN_ID", &run_id)
                     .stdout(Stdio::inherit())
                     .stderr(Stdio::inherit())
                     .output()
@@ -285,9 +328,9 @@ pub fn process_event(mut event: Event) -> Event {
                     }
                 }
 
-                // Reset counter for next (warm) execution
+                // Forget the counter, so that warm executions start from 0
                 println!("${WORKFLOW_NAME}: resetting counter to 0");
-                *count = 0;
+                counters.remove(&run_id);
             }
 
             "done"
@@ -325,7 +368,7 @@ pub fn process_event(mut event: Event) -> Event {
                 scaled_event.set_id((run_magic + i).to_string());
                 scaled_event.set_data(
                     "aplication/json",
-                    json!({"audit-id": i, "num-audit": num_audit}),
+                    json!({"audit-id": i, "num-audit": num_audit, "run-id": run_id}),
                 );
 
                 println!(
@@ -345,7 +388,7 @@ pub fn process_event(mut event: Event) -> Event {
             scaled_event.set_id((run_magic + 0).to_string());
             scaled_event.set_data(
                 "aplication/json",
-                json!({"audit-id": 0, "num-audit": num_audit}),
+                json!({"audit-id": 0, "num-audit": num_audit, "run-id": run_id}),
             );
 
             scaled_event
@@ -388,13 +431,14 @@ async fn main() {
             // we make sure both 'fetch-public' and 'fetch-private' only
             // trigger one job (using the same id), and here we wait for
             // both of them to finish
+            let outputs_prefix = get_outputs_prefix(&get_run_id(&event));
             let keys_to_wait = vec![
-                "finra/outputs/fetch-public/trades",
-                "finra/outputs/fetch-private/portfolio",
+                format!("{outputs_prefix}fetch-public/trades"),
+                format!("{outputs_prefix}fetch-private/portfolio"),
             ];
             for key_to_wait in keys_to_wait {
                 println!("{WORKFLOW_NAME}: audit: waiting for key {key_to_wait}");
-                wait_for_key(key_to_wait).await;
+                wait_for_key(&key_to_wait).await;
             }
 
             let processed_event = process_event(event);
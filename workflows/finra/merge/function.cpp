@@ -12,6 +12,8 @@ extern "C"
 #include "libs/s3/S3Wrapper.hpp"
 #endif
 
+#include "utils.h"
+
 #include <iostream>
 #include <string>
 #include <string_view>
@@ -24,8 +26,13 @@ extern "C"
  */
 int main(int argc, char** argv)
 {
+#ifdef __faasm
+    // We take no input, but we still need our run id
+    tless::utils::getInput();
+#endif
+
     std::string bucketName = "tless";
-    std::string s3prefix = "finra/outputs/audit/audit-";
+    std::string s3prefix = tless::utils::getOutputsPrefix("finra") + "audit/audit-";
 
 #ifndef __faasm
     s3::initS3Wrapper();
@@ -81,7 +88,7 @@ int main(int argc, char** argv)
     }
 
     // Upload merged results
-    std::string key = "finra/outputs/merge/results.txt";
+    std::string key = tless::utils::getOutputsPrefix("finra") + "merge/results.txt";
     std::cout << "finra(merge): uploading merged audit results to "
               << key
               << std::endl;
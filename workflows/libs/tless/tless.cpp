@@ -341,7 +341,7 @@ bool checkChain(const std::string& workflow, const std::string& function, int id
 
     // Attempt to get the exec-token (tolerate missing)
     std::vector<uint8_t> execToken;
-    std::string execTokenKey = workflow + "/exec-tokens/" + function + "-" + std::to_string(id);
+    std::string execTokenKey = tless::utils::getExecTokensPrefix(workflow) + function + "-" + std::to_string(id);
 #ifdef __faasm
     execToken = tless::utils::doGetKeyBytes("tless", execTokenKey, true);
 #else
@@ -385,7 +385,7 @@ int32_t chain(const std::string& workflow,
 {
     if (!on()) {
 #ifdef __faasm
-        return faasmChainNamed(funcName.c_str(), (uint8_t*) inputData.c_str(), inputData.size());
+        return tless::utils::chainNamed(funcName, inputData);
 #else
         return 0;
 #endif
@@ -406,7 +406,7 @@ int32_t chain(const std::string& workflow,
 
 
 #ifdef __faasm
-    return faasmChainNamed(funcName.c_str(), (uint8_t*) inputData.c_str(), inputData.size());
+    return tless::utils::chainNamed(funcName, inputData);
 #else
     return 0;
 #endif
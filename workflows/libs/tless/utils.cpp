@@ -5,10 +5,17 @@ extern "C"
 {
 #include "faasm/host_interface.h"
 }
+#include <faasm/core.h>
 #endif
+#include <cstdlib>
+#include <cstring>
 #include <iomanip>
+#include <optional>
 #include <sstream>
 
+// Separates the run id from the actual input in Faasm function inputs
+#define RUN_ID_SEPARATOR '|'
+
 namespace tless::utils {
 std::string byteArrayToHexString(const uint8_t* data, int dataSize)
 {
@@ -22,6 +29,43 @@ std::string byteArrayToHexString(const uint8_t* data, int dataSize)
     return ss.str();
 }
 
+static std::optional<std::string> currentRunId;
+
+void setRunId(const std::string& runId)
+{
+    currentRunId = runId;
+}
+
+std::string getRunId()
+{
+    if (!currentRunId.has_value()) {
+        char* runIdChar = std::getenv("TLESS_RUN_ID");
+        currentRunId = runIdChar == nullptr ? "" : std::string(runIdChar);
+    }
+
+    return currentRunId.value();
+}
+
+static std::string getRunPrefix(const std::string& workflow, const std::string& dir)
+{
+    std::string runId = getRunId();
+    if (runId.empty()) {
+        return workflow + "/" + dir + "/";
+    }
+
+    return workflow + "/" + dir + "/" + runId + "/";
+}
+
+std::string getOutputsPrefix(const std::string& workflow)
+{
+    return getRunPrefix(workflow, "outputs");
+}
+
+std::string getExecTokensPrefix(const std::string& workflow)
+{
+    return getRunPrefix(workflow, "exec-tokens");
+}
+
 #ifdef __faasm
 std::vector<uint8_t> doGetKeyBytes(const std::string& bucketName, const std::string& key, bool tolerateMissing)
 {
@@ -58,5 +102,27 @@ void doAddKeyBytes(const std::string& bucketName, const std::string& key, const
                                bytes.size(),
                                true);
 }
+
+std::string getInput()
+{
+    int inputSize = faasmGetInputSize();
+    std::string input(inputSize, '\0');
+    faasmGetInput((uint8_t*) input.data(), inputSize);
+
+    size_t pos = input.find(RUN_ID_SEPARATOR);
+    if (pos == std::string::npos) {
+        setRunId("");
+        return input;
+    }
+
+    setRunId(input.substr(0, pos));
+    return input.substr(pos + 1);
+}
+
+int32_t chainNamed(const std::string& funcName, const std::string& input)
+{
+    std::string inputData = getRunId() + RUN_ID_SEPARATOR + input;
+    return faasmChainNamed(funcName.c_str(), (uint8_t*) inputData.c_str(), inputData.size());
+}
 #endif
 }
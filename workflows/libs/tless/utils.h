@@ -1,10 +1,27 @@
 #pragma once
 
 #include <sstream>
+#include <string>
+#include <vector>
 
 namespace tless::utils {
 std::string byteArrayToHexString(const uint8_t* data, int dataSize);
 
+// Executions of the same workflow may overlap, so each execution keeps its
+// intermediate state under the run id that tlessctl assigns to it. In native
+// deployments we read it from the TLESS_RUN_ID env. variable, and in Faasm
+// from our input. Without a run id we use the same keys as before
+void setRunId(const std::string& runId);
+std::string getRunId();
+
+// Prefix (with a trailing slash) for the outputs of the current execution,
+// e.g. finra/outputs/<run-id>/
+std::string getOutputsPrefix(const std::string& workflow);
+
+// Prefix (with a trailing slash) for the execution tokens of the current
+// execution, e.g. finra/exec-tokens/<run-id>/
+std::string getExecTokensPrefix(const std::string& workflow);
+
 #ifdef __faasm
 std::vector<uint8_t> doGetKeyBytes(const std::string& bucketName,
                                    const std::string& key,
@@ -12,5 +29,12 @@ std::vector<uint8_t> doGetKeyBytes(const std::string& bucketName,
 
 void doAddKeyBytes(const std::string& bucketName, const std::string& key, const std::string& bytes);
 void doAddKeyBytes(const std::string& bucketName, const std::string& key, const std::vector<uint8_t>& bytes);
+
+// Function inputs carry the run id of the caller in a header, i.e.
+// <run-id>|<input>. Get our input without the header, and set our run id
+std::string getInput();
+
+// Chain to a function by name, passing our run id along with its input
+int32_t chainNamed(const std::string& funcName, const std::string& input);
 #endif
 }
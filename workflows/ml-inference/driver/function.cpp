@@ -1,6 +1,7 @@
 #ifdef __faasm
 #include <faasm/core.h>
 #endif
+#include "utils.h"
 
 #include <iostream>
 #include <string>
@@ -29,30 +30,32 @@ std::vector<std::string> splitByDelimiter(std::string stringCopy, const std::str
  *
  * As an input, this workflow gets the S3 path to read model data from, the S3
  * path to load the images from and the number of inference functions to run.
+ * Optionally, it also gets the run id to keep its intermediate state under.
  */
 int main(int argc, char** argv)
 {
 #ifdef __faasm
-    if (argc != 4) {
-        std::cout << "ml-infernce(driver): usage: <s3_path_model> <s3_image_data> <num_inf_funcs>"
+    if (argc != 4 && argc != 5) {
+        std::cout << "ml-infernce(driver): usage: <s3_path_model> <s3_image_data> <num_inf_funcs> [<run_id>]"
                   << std::endl;
         return 1;
     }
     std::string s3ModelPrefix = argv[1];
     std::string s3DataPrefix = argv[2];
     int numInfFuncs = std::stoi(argv[3]);
+    tless::utils::setRunId(argc == 5 ? argv[4] : "");
 
     // Invoke one instance of the partition function. It will populate
     // different files in S3 with the images to run inference on for each
     // inference function
     std::cout << "ml-inference(driver): invoking one partition function" << std::endl;
     std::string partitionInput = s3DataPrefix + ":" + std::to_string(numInfFuncs);
-    int partitionId = faasmChainNamed("partition", (uint8_t*) partitionInput.c_str(), partitionInput.size());
+    int partitionId = tless::utils::chainNamed("partition", partitionInput);
 
     // Invoke one instance of the model loading function
     std::cout << "ml-inference(driver): invoking one load function" << std::endl;
     std::string loadInput = s3ModelPrefix;
-    int loadId = faasmChainNamed("load", (uint8_t*) loadInput.c_str(), loadInput.size());
+    int loadId = tless::utils::chainNamed("load", loadInput);
 
     // Wait for both partition and load to finish
     int result = faasmAwaitCall(partitionId);
@@ -77,14 +80,14 @@ int main(int argc, char** argv)
               << " inference functions..."
               << std::endl;
     std::vector<int> inferenceIds(numInfFuncs);
-    std::string loadOutput = "ml-inference/outputs/load";
-    std::string partitionOutput = "ml-inference/outputs/partition/inf-";
+    std::string loadOutput = tless::utils::getOutputsPrefix("ml-inference") + "load";
+    std::string partitionOutput = tless::utils::getOutputsPrefix("ml-inference") + "partition/inf-";
     for (int i = 0; i < numInfFuncs; i++) {
         std::string infInput = std::to_string(i) + ":" + loadOutput + ":" + partitionOutput + std::to_string(i);
         std::cout << "ml-inference(driver): invoking prediction with input "
                   << infInput
                   << std::endl; // DELETE ME
-        int infId = faasmChainNamed("predict", (uint8_t*) infInput.c_str(), infInput.size());
+        int infId = tless::utils::chainNamed("predict", infInput);
         inferenceIds.at(i) = infId;
     }
 
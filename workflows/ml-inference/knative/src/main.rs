@@ -169,6 +169,24 @@ pub fn get_json_from_event(event: &Event) -> Value {
     .unwrap()
 }
 
+// Executions of the same workflow may overlap, so each one keeps its state
+// under the run id that tlessctl gives it. Events without a run id share the
+// same keys
+pub fn get_run_id(event: &Event) -> String {
+    get_json_from_event(event)
+        .get("run-id")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+pub fn get_outputs_prefix(run_id: &str) -> String {
+    match run_id {
+        "" => "ml-inference/outputs/".to_string(),
+        _ => format!("ml-inference/outputs/{run_id}/"),
+    }
+}
+
 // This function is a general wrapper that takes a cloud event as an input,
 // decides what function to execute, and outputs another cloud event
 pub fn process_event(mut event: Event) -> Event {
@@ -176,6 +194,13 @@ pub fn process_event(mut event: Event) -> Event {
     // Pre-process and function invocation
     // -----
 
+    // The cold-start breakdown experiment looks for this line in the logs
+    // to tell when the handler starts
+    println!("tless(handler): handling event {}", event.id());
+
+    let run_id = get_run_id(&event);
+    let outputs_prefix = get_outputs_prefix(&run_id);
+
     event.set_source(match event.source().as_str() {
         "cli-partition" => {
             let func_name = "partition";
@@ -201,6 +226,7 @@ pub fn process_event(mut event: Event) -> Event {
                 .env("S3_PORT", S3Data::PORT.data)
                 .env("S3_USER", S3Data::USER.data)
                 .env("TLESS_MODE", get_tless_mode())
+                .env("TLESS_RUN_ID", &run_id)
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit())
                 .arg(data_dir)
@@ -242,6 +268,7 @@ pub fn process_event(mut event: Event) -> Event {
                 .env("S3_PORT", S3Data::PORT.data)
                 .env("S3_USER", S3Data::USER.data)
                 .env("TLESS_MODE", get_tless_mode())
+                .env("TLESS_RUN_ID", &run_id)
                 .arg(model_dir)
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit())
@@ -283,9 +310,10 @@ pub fn process_event(mut event: Event) -> Event {
                 .env("S3_PORT", S3Data::PORT.data)
                 .env("S3_USER", S3Data::USER.data)
                 .env("TLESS_MODE", get_tless_mode())
+                .env("TLESS_RUN_ID", &run_id)
                 .arg(inf_id.to_string())
-                .arg("ml-inference/outputs/load/rf-")
-                .arg(format!("ml-inference/outputs/partition/inf-{inf_id}"))
+                .arg(format!("{outputs_prefix}load/rf-"))
+                .arg(format!("{outputs_prefix}partition/inf-{inf_id}"))
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit())
                 .output()
@@ -357,6 +385,7 @@ pub fn process_event(mut event: Event) -> Event {
                         "run-magic": run_magic,
                         "model-dir": model_dir,
                         "data-dir": data_dir,
+                        "run-id": run_id,
                     }),
                 );
 
@@ -383,6 +412,7 @@ pub fn process_event(mut event: Event) -> Event {
                     "run-magic": run_magic,
                     "model-dir": model_dir,
                     "data-dir": data_dir,
+                    "run-id": run_id,
                 }),
             );
 
@@ -415,13 +445,14 @@ async fn main() {
             // with the same (source, id) pair (triggering one job) which means
             // that the other one may not have finished yet. To this extent,
             // we wait here for both to finish before executing
+            let outputs_prefix = get_outputs_prefix(&get_run_id(&event));
             let keys_to_wait = vec![
-                "ml-training/outputs/partition/done.txt",
-                "ml-training/outputs/load/done.txt",
+                format!("{outputs_prefix}partition/done.txt"),
+                format!("{outputs_prefix}load/done.txt"),
             ];
             for key_to_wait in keys_to_wait {
                 println!("{WORKFLOW_NAME}: audit: waiting for key {key_to_wait}");
-                wait_for_key(key_to_wait).await;
+                wait_for_key(&key_to_wait).await;
             }
 
             process_event(event.clone());
@@ -434,14 +465,14 @@ async fn main() {
             // After executing the predict function (only JobSink in this
             // workflow) we are done so we need to check if all other jobs have
             // finished, and if so, right to a key letting know we are done
-            let num_keys = get_num_keys("ml-inference/outputs/predict-").await;
+            let num_keys = get_num_keys(&format!("{outputs_prefix}predict-")).await;
 
             println!(
                 "{WORKFLOW_NAME}: queried number of keys (got: {num_keys} - want: {num_inf_funcs})"
             );
             if num_keys == num_inf_funcs {
                 println!("{WORKFLOW_NAME}: done!");
-                add_key_str("ml-inference/outputs/predict/done.txt", "done!").await;
+                add_key_str(&format!("{outputs_prefix}predict/done.txt"), "done!").await;
             }
 
             // We are also done, so we do not need to process the event
@@ -13,6 +13,7 @@ extern "C"
 #endif
 
 #include "tless.h"
+#include "utils.h"
 
 #include <iostream>
 #include <string>
@@ -53,22 +54,13 @@ std::string join(const std::vector<std::string>& stringList, const std::string&
  */
 int main(int argc, char** argv)
 {
-    if (!tless::checkChain("ml-inference", "load", 0)) {
-        std::cerr << "ml-inference(load): error checking TLess chain" << std::endl;
-        return 1;
-    }
-
     // TODO: the bucket name is currently hardcoded
     std::string bucketName = "tless";
     std::string s3prefix;
 
 #ifdef __faasm
     // Get the object key as an input
-    int inputSize = faasmGetInputSize();
-    char inputChar[inputSize];
-    faasmGetInput((uint8_t*)inputChar, inputSize);
-
-    s3prefix.assign(inputChar, inputChar + inputSize);
+    s3prefix = tless::utils::getInput();
 #else
     if (argc != 2) {
         std::cerr << "ml-inference(load): error parsing driver input" << std::endl;
@@ -81,6 +73,12 @@ int main(int argc, char** argv)
     s3::S3Wrapper s3cli;
 #endif
 
+    // We need our input (and run id) to check the chain
+    if (!tless::checkChain("ml-inference", "load", 0)) {
+        std::cerr << "ml-inference(load): error checking TLess chain" << std::endl;
+        return 1;
+    }
+
     // Get the list of files for each PCA function
     std::cout << "ml-inference(load): loading model data from "
               << s3prefix
@@ -131,7 +129,7 @@ int main(int argc, char** argv)
         // Now upload as input for ML inference workflow
         auto fileParts = splitByDelimiter(file, "/");
         auto fileName = fileParts.at(fileParts.size() - 1);
-        std::string key = "ml-inference/outputs/load/" + fileName;
+        std::string key = tless::utils::getOutputsPrefix("ml-inference") + "load/" + fileName;
 #ifdef __faasm
         // Overwrite the results
         ret =
@@ -151,7 +149,7 @@ int main(int argc, char** argv)
 
 #ifndef __faasm
     // Add a file to let know we are done loading
-    s3cli.addKeyStr(bucketName, "ml-inference/outputs/load/done.txt", "done");
+    s3cli.addKeyStr(bucketName, tless::utils::getOutputsPrefix("ml-inference") + "load/done.txt", "done");
     s3::shutdownS3Wrapper();
 #endif
 
@@ -13,6 +13,7 @@ extern "C"
 #endif
 
 #include "tless.h"
+#include "utils.h"
 
 #include <iostream>
 #include <string>
@@ -58,18 +59,9 @@ int main(int argc, char** argv)
     std::string s3dir;
     int numInfFuncs;
 
-    if (!tless::checkChain("ml-inference", "partition", 0)) {
-        std::cerr << "ml-inference(partition): error checking TLess chain" << std::endl;
-        return 1;
-    }
-
 #ifdef __faasm
     // Get the object key as an input
-    int inputSize = faasmGetInputSize();
-    char inputChar[inputSize];
-    faasmGetInput((uint8_t*)inputChar, inputSize);
-
-    std::string tmpStr(inputChar, inputChar + inputSize);
+    std::string tmpStr = tless::utils::getInput();
     auto parts = splitByDelimiter(tmpStr, ":");
     if (parts.size() != 2) {
         std::cerr << "ml-inference(partition): error parsing driver input" << std::endl;
@@ -91,6 +83,12 @@ int main(int argc, char** argv)
     s3::S3Wrapper s3cli;
 #endif
 
+    // We need our input (and run id) to check the chain
+    if (!tless::checkChain("ml-inference", "partition", 0)) {
+        std::cerr << "ml-inference(partition): error checking TLess chain" << std::endl;
+        return 1;
+    }
+
     // Get the list of files for each PCA function
     std::cout << "ml-inference(partition): partitioning "
               << s3dir
@@ -162,7 +160,7 @@ int main(int argc, char** argv)
 
     // Upload one file per calling function
     for (int i = 0; i < numInfFuncs; i++) {
-        std::string key = "ml-inference/outputs/partition/inf-" + std::to_string(i);
+        std::string key = tless::utils::getOutputsPrefix("ml-inference") + "partition/inf-" + std::to_string(i);
 #ifdef __faasm
         // Overwrite the results
         int ret =
@@ -183,7 +181,7 @@ int main(int argc, char** argv)
 
 #ifndef __faasm
     // Add a file to let know we are done partitioning
-    s3cli.addKeyStr(bucketName, "ml-inference/outputs/partition/done.txt", "done");
+    s3cli.addKeyStr(bucketName, tless::utils::getOutputsPrefix("ml-inference") + "partition/done.txt", "done");
     s3::shutdownS3Wrapper();
 #endif
 
@@ -31,6 +31,7 @@ extern "C"
 #endif
 
 #include "tless.h"
+#include "utils.h"
 
 #include <filesystem>
 #include <iostream>
@@ -235,11 +236,7 @@ int main(int argc, char** argv) {
 
 #ifdef __faasm
     // Get the object key as an input
-    int inputSize = faasmGetInputSize();
-    char inputChar[inputSize];
-    faasmGetInput((uint8_t*)inputChar, inputSize);
-
-    std::string tmpStr(inputChar, inputChar + inputSize);
+    std::string tmpStr = tless::utils::getInput();
     auto parts = splitByDelimiter(tmpStr, ":");
     if (parts.size() != 3) {
         std::cerr << "ml-inference(predict): error parsing driver input" << std::endl;
@@ -329,7 +326,7 @@ int main(int argc, char** argv) {
     }
 
     // Upload the serialized results
-    std::string resultsKey = "ml-inference/outputs/" + us;
+    std::string resultsKey = tless::utils::getOutputsPrefix("ml-inference") + us;
 #ifdef __faasm
     int ret =
       __faasm_s3_add_key_bytes(bucketName.c_str(),
@@ -1,6 +1,7 @@
 #ifdef __faasm
 #include <faasm/core.h>
 #endif
+#include "utils.h"
 
 #include <iostream>
 #include <string>
@@ -30,15 +31,17 @@ std::vector<std::string> splitByDelimiter(std::string stringCopy, const std::str
  * As an input, this workflow gets the S3 path read data from, and two numbers:
  * - numPca: the number of PCA analysis to start in parallel.
  * - numRf: the number of random forest trees to store in parallel
+ * Optionally, it also gets the run id to keep its intermediate state under.
  */
 int main(int argc, char** argv)
 {
 #ifdef __faasm
-    if (argc != 4) {
-        printf("ml-training(driver): usage: <s3_path_mnist> <num_pca> <num_rf>\n");
+    if (argc != 4 && argc != 5) {
+        printf("ml-training(driver): usage: <s3_path_mnist> <num_pca> <num_rf> [<run_id>]\n");
         return 1;
     }
     std::string s3prefix = argv[1];
+    tless::utils::setRunId(argc == 5 ? argv[4] : "");
 
     // 1. Invoke one instance of the partition function with an S3 path as an
     // input and the number of PCA component analysis to spawn.
@@ -47,7 +50,7 @@ int main(int argc, char** argv)
     printf("ml-training(driver): invoking one partition function\n");
     // Call splitter
     std::string splitterInput = s3prefix + ":" + argv[2] + ":" + argv[3];
-    int partitionId = faasmChainNamed("partition", (uint8_t*) splitterInput.c_str(), splitterInput.size());
+    int partitionId = tless::utils::chainNamed("partition", splitterInput);
 
     char* partitionOutput;
     int partitionOutputLen;
@@ -93,8 +96,8 @@ int main(int argc, char** argv)
 
     // Finally, invoke one validation function
     printf("ml-training(driver): invoking one validation function\n");
-    std::string validationInput = "ml-training/outputs/rf-";
-    int validationId = faasmChainNamed("validation", (uint8_t*) validationInput.c_str(), validationInput.size());
+    std::string validationInput = tless::utils::getOutputsPrefix("ml-training") + "rf-";
+    int validationId = tless::utils::chainNamed("validation", validationInput);
     result = faasmAwaitCall(validationId);
     if (result != 0) {
         printf("ml-training(driver): error: validation execution (id: %i) failed with rc %i\n",
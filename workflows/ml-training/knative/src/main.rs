@@ -10,6 +10,7 @@ use minio::s3::http::BaseUrl;
 use minio::s3::types::ToStream;
 use once_cell::sync::Lazy;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::process::{Command, Stdio};
 use std::sync::{Arc, Mutex};
 use std::{env, fs, thread, time};
@@ -18,7 +19,9 @@ use uuid::Uuid;
 use warp::Filter;
 
 static BINARY_DIR: &str = "/workflows/build-native/ml-training";
-static MERGE_INVOCATION_COUNTER: Lazy<Arc<Mutex<i64>>> = Lazy::new(|| Arc::new(Mutex::new(0)));
+// Fan-in counter for each execution (by run id) in flight
+static MERGE_INVOCATION_COUNTER: Lazy<Arc<Mutex<HashMap<String, i64>>>> =
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 static WORKFLOW_NAME: &str = "ml-training(driver)";
 
 struct S3Data {
@@ -70,6 +73,24 @@ pub fn get_json_from_event(event: &Event) -> Value {
     .unwrap()
 }
 
+// Executions of the same workflow may overlap, so each one keeps its state
+// under the run id that tlessctl gives it. Events without a run id share the
+// same keys
+pub fn get_run_id(event: &Event) -> String {
+    get_json_from_event(event)
+        .get("run-id")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+pub fn get_outputs_prefix(run_id: &str) -> String {
+    match run_id {
+        "" => "ml-training/outputs/".to_string(),
+        _ => format!("ml-training/outputs/{run_id}/"),
+    }
+}
+
 // This function is a general wrapper that takes a cloud event as an input,
 // decides what function to execute, and outputs another cloud event
 pub fn process_event(mut event: Event) -> Event {
@@ -77,6 +98,13 @@ pub fn process_event(mut event: Event) -> Event {
     // Pre-process and function invocation
     // -----
 
+    // The cold-start breakdown experiment looks for this line in the logs
+    // to tell when the handler starts
+    println!("tless(handler): handling event {}", event.id());
+
+    let run_id = get_run_id(&event);
+    let outputs_prefix = get_outputs_prefix(&run_id);
+
     event.set_source(match event.source().as_str() {
         "cli" => {
             let func_name = "partition";
@@ -107,6 +135,7 @@ pub fn process_event(mut event: Event) -> Event {
                 .env("S3_PORT", S3Data::PORT.data)
                 .env("S3_USER", S3Data::USER.data)
                 .env("TLESS_MODE", get_tless_mode())
+                .env("TLESS_RUN_ID", &run_id)
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit())
                 .arg(data_dir)
@@ -158,8 +187,9 @@ pub fn process_event(mut event: Event) -> Event {
                 .env("S3_PORT", S3Data::PORT.data)
                 .env("S3_USER", S3Data::USER.data)
                 .env("TLESS_MODE", get_tless_mode())
+                .env("TLESS_RUN_ID", &run_id)
                 .arg(pca_id.to_string())
-                .arg(format!("ml-training/outputs/partition/pca-{pca_id}"))
+                .arg(format!("{outputs_prefix}partition/pca-{pca_id}"))
                 .arg(((num_train_funcs / num_pca_funcs) as i64).to_string())
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit())
@@ -206,12 +236,11 @@ pub fn process_event(mut event: Event) -> Event {
                 .env("S3_PORT", S3Data::PORT.data)
                 .env("S3_USER", S3Data::USER.data)
                 .env("TLESS_MODE", get_tless_mode())
+                .env("TLESS_RUN_ID", &run_id)
                 .arg(pca_id.to_string())
                 .arg(rf_id.to_string())
-                .arg(format!("ml-training/outputs/pca-{pca_id}/rf-{rf_id}-data"))
-                .arg(format!(
-                    "ml-training/outputs/pca-{pca_id}/rf-{rf_id}-labels"
-                ))
+                .arg(format!("{outputs_prefix}pca-{pca_id}/rf-{rf_id}-data"))
+                .arg(format!("{outputs_prefix}pca-{pca_id}/rf-{rf_id}-labels"))
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit())
                 .output()
@@ -241,7 +270,8 @@ pub fn process_event(mut event: Event) -> Event {
                 .and_then(Value::as_i64)
                 .expect("ml-training(driver): error: cannot find 'num-audit' in CE");
 
-            let mut count = MERGE_INVOCATION_COUNTER.lock().unwrap();
+            let mut counters = MERGE_INVOCATION_COUNTER.lock().unwrap();
+            let count = counters.entry(run_id.clone()).or_insert(0);
             *count += 1;
             println!("${WORKFLOW_NAME}: counted {}/{}", *count, num_train_funcs);
 
@@ -257,7 +287,8 @@ pub fn process_event(mut event: Event) -> Event {
                     .env("S3_PORT", S3Data::PORT.data)
                     .env("S3_USER", S3Data::USER.data)
                     .env("TLESS_MODE", get_tless_mode())
-                    .arg("ml-training/outputs/rf-")
+                    .env("TLESS_RUN_ID", &run_id)
+                    .arg(format!("{outputs_prefix}rf-"))
                     .stdout(Stdio::inherit())
                     .stderr(Stdio::inherit())
                     .output()
@@ -276,9 +307,9 @@ pub fn process_event(mut event: Event) -> Event {
                     }
                 }
 
-                // Reset counter for next (warm) execution
+                // Forget the counter, so that warm executions start from 0
                 println!("${WORKFLOW_NAME}: resetting counter to 0");
-                *count = 0;
+                counters.remove(&run_id);
             }
 
             "validation"
@@ -321,7 +352,7 @@ pub fn process_event(mut event: Event) -> Event {
                 scaled_event.set_id(Uuid::new_v4().to_string());
                 scaled_event.set_data(
                     "aplication/json",
-                    json!({"pca-id": i, "num-train-funcs": num_train_funcs, "run-magic": run_magic, "num-pca-funcs": num_pca_funcs}),
+                    json!({"pca-id": i, "num-train-funcs": num_train_funcs, "run-magic": run_magic, "num-pca-funcs": num_pca_funcs, "run-id": run_id}),
                 );
 
                 println!(
@@ -337,7 +368,7 @@ pub fn process_event(mut event: Event) -> Event {
             scaled_event.set_id(Uuid::new_v4().to_string());
             scaled_event.set_data(
                 "aplication/json",
-                json!({"pca-id": 0, "num-train-funcs": num_train_funcs, "run-magic": run_magic, "num-pca-funcs": num_pca_funcs}),
+                json!({"pca-id": 0, "num-train-funcs": num_train_funcs, "run-magic": run_magic, "num-pca-funcs": num_pca_funcs, "run-id": run_id}),
             );
 
             scaled_event
@@ -379,7 +410,7 @@ pub fn process_event(mut event: Event) -> Event {
                 scaled_event.set_id(Uuid::new_v4().to_string());
                 scaled_event.set_data(
                     "aplication/json",
-                    json!({"pca-id": pca_id, "rf-id": i, "num-train-funcs": num_train_funcs}),
+                    json!({"pca-id": pca_id, "rf-id": i, "num-train-funcs": num_train_funcs, "run-id": run_id}),
                 );
 
                 println!(
@@ -398,7 +429,7 @@ pub fn process_event(mut event: Event) -> Event {
             scaled_event.set_id(Uuid::new_v4().to_string());
             scaled_event.set_data(
                 "aplication/json",
-                json!({"pca-id": pca_id, "rf-id": 0, "num-train-funcs": num_train_funcs}),
+                json!({"pca-id": pca_id, "rf-id": 0, "num-train-funcs": num_train_funcs, "run-id": run_id}),
             );
 
             scaled_event
@@ -13,6 +13,7 @@ extern "C"
 #endif
 
 #include "tless.h"
+#include "utils.h"
 
 #include <iostream>
 #include <string>
@@ -59,11 +60,6 @@ std::string join(const std::vector<std::string>& stringList, const std::string&
  */
 int main(int argc, char** argv)
 {
-    if (!tless::checkChain("ml-training", "partition", 0)) {
-        std::cerr << "ml-training(partition): error checking TLess chain" << std::endl;
-        return 1;
-    }
-
     // TODO: the bucket name is currently hardcoded
     std::string bucketName = "tless";
     std::string s3dir;
@@ -72,11 +68,7 @@ int main(int argc, char** argv)
 
 #ifdef __faasm
     // Get the object key as an input
-    int inputSize = faasmGetInputSize();
-    char inputChar[inputSize];
-    faasmGetInput((uint8_t*)inputChar, inputSize);
-
-    std::string tmpStr(inputChar, inputChar + inputSize);
+    std::string tmpStr = tless::utils::getInput();
     auto parts = splitByDelimiter(tmpStr, ":");
     if (parts.size() != 3) {
         std::cerr << "ml-training(partition): error parsing driver input" << std::endl;
@@ -100,6 +92,12 @@ int main(int argc, char** argv)
     s3::S3Wrapper s3cli;
 #endif
 
+    // We need our input (and run id) to check the chain
+    if (!tless::checkChain("ml-training", "partition", 0)) {
+        std::cerr << "ml-training(partition): error checking TLess chain" << std::endl;
+        return 1;
+    }
+
     // Get the list of files for each PCA function
     std::cout << "ml-training(partition): partitioning "
               << s3dir
@@ -184,7 +182,7 @@ int main(int argc, char** argv)
 
     // Upload one file per calling function
     for (int i = 0; i < numPcaFuncs; i++) {
-        std::string key = "ml-training/outputs/partition/pca-" + std::to_string(i);
+        std::string key = tless::utils::getOutputsPrefix("ml-training") + "partition/pca-" + std::to_string(i);
 #ifdef __faasm
         std::string_view fileNames(s3filesPtr.at(i));
         // Overwrite the results
@@ -217,7 +215,7 @@ int main(int argc, char** argv)
 
     std::vector<std::string> pcaFuncsIds;
     for (int i = 0; i < numPcaFuncs; i++) {
-        std::string key = "ml-training/outputs/partition/pca-" + std::to_string(i);
+        std::string key = tless::utils::getOutputsPrefix("ml-training") + "partition/pca-" + std::to_string(i);
         std::string pcaInput = std::to_string(i) + ":" + key + ":" + std::to_string(numTrainPerPca);
 #ifdef __faasm
         // int pcaId = faasmChainNamed("pca", (uint8_t*) pcaInput.c_str(), pcaInput.size());
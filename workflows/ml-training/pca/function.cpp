@@ -31,6 +31,7 @@ extern "C"
 #endif
 
 #include "tless.h"
+#include "utils.h"
 
 #include <filesystem>
 #include <iostream>
@@ -198,11 +199,7 @@ int main(int argc, char** argv) {
 
 #ifdef __faasm
     // Get the object key as an input
-    int inputSize = faasmGetInputSize();
-    char inputChar[inputSize];
-    faasmGetInput((uint8_t*)inputChar, inputSize);
-
-    std::string tmpStr(inputChar, inputChar + inputSize);
+    std::string tmpStr = tless::utils::getInput();
     auto parts = splitByDelimiter(tmpStr, ":");
     if (parts.size() != 3) {
         std::cerr << "ml-training(pca): error parsing partition input" << std::endl;
@@ -272,7 +269,7 @@ int main(int argc, char** argv) {
 
     // Upload the serialized results
     for (int i = 0; i < serializedMats.size(); i++) {
-        std::string dataKey = "ml-training/outputs/" + us + "/rf-" + std::to_string(i) + "-data";
+        std::string dataKey = tless::utils::getOutputsPrefix("ml-training") + us + "/rf-" + std::to_string(i) + "-data";
 #ifdef __faasm
         // Overwrite the results
         int ret =
@@ -290,7 +287,7 @@ int main(int argc, char** argv) {
 #endif
 
         // Upload the labels
-        std::string labelsKey = "ml-training/outputs/" + us + "/rf-" + std::to_string(i) + "-labels";
+        std::string labelsKey = tless::utils::getOutputsPrefix("ml-training") + us + "/rf-" + std::to_string(i) + "-labels";
 #ifdef __faasm
         // Overwrite the results
         ret =
@@ -310,8 +307,8 @@ int main(int argc, char** argv) {
 
     std::vector<std::string> trainFuncIds;
     for (int i = 0; i < numTrainFuncs; i++) {
-        std::string dataKey = "ml-training/outputs/" + us + "/rf-" + std::to_string(i) + "-data";
-        std::string labelsKey = "ml-training/outputs/" + us + "/rf-" + std::to_string(i) + "-labels";
+        std::string dataKey = tless::utils::getOutputsPrefix("ml-training") + us + "/rf-" + std::to_string(i) + "-data";
+        std::string labelsKey = tless::utils::getOutputsPrefix("ml-training") + us + "/rf-" + std::to_string(i) + "-labels";
         std::string pcaInput = std::to_string(id) + ":" + std::to_string(i) + ":" + dataKey + ":" + labelsKey;
 #ifdef __faasm
         // int pcaId = faasmChainNamed("rf", (uint8_t*) pcaInput.c_str(), pcaInput.size());
@@ -31,6 +31,7 @@ extern "C"
 #endif
 
 #include "tless.h"
+#include "utils.h"
 
 #include <filesystem>
 #include <iostream>
@@ -90,11 +91,7 @@ int main(int argc, char** argv) {
 
 #ifdef __faasm
     // Get the object key as an input
-    int inputSize = faasmGetInputSize();
-    char inputChar[inputSize];
-    faasmGetInput((uint8_t*)inputChar, inputSize);
-
-    std::string tmpStr(inputChar, inputChar + inputSize);
+    std::string tmpStr = tless::utils::getInput();
     auto parts = splitByDelimiter(tmpStr, ":");
     if (parts.size() != 4) {
         std::cerr << "ml-training(rf): error parsing pca input" << std::endl;
@@ -186,7 +183,7 @@ int main(int argc, char** argv) {
     auto rfData = serializeForest(rf);
 
     // Upload the serialized results
-    std::string modelDataKey = "ml-training/outputs/" + us;
+    std::string modelDataKey = tless::utils::getOutputsPrefix("ml-training") + us;
 #ifdef __faasm
     // Overwrite the results
     ret =
@@ -13,6 +13,7 @@ extern "C"
 #endif
 
 #include "tless.h"
+#include "utils.h"
 
 #include <iostream>
 #include <string>
@@ -63,18 +64,9 @@ int main(int argc, char** argv)
     std::string bucketName = "tless";
     std::string s3prefix;
 
-    if (!tless::checkChain("ml-training", "validation", 0)) {
-        std::cerr << "ml-training(validation): error checking TLess chain" << std::endl;
-        return 1;
-    }
-
 #ifdef __faasm
     // Get the object key as an input
-    int inputSize = faasmGetInputSize();
-    char inputChar[inputSize];
-    faasmGetInput((uint8_t*)inputChar, inputSize);
-
-    s3prefix.assign(inputChar);
+    s3prefix = tless::utils::getInput();
 #else
     if (argc != 2) {
         std::cerr << "ml-training(validation): error parsing driver input" << std::endl;
@@ -86,6 +78,12 @@ int main(int argc, char** argv)
     s3::S3Wrapper s3cli;
 #endif
 
+    // We need our input (and run id) to check the chain
+    if (!tless::checkChain("ml-training", "validation", 0)) {
+        std::cerr << "ml-training(validation): error checking TLess chain" << std::endl;
+        return 1;
+    }
+
     // Get the list of files for each PCA function
     std::cout << "ml-training(validation): validating rf model data from "
               << s3prefix
@@ -154,7 +152,7 @@ int main(int argc, char** argv)
 
     // Add result key that we can wait-on in Knative
     std::string resultsStr = "done!";
-    std::string resultKey = "ml-training/outputs/done.txt";
+    std::string resultKey = tless::utils::getOutputsPrefix("ml-training") + "done.txt";
     std::cout << "ml-training(validation): writting done file to "
               << resultKey
               << std::endl;
@@ -2,6 +2,7 @@
 #include <faasm/core.h>
 #include "tless.h"
 #endif
+#include "utils.h"
 
 #include <iostream>
 #include <stdio.h>
@@ -33,15 +34,17 @@ std::vector<std::string> splitByDelimiter(std::string stringCopy, const std::str
  * In a TLess context, the coordinator can be interpreted as "all the things
  * that could go wrong" during execution of a confidential serverless workflow.
  *
- * As an input, this workflow gets the S3 path read data from.
+ * As an input, this workflow gets the S3 path read data from, and, optionally,
+ * the run id to keep its intermediate state under.
  */
 int main(int argc, char** argv)
 {
-    if (argc != 2) {
-        printf("word-count(driver): error: workflow must be invoked with one parameter: <s3_prefix>\n");
+    if (argc != 2 && argc != 3) {
+        printf("word-count(driver): error: workflow must be invoked with parameters: <s3_prefix> [<run_id>]\n");
         return 1;
     }
     std::string s3prefix = argv[1];
+    tless::utils::setRunId(argc == 3 ? argv[2] : "");
 
     // 1. Invoke one instance of the splitter function with an S3 path as an
     // input. The splitter function will, in turn, traverse the S3 directory
@@ -52,7 +55,7 @@ int main(int argc, char** argv)
     printf("word-count(driver): invoking one splitter function\n");
 #ifdef __faasm
     // Call splitter
-    int splitterId = faasmChainNamed("splitter", (uint8_t*) s3prefix.c_str(), s3prefix.size());
+    int splitterId = tless::utils::chainNamed("splitter", s3prefix);
 #endif
 
 #ifdef __faasm
@@ -87,12 +90,12 @@ int main(int argc, char** argv)
     }
 
     // 3. Invoke one reducer function to aggreagate all results
-    std::string s3result = "word-count/outputs/mapper-";
+    std::string s3result = tless::utils::getOutputsPrefix("word-count") + "mapper-";
     printf("word-count(driver): invoking one reducer function on prefix %s\n",
            s3result.c_str());
 #ifdef __faasm
     // Call reducer and await
-    int reducerId = faasmChainNamed("reducer", (uint8_t*) s3result.c_str(), s3result.size());
+    int reducerId = tless::utils::chainNamed("reducer", s3result);
     // std::tie(result, std::ignore) = tless::wait(reducerId, true);
     result = faasmAwaitCall(reducerId);
     if (result != 0) {
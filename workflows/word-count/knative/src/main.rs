@@ -3,6 +3,7 @@ use cloudevents::binding::warp::{filter, reply};
 use cloudevents::{AttributesReader, AttributesWriter, Event};
 use once_cell::sync::Lazy;
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::process::{Command, Stdio};
 use std::sync::{Arc, Mutex};
 use std::{env, fs, io::BufRead, io::BufReader, thread, time};
@@ -11,7 +12,9 @@ use uuid::Uuid;
 use warp::Filter;
 
 static BINARY_DIR: &str = "/workflows/build-native/word-count";
-static INVOCATION_COUNTER: Lazy<Arc<Mutex<i64>>> = Lazy::new(|| Arc::new(Mutex::new(0)));
+// Fan-in counter for each execution (by run id) in flight
+static INVOCATION_COUNTER: Lazy<Arc<Mutex<HashMap<String, i64>>>> =
+    Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 
 // We must wait for the POST event to go through before we can return, as
 // otherwise the chain may not make progress
@@ -40,6 +43,41 @@ pub fn get_json_from_event(event: &Event) -> Value {
     .unwrap()
 }
 
+// Executions of the same workflow may overlap, so each one keeps its state
+// under the run id that tlessctl gives it. Events without a run id share the
+// same keys
+pub fn get_run_id(event: &Event) -> String {
+    get_json_from_event(event)
+        .get("run-id")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+// Executions may read inputs of different sizes, so tlessctl tells us which
+// one to read. Events without one read the default input
+pub fn get_data_dir(event: &Event) -> String {
+    get_json_from_event(event)
+        .get("data-dir")
+        .and_then(Value::as_str)
+        .unwrap_or("word-count/few-files")
+        .to_string()
+}
+
+pub fn get_outputs_prefix(run_id: &str) -> String {
+    match run_id {
+        "" => "word-count/outputs/".to_string(),
+        _ => format!("word-count/outputs/{run_id}/"),
+    }
+}
+
+pub fn get_splitter_output_file(run_id: &str) -> String {
+    match run_id {
+        "" => format!("{BINARY_DIR}/output_splitter.txt"),
+        _ => format!("{BINARY_DIR}/output_splitter-{run_id}.txt"),
+    }
+}
+
 pub fn get_tless_mode() -> String {
     match env::var("TLESS_MODE") {
         Ok(value) => match value.as_str() {
@@ -57,6 +95,12 @@ pub fn process_event(mut event: Event) -> Event {
     // Pre-process and function invocation
     // -----
 
+    // The cold-start breakdown experiment looks for this line in the logs
+    // to tell when the handler starts
+    println!("tless(handler): handling event {}", event.id());
+
+    let run_id = get_run_id(&event);
+
     event.set_source(match event.source().as_str() {
         "cli" => {
             println!("cloudevent: executing 'splitter' from cli: {event}");
@@ -69,8 +113,9 @@ pub fn process_event(mut event: Event) -> Event {
                 .env("S3_PASSWORD", "minio123")
                 .env("S3_PORT", "9000")
                 .env("S3_USER", "minio")
-                .env("TLESS_S3_DIR", "word-count/few-files")
+                .env("TLESS_S3_DIR", get_data_dir(&event))
                 .env("TLESS_MODE", get_tless_mode())
+                .env("TLESS_RUN_ID", &run_id)
                 .stdout(Stdio::inherit())
                 .stderr(Stdio::inherit())
                 .output()
@@ -102,6 +147,7 @@ pub fn process_event(mut event: Event) -> Event {
                 .env("S3_PORT", "9000")
                 .env("S3_USER", "minio")
                 .env("TLESS_MODE", get_tless_mode())
+                .env("TLESS_RUN_ID", &run_id)
                 .arg(mapper_id.to_string())
                 .arg(s3_file)
                 .stdout(Stdio::inherit())
@@ -121,7 +167,8 @@ pub fn process_event(mut event: Event) -> Event {
 
             // Increment an atomic counter, and only execute the reducer
             // function when all fan-in functions have executed
-            let mut count = INVOCATION_COUNTER.lock().unwrap();
+            let mut counters = INVOCATION_COUNTER.lock().unwrap();
+            let count = counters.entry(run_id.clone()).or_insert(0);
             *count += 1;
             println!("tless(driver): counted {}/{}", *count, fan_out_scale);
 
@@ -139,15 +186,16 @@ pub fn process_event(mut event: Event) -> Event {
                     .env("S3_PORT", "9000")
                     .env("S3_USER", "minio")
                     .env("TLESS_MODE", get_tless_mode())
-                    .arg("word-count/outputs/mapper-")
+                    .env("TLESS_RUN_ID", &run_id)
+                    .arg(format!("{}mapper-", get_outputs_prefix(&run_id)))
                     .stdout(Stdio::inherit())
                     .stderr(Stdio::inherit())
                     .output()
                     .expect("tless(reducer): failed executing command");
 
-                // Reset counter for next (warm) execution
+                // Forget the counter, so that warm executions start from 0
                 println!("tless(reducer): resetting counter to 0");
-                *count = 0;
+                counters.remove(&run_id);
             }
 
             "reducer"
@@ -172,7 +220,7 @@ pub fn process_event(mut event: Event) -> Event {
                 .read(true)
                 .write(true)
                 .create(true)
-                .open(format!("{}/{}", BINARY_DIR, "output_splitter.txt"))
+                .open(get_splitter_output_file(&run_id))
                 .unwrap();
             let reader = BufReader::new(file);
 
@@ -197,7 +245,12 @@ pub fn process_event(mut event: Event) -> Event {
                 scaled_event.set_id(Uuid::new_v4().to_string());
                 scaled_event.set_data(
                     "aplication/json",
-                    json!({"scale-factor": lines.len(), "input-file": lines[i], "mapper-id": i}),
+                    json!({
+                        "scale-factor": lines.len(),
+                        "input-file": lines[i],
+                        "mapper-id": i,
+                        "run-id": run_id,
+                    }),
                 );
 
                 println!(
@@ -216,7 +269,12 @@ pub fn process_event(mut event: Event) -> Event {
             scaled_event.set_id(Uuid::new_v4().to_string());
             scaled_event.set_data(
                 "aplication/json",
-                json!({"scale-factor": lines.len(), "input-file": lines[0], "mapper-id": 0}),
+                json!({
+                    "scale-factor": lines.len(),
+                    "input-file": lines[0],
+                    "mapper-id": 0,
+                    "run-id": run_id,
+                }),
             );
             scaled_event
         }
@@ -10,6 +10,7 @@ extern "C"
 #endif
 
 #include "tless.h"
+#include "utils.h"
 
 #include <iostream>
 #include <map>
@@ -76,11 +77,7 @@ int main(int argc, char** argv)
 
 #ifdef __faasm
     // Get the object key as an input
-    int inputSize = faasmGetInputSize();
-    char inputChar[inputSize];
-    faasmGetInput((uint8_t*)inputChar, inputSize);
-
-    std::string tmpStr(inputChar, inputChar + inputSize);
+    std::string tmpStr = tless::utils::getInput();
     auto parts = splitByDelimiter(tmpStr, ":");
     if (parts.size() != 2) {
         std::cerr << "word-count(mapper): error parsing driver input" << std::endl;
@@ -143,7 +140,7 @@ int main(int argc, char** argv)
 
     // Work-out the serialised payload and directory
     auto thisWordCount = serialiseWordCount();
-    std::string resultsKey = "word-count/outputs/" + us;
+    std::string resultsKey = tless::utils::getOutputsPrefix("word-count") + us;
     std::cout << "word-count(" << us << "): writting result to " << resultsKey << std::endl;
 #ifdef __faasm
     // Overwrite the results key
@@ -10,6 +10,7 @@ extern "C"
 #endif
 
 #include "tless.h"
+#include "utils.h"
 
 #include <iostream>
 #include <map>
@@ -54,21 +55,13 @@ std::string serialiseWordCount(const std::map<std::string, int>& wordCount)
  */
 int main(int argc, char** argv)
 {
-    if (!tless::checkChain("word-count", "reducer", 0)) {
-        std::cerr << "word-count(splitter): error checking TLess chain" << std::endl;
-        return 1;
-    }
-
     // TODO: the bucket name is currently hardcoded
     std::string bucketName = "tless";
     std::string s3dir;
 
 #ifdef __faasm
     // Get the results dir as an input
-    int inputSize = faasmGetInputSize();
-    char s3dirChar[inputSize];
-    faasmGetInput((uint8_t*)s3dirChar, inputSize);
-    s3dir.assign(s3dirChar, s3dirChar + inputSize);
+    s3dir = tless::utils::getInput();
 #else
     if (argc != 2) {
         std::cerr << "word-count(splitter): error: wrong input from driver"
@@ -81,6 +74,12 @@ int main(int argc, char** argv)
     s3::S3Wrapper s3cli;
 #endif
 
+    // We need our input (and run id) to check the chain
+    if (!tless::checkChain("word-count", "reducer", 0)) {
+        std::cerr << "word-count(splitter): error checking TLess chain" << std::endl;
+        return 1;
+    }
+
     // Get the list of files in the s3 dir
     std::vector<std::string> s3files;
 #ifdef __faasm
@@ -137,7 +136,7 @@ int main(int argc, char** argv)
     }
 
     auto resultsStr = serialiseWordCount(results);
-    std::string resultKey = "word-count/outputs/aggregated-results.txt";
+    std::string resultKey = tless::utils::getOutputsPrefix("word-count") + "aggregated-results.txt";
     printf("word-count(reducer): writting results to %s: %s\n", resultKey.c_str(), resultsStr.c_str());
 #ifdef __faasm
     // Overwrite the results key
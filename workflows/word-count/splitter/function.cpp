@@ -12,6 +12,7 @@ extern "C"
 #endif
 
 #include "tless.h"
+#include "utils.h"
 
 #include <iostream>
 #include <stdio.h>
@@ -28,21 +29,13 @@ extern "C"
  */
 int main(int argc, char** argv)
 {
-    if (!tless::checkChain("word-count", "splitter", 0)) {
-        std::cerr << "word-count(splitter): error checking TLess chain" << std::endl;
-        return 1;
-    }
-
     // TODO: the bucket name is currently hardcoded
     std::string bucketName = "tless";
     std::string s3dir;
 
 #ifdef __faasm
     // Get the object key as an input
-    int inputSize = faasmGetInputSize();
-    char s3dirChar[inputSize];
-    faasmGetInput((uint8_t*)s3dirChar, inputSize);
-    s3dir.assign(s3dirChar, s3dirChar + inputSize);
+    s3dir = tless::utils::getInput();
 #else
     s3::initS3Wrapper();
 
@@ -61,6 +54,12 @@ int main(int argc, char** argv)
     s3dir.assign(s3dirChar);
 #endif
 
+    // We need our input (and run id) to check the chain
+    if (!tless::checkChain("word-count", "splitter", 0)) {
+        std::cerr << "word-count(splitter): error checking TLess chain" << std::endl;
+        return 1;
+    }
+
     // Get the list of files in the s3 dir
     std::vector<std::string> s3files;
 
@@ -97,8 +96,10 @@ int main(int argc, char** argv)
     // able to wait on it
     std::vector<int> splitterCallIds;
 #ifndef __faasm
-    // For Knative, we write the output to a file
-    std::ofstream outfile("./output_splitter.txt");
+    // For Knative, we write the output to a file. Executions may overlap
+    // in the same pod, so each one writes to its own file
+    std::string runId = tless::utils::getRunId();
+    std::ofstream outfile(runId.empty() ? "./output_splitter.txt" : "./output_splitter-" + runId + ".txt");
     assert(outfile.is_open());
 #endif
     for (int i = 0; i < s3files.size(); i++) {